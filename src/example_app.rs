@@ -1,63 +1,127 @@
-use std::time::SystemTime;
+use std::{path::PathBuf, time::Duration};
 
-use windows::Win32::UI::{Input::KeyboardAndMouse::VK_RETURN, WindowsAndMessaging::CS_OWNDC};
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RETURN;
 
-use crate::window::Window;
+use win32_boilerplate::{
+    loc,
+    window::{
+        builder::WindowBuilder,
+        coords::LogicalSize,
+        drag_drop::DragDropEvent,
+        error::WindowError,
+        event::{Event, WindowEvent},
+        game_loop::{Game, GameLoop, GameLoopConfig},
+        keyboard::{KeyEvent, KeyState},
+        placement_store::PlacementStore,
+        timer::TimerId,
+        Window,
+    },
+};
 
-pub struct ExampleApp<'a> {
-    pub window: Window<'a>,
+pub struct ExampleApp {
+    pub window: Window,
     input_buffer: String,
-    time_buffer: SystemTime,
+    seconds_alive: u64,
+    second_timer: TimerId,
+    /// The files that were dropped on the window, in the order they arrived.
+    dropped_files: Vec<PathBuf>,
 }
 
-impl ExampleApp<'_> {
-    pub fn create() -> ExampleApp<'static> {
-        let app = ExampleApp {
-            window: Window::new("Example App", CS_OWNDC, 1000, 750),
+impl ExampleApp {
+    pub fn create() -> ExampleApp {
+        let window: Window = WindowBuilder::new("Example App")
+            .class_name("ExampleApp")
+            .inner_size(LogicalSize::new(1000.0, 750.0))
+            .min_size(LogicalSize::new(320.0, 240.0))
+            .centered()
+            // Reopen where the app was closed last time
+            .placement_store(PlacementStore::in_config_dir("Win32ExampleApp"))
+            .accept_files(true)
+            .build()
+            .unwrap_or_else(|err| {
+                WindowError::new(&err.to_string(), None, loc!());
+            });
+
+        let second_timer: TimerId = window.timers().repeat(Duration::from_secs(1));
+
+        let app: ExampleApp = ExampleApp {
+            window,
             input_buffer: String::new(),
-            time_buffer: SystemTime::now(),
+            seconds_alive: 0,
+            second_timer,
+            dropped_files: vec![],
         };
-        app.window.show_window();
+        app.update_title();
         return app;
     }
 
     pub fn launch(&mut self) -> usize {
-        let mut exit_code: Option<usize>;
-        loop {
-            exit_code = self.window.handle_messages();
-            if exit_code.is_some() {
-                break;
-            }
-            self.render_frame();
-        }
-        return exit_code.unwrap();
+        // The app doesn't need exact frame times, so the loop only sleeps between frames instead of spinning
+        let mut game_loop: GameLoop = GameLoop::new(GameLoopConfig {
+            spin_margin: Duration::ZERO,
+            ..GameLoopConfig::DEFAULT
+        });
+        let exit_code: usize = game_loop.run(self);
+        println!(
+            "{:.1} fps, p99 frame time {:?}",
+            game_loop.stats().fps(),
+            game_loop.stats().p99_frame_time()
+        );
+        return exit_code;
     }
 
-    pub fn render_frame(&mut self) {
-        // A test to check if the window updates even if there are no events:
-        let time_alive: std::time::Duration =
-            SystemTime::now().duration_since(self.time_buffer).unwrap();
-
-        let elapsed_time: windows::core::PCSTR = windows::core::PCSTR::from_raw(
-            format!(
-                "{},{}s\0",
-                time_alive.as_secs(),
-                time_alive.as_millis() % 1000
-            )
-            .as_ptr(),
-        );
-        unsafe {
-            windows::Win32::UI::WindowsAndMessaging::SetWindowTextA(self.window.hwnd, elapsed_time)
-        };
-        std::thread::sleep(std::time::Duration::from_millis(1));
-        // App logic
-        if let Some(ch) = self.window.keyboard.read_char() {
-            self.input_buffer.push(ch);
+    fn update_title(&self) {
+        let mut title: String = format!("Example App - {}s", self.seconds_alive);
+        if !self.dropped_files.is_empty() {
+            title.push_str(&format!(" - {} dropped files", self.dropped_files.len()));
         }
+        // A format! result never contains a NUL character
+        self.window.set_title(&title).unwrap();
+    }
 
-        if self.window.keyboard.key_is_pressed_pop(VK_RETURN.0) {
-            println!("{:?}", self.input_buffer);
-            self.input_buffer = "".to_string();
+    fn handle_events(&mut self) {
+        for event in self.window.poll_events() {
+            match event {
+                Event::Char(ch) => self.input_buffer.push(ch),
+                Event::Key(KeyEvent {
+                    key_state: KeyState::Press,
+                    key_code,
+                }) if key_code == VK_RETURN.0 as u32 => {
+                    println!("{:?}", self.input_buffer);
+                    self.input_buffer = "".to_string();
+                }
+                // This is where an editor would ask "save changes?" and skip close() to veto
+                Event::Window(WindowEvent::CloseRequested) => {
+                    if let Err(error) = self.window.close() {
+                        eprintln!("Unable to save the window placement: {}", error);
+                    }
+                }
+                // A test to check if the window updates even if there are no events
+                Event::Timer(id) if id == self.second_timer => {
+                    self.seconds_alive += 1;
+                    self.update_title();
+                }
+                Event::Window(event) => println!("{:?}", event),
+                Event::DragDrop(DragDropEvent::DroppedFiles { paths, .. }) => {
+                    self.dropped_files.extend(paths);
+                    self.update_title();
+                }
+                _ => {}
+            }
         }
     }
 }
+
+impl Game for ExampleApp {
+    fn window(&mut self) -> &mut Window {
+        return &mut self.window;
+    }
+
+    fn update(&mut self, _dt: Duration) {
+        self.handle_events();
+    }
+
+    fn render(&mut self, _alpha: f64) {
+        // Nothing to draw yet, a game would hand its frame to Window::present() here
+    }
+}
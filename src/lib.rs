@@ -0,0 +1,4 @@
+// Every function ends with an explicit return, that is the style of this code base
+#![allow(clippy::needless_return)]
+
+pub mod window;
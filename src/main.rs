@@ -1,9 +1,15 @@
+// Every function ends with an explicit return, that is the style of this code base
+#![allow(clippy::needless_return)]
+
 use example_app::ExampleApp;
+use win32_boilerplate::window;
 
 mod example_app;
-mod window;
 
 fn main() {
+    // render sharp on monitors with a scale above 100%
+    window::enable_dpi_awareness();
+
     // create an app
     let mut app: ExampleApp = example_app::ExampleApp::create();
 
@@ -1,439 +1,888 @@
-use crate::loc;
-use windows::{
-    core::{PCSTR, PSTR},
-    s,
-    Win32::{
-        Foundation::{GetLastError, BOOL, HINSTANCE, HWND, LPARAM, LRESULT, POINTS, WPARAM},
-        Graphics::Gdi::HBRUSH,
-        System::{
-            Diagnostics::Debug::{
-                FormatMessageA, FORMAT_MESSAGE_ALLOCATE_BUFFER, FORMAT_MESSAGE_FROM_SYSTEM,
-            },
-            LibraryLoader::GetModuleHandleA,
-        },
-        UI::{
-            Input::KeyboardAndMouse::{ReleaseCapture, SetCapture},
-            WindowsAndMessaging::{
-                CreateWindowExA, DefWindowProcA, DestroyWindow, DispatchMessageA, LoadCursorW,
-                MessageBoxExA, PeekMessageA, PostQuitMessage, RegisterClassExA, ShowWindow,
-                TranslateMessage, HICON, IDC_ARROW, MESSAGEBOX_RESULT, MESSAGEBOX_STYLE, MSG,
-                PM_REMOVE, WM_CHAR, WM_CLOSE, WM_DESTROY, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS,
-                WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE,
-                WM_MOUSEWHEEL, WM_QUIT, WM_RBUTTONDOWN, WM_RBUTTONUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
-                WNDCLASSEXA, WNDCLASS_STYLES, WS_CAPTION, WS_MINIMIZEBOX, WS_SYSMENU,
-            },
-        },
-    },
+#[cfg(windows)]
+use windows::Win32::Foundation::HWND;
+use windows::Win32::{
+    Foundation::{LPARAM, POINTS, WPARAM},
+    UI::WindowsAndMessaging::{WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASS_STYLES, WS_CHILD, WS_POPUP},
 };
 
-use self::{keyboard::Keyboard, mouse::Mouse};
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    io,
+    path::PathBuf,
+    rc::{Rc, Weak},
+    sync::mpsc::{channel, Receiver, Sender},
+    time::{Duration, Instant},
+};
 
+use self::{
+    backend::{Backend, CursorIcon},
+    builder::{WindowBuilder, WindowPosition},
+    chrome::{CaptionButton, CaptionButtonState, ChromeLayout, HitTest},
+    clipboard::{ClipboardFormat, ClipboardImage, ClipboardItem},
+    control_flow::ControlFlow,
+    coords::{ClientPoint, LogicalSize, PhysicalPoint, PhysicalSize},
+    dialog::{DialogResult, FileDialog, MessageBox},
+    error::{BuildError, ModeError},
+    event::{Event, WindowEvent},
+    gesture::GestureRecognizer,
+    keyboard::Keyboard,
+    manager::{WindowId, WindowRelation},
+    menu::{AcceleratorTable, Menu, MenuId},
+    monitor::{Monitor, Placement, WindowMode},
+    mouse::Mouse,
+    placement_store::{PlacementStore, SavedPlacement},
+    proxy::EventLoopProxy,
+    recording::{InputRecorder, Recording, Replayer},
+    rect::Rect,
+    state::WindowState,
+    timer::{TimerId, Timers},
+    wide::NulError,
+};
+
+pub mod backend;
+pub mod builder;
+pub mod chrome;
+pub mod clipboard;
+pub mod control_flow;
+pub mod coords;
+pub mod dialog;
+pub mod drag_drop;
 pub mod error;
+pub mod event;
+pub mod game_loop;
+pub mod gesture;
 pub mod keyboard;
+pub mod manager;
+pub mod menu;
 pub mod message;
+pub mod monitor;
 pub mod mouse;
+pub mod placement_store;
+pub mod proxy;
+pub mod recording;
+pub mod rect;
+pub mod state;
+pub mod timer;
+pub mod translate;
+pub mod wide;
 
 /**
-    We need some public variables for the wndproc because we can't pass in any other arguments in that function.<br>
-    I know public variables are bad but i haven't seen a solution to use variables in [`self::wndproc()`].
-*/
-pub mod io {
-    use super::keyboard::Keyboard;
-    use super::mouse::Mouse;
-
-    /// The Mouse state
-    pub static mut MOUSE: Mouse = Mouse {
-        x: 0,
-        y: 0,
-        event_queue: vec![],
-        left_pressed: false,
-        right_pressed: false,
-        is_in_window: false,
-        wheel_pressed: false,
-        wheel_delta_carry: 0,
-    };
+    Opt in to per-monitor DPI awareness for this process with the [current](backend::current()) backend. Call it
+    before the first window is created. Without it windows are scaled up by the system on monitors above 100%,
+    which makes them blurry.
 
-    /// The keyboard state   
-    pub static mut KEYBOARD: Keyboard = Keyboard {
-        key_states: vec![],
-        key_queue: vec![],
-        char_queue: vec![],
-        auto_repeat_enabled: false,
-    };
-
-    /// Width of the window
-    pub static mut MAX_MOUSE_X: i16 = 0;
-    /// Height of the window
-    pub static mut MAX_MOUSE_Y: i16 = 0;
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/hidpi/setting-the-default-dpi-awareness-for-a-process
+*/
+pub fn enable_dpi_awareness() -> bool {
+    return backend::current().enable_dpi_awareness();
 }
 
 /// The Window class which holds every recieved windowEvent and the window data.
-pub struct Window<'a> {
-    pub instance: HINSTANCE,
-    pub class_name: PCSTR,
-    pub atom: u16,
-    pub width: i16,
-    pub height: i16,
-    pub class: WNDCLASSEXA,
-    pub hwnd: HWND,
-    pub msg_buffer: MSG,
-    pub last_result: BOOL,
-    pub keyboard: &'a mut Keyboard,
-    pub mouse: &'a mut Mouse,
+/// `T` is the payload of [Event::User], see [Window::create_proxy()].
+pub struct Window<T = ()> {
+    pub id: WindowId,
+    /// The exit code of the last WM_QUIT, see [Window::handle_messages()].
+    exit_code: Option<usize>,
+    control_flow: ControlFlow,
+    state: Rc<RefCell<WindowState>>,
+    backend: Rc<dyn Backend>,
+    user_sender: Sender<T>,
+    user_events: Receiver<T>,
+    timers: Rc<RefCell<Timers>>,
+    /// Saves the placement on [Window::close()], see [WindowBuilder::placement_store()].
+    placement_store: Option<PlacementStore>,
 }
 
-/// Create a message box
-pub fn create_message_box(
-    lptext: PCSTR,
-    utype: MESSAGEBOX_STYLE,
-    wlanguageid: u16,
-) -> MESSAGEBOX_RESULT {
-    let lpcaption: PCSTR = match utype {
-        MESSAGEBOX_STYLE(16) => {
-            s!("Fatal error")
-        }
-        _ => {
-            s!("Warning")
-        }
-    };
+/// The timers of a window and the state their events go to.
+struct TimerOwner {
+    timers: Weak<RefCell<Timers>>,
+    state: Weak<RefCell<WindowState>>,
+}
 
-    return unsafe { MessageBoxExA(HWND::default(), lptext, lpcaption, utype, wlanguageid) };
-    /*
-        Creates, displays, and operates a message box. The message box contains an application-defined message and title, plus any
-        combination of predefined icons and push buttons. The buttons are in the language of the system user interface.
+thread_local! {
+    /// Every window of this thread. Any window pumps the messages of the whole thread, so it fires all the timers.
+    static TIMER_OWNERS: RefCell<Vec<TimerOwner>> = const { RefCell::new(Vec::new()) };
+}
 
-        For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxexa
-    */
+/// The timers of a window that is still alive and its state.
+type LiveTimerOwner = (Rc<RefCell<Timers>>, Rc<RefCell<WindowState>>);
+
+/// The timers and state of the windows of this thread which weren't dropped yet.
+fn timer_owners() -> Vec<LiveTimerOwner> {
+    return TIMER_OWNERS.with(|owners| {
+        let mut owners: RefMut<Vec<TimerOwner>> = owners.borrow_mut();
+        owners.retain(|owner| owner.timers.strong_count() > 0 && owner.state.strong_count() > 0);
+        return owners
+            .iter()
+            .filter_map(|owner| Some((owner.timers.upgrade()?, owner.state.upgrade()?)))
+            .collect();
+    });
 }
 
-impl Window<'_> {
-    /// Create a window instance
+impl Window {
+    /// Create a hidden top level window instance at (200, 200). Use [WindowBuilder] for more options.
     pub fn new(
         window_name: &str,
         style: WNDCLASS_STYLES,
         window_width: i16,
         window_height: i16,
-    ) -> Window<'static> {
-        let base_details: String = window_name.to_string();
-        let class_name: PCSTR = PCSTR::from_raw(base_details.as_ptr());
+    ) -> Result<Window, BuildError> {
+        return WindowBuilder::new(window_name)
+            .class_name(window_name)
+            .class_style(style)
+            .position(PhysicalPoint::new(200, 200))
+            .outer_size(LogicalSize::new(window_width.into(), window_height.into()))
+            .resizable(false)
+            .visible(false)
+            .build();
+    }
+}
 
+impl<T> Window<T> {
+    /// Create the window described by a validated [WindowBuilder]. See [WindowBuilder::build_with()].
+    fn create(builder: &WindowBuilder, backend: Rc<dyn Backend>) -> Result<Window<T>, BuildError> {
+        let state: Rc<RefCell<WindowState>> = Rc::new(RefCell::new(WindowState::new(0, 0)));
+        // The backend needs them for the frame size
+        {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            state.menu = builder.menu.clone();
+            state.chrome = builder.chrome.clone();
+        }
+
+        let restore: Option<(Rect, bool)> = match (&builder.placement_store, builder.relation) {
+            (_, WindowRelation::Child(_)) | (None, _) => None,
+            (Some(store), _) => store
+                .load()
+                .map(|saved| (saved.restore_rect(&backend.monitors()), saved.maximized)),
+        };
         /*
-            hInstance is the handle to an instance or handle to a module. The
-            operating system uses this value to identify the executable or EXE
-            when it's loaded in memory.
+            Open at the saved position so the window gets the DPI of the right monitor. A restored window is only
+            shown after it has its saved size, unless it is maximized: the size doesn't matter then, and showing
+            it maximized right away keeps the saved rect as the rect it is restored to.
         */
-        let instance: HINSTANCE = unsafe {
-            GetModuleHandleA(None).unwrap_or_else(|_| {
-                error::WindowError::new(
-                    "Unable to create an hInstance with GetModuleHandle.",
-                    None,
-                    loc!(),
-                );
-            })
+        let mut create_builder: WindowBuilder = builder.clone();
+        if let (Some((rect, maximized)), false) = (restore, builder.fullscreen) {
+            create_builder.position =
+                WindowPosition::At(PhysicalPoint::new(rect.left, rect.top).into());
+            create_builder.maximized = maximized;
+            create_builder.visible = builder.visible && maximized;
+        }
+        let id: WindowId = backend.create_window(&create_builder, &state)?;
+
+        if let Some((rect, maximized)) = restore {
+            if builder.fullscreen {
+                // Stay fullscreen, but go back to the saved placement when leaving it
+                if let Some(windowed) = state.borrow_mut().windowed_placement.as_mut() {
+                    windowed.rect = rect;
+                    windowed.maximized = maximized;
+                }
+            } else {
+                let (style, ex_style): (WINDOW_STYLE, WINDOW_EX_STYLE) = {
+                    let state: Ref<WindowState> = state.borrow();
+                    (state.style, state.ex_style)
+                };
+                let placement: Placement = Placement {
+                    rect,
+                    maximized,
+                    style,
+                    ex_style,
+                };
+                backend.set_placement(id, &placement);
+                if builder.visible && !maximized {
+                    backend.set_visible(id, true);
+                }
+            }
+        }
+
+        let (user_sender, user_events): (Sender<T>, Receiver<T>) = channel();
+        let timers: Rc<RefCell<Timers>> = Rc::new(RefCell::new(Timers::default()));
+        TIMER_OWNERS.with(|owners| {
+            owners.borrow_mut().push(TimerOwner {
+                timers: Rc::downgrade(&timers),
+                state: Rc::downgrade(&state),
+            });
+        });
+
+        // return the new Window instance
+        return Ok(Window {
+            id,
+            exit_code: None,
+            control_flow: ControlFlow::Poll,
+            state,
+            backend,
+            user_sender,
+            user_events,
+            timers,
+            placement_store: builder.placement_store.clone(),
+        });
+    }
+
+    /// The native handle of the window. [None] for windows of another backend or after the window is destroyed.
+    #[cfg(windows)]
+    pub fn hwnd(&self) -> Option<HWND> {
+        return manager::with_manager(|manager| manager.hwnd(self.id));
+    }
+
+    /// Quit the app when this window closes, even if other windows are still open.
+    pub fn set_main_window(&self) {
+        self.backend.set_main_window(Some(self.id));
+    }
+
+    /**
+        Destroy the window. Call this after a [WindowEvent::CloseRequested] to accept it. The placement is saved
+        first when the window has a [PlacementStore]. The window is destroyed even when saving fails, the error is
+        returned so the app can report it.
+    */
+    pub fn close(&self) -> io::Result<()> {
+        if self.is_destroyed() {
+            return Ok(());
+        }
+        let saved: io::Result<()> = match (&self.placement_store, self.saved_placement()) {
+            (Some(store), Some(placement)) => store.save(&placement),
+            _ => Ok(()),
         };
+        self.backend.destroy_window(self.id);
+        return saved;
+    }
 
-        /*
-            Contains window class information. It is used with the RegisterClassEx
-            and GetClassInfoEx functions.
-            For more info about the fields of this class:
-            https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-wndclassexa
-        */
-        let class: WNDCLASSEXA = WNDCLASSEXA {
-            cbSize: std::mem::size_of::<WNDCLASSEXA>() as u32,
-            style,
-            lpfnWndProc: Some(Self::wndproc),
-            hInstance: instance,
-            hCursor: unsafe {
-                LoadCursorW(None, IDC_ARROW).unwrap_or_else(|_| {
-                    error::WindowError::new("Unable to load cursor.", None, loc!());
-                })
-            },
-            lpszClassName: class_name,
-            cbClsExtra: 0,
-            cbWndExtra: 0,
-            hIcon: HICON(0isize as _),
-            hbrBackground: HBRUSH(0isize as _),
-            lpszMenuName: PCSTR(0isize as _),
-            hIconSm: HICON(0isize as _),
+    /// The placement a [PlacementStore] would save right now. A fullscreen window gives its windowed placement.
+    pub fn saved_placement(&self) -> Option<SavedPlacement> {
+        let windowed: Option<Placement> = self.state.borrow().windowed_placement;
+        let placement: Placement = match self.window_mode() {
+            WindowMode::Windowed => self.backend.placement(self.id)?,
+            _ => windowed?,
         };
+        return Some(SavedPlacement::new(&placement, &self.backend.monitors()));
+    }
 
-        /*
-            If you register the window class by using RegisterClassExA, the application tells the system that
-            the windows of the created class expect messages with text or character parameters to use the ANSI
-            character set.
+    /// True after the window received WM_DESTROY.
+    pub fn is_destroyed(&self) -> bool {
+        return self.state.borrow().destroyed;
+    }
 
-            If the function succeeds, the return value is a class atom that uniquely identifies the class being
-            registered. If the function fails, the return value is zero.
+    /// Change the text in the title bar. Fails if the title contains a NUL character.
+    pub fn set_title(&self, title: &str) -> Result<(), NulError> {
+        return self.backend.set_title(self.id, title);
+    }
 
-            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerclassexa
-        */
-        let atom: u16 = unsafe { RegisterClassExA(&class) };
+    /// The current size of the client area.
+    pub fn client_size(&self) -> (i32, i32) {
+        let state: RefMut<WindowState> = self.state.borrow_mut();
+        return (state.client_width, state.client_height);
+    }
 
-        if atom == 0 {
-            // check if the registerClass function failed
-            panic!("unable to register class");
-        }
+    /// The size of the client area in physical pixels, the size of the buffer you render to.
+    pub fn inner_size(&self) -> PhysicalSize {
+        let (width, height): (i32, i32) = self.client_size();
+        return PhysicalSize::new(width, height);
+    }
 
-        /*
-            Creates an overlapped, pop-up, or child window. It specifies the window class, window title, window
-            style, and (optionally) the initial position and size of the window. The function also specifies
-            the window's parent or owner, if any, and the window's menu.
+    /// The size of the client area in logical pixels, the size to lay out the UI in.
+    pub fn logical_inner_size(&self) -> LogicalSize {
+        return self.inner_size().to_logical(self.scale_factor());
+    }
 
-            If the function succeeds, the return value is a handle to the new window. If the function fails, the
-            return value is NULL. We can get the error info by calling GetLastError. See GetExitCodes().
+    /**
+        The scale factor of the monitor the window is on, 1.0 is 96 DPI. Always 1.0 unless
+        [enable_dpi_awareness()] was called, windows scales the whole window as a bitmap then.
+        [WindowEvent::ScaleFactorChanged] tells you when it changes.
+    */
+    pub fn scale_factor(&self) -> f64 {
+        return self.state.borrow().scale_factor();
+    }
 
-            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-createwindowexa
-        */
-        let hwnd: HWND = unsafe {
-            CreateWindowExA(
-                windows::Win32::UI::WindowsAndMessaging::WINDOW_EX_STYLE(0),
-                class_name,
-                class_name,
-                WS_CAPTION | WS_MINIMIZEBOX | WS_SYSMENU,
-                200,
-                200,
-                window_width.into(),
-                window_height.into(),
-                None,
-                None,
-                instance,
-                None,
-            )
-        };
+    /// Read the oldest [WindowEvent] and remove it from the queue.
+    pub fn read_window_event(&self) -> Option<WindowEvent> {
+        let mut state: RefMut<WindowState> = self.state.borrow_mut();
+        if !state.window_event_queue.is_empty() {
+            return Some(state.window_event_queue.remove(0));
+        }
+        return None;
+    }
 
-        unsafe {
-            io::KEYBOARD.reset();
-            io::MOUSE.reset();
-            io::MAX_MOUSE_X = window_width;
-            io::MAX_MOUSE_Y = window_height;
+    /**
+        Iterate over all the events that arrived since the last call, in the order the messages arrived. Call
+        [Window::handle_messages()] first to dispatch the pending messages.
+        ```ignore
+        for event in window.poll_events() {
+            match event {
+                Event::Char(char) => buffer.push(char),
+                Event::Window(WindowEvent::CloseRequested) => window.close()?,
+                _ => {}
+            }
+        }
+        ```
+        The state is only borrowed while taking the next event, so it is fine to call [Window::close()] or other
+        functions of the window inside the loop.
+    */
+    pub fn poll_events(&self) -> PollEvents<'_, T> {
+        return PollEvents {
+            state: &self.state,
+            user_events: &self.user_events,
         };
+    }
 
-        // return the new Window instance
-        Window {
-            instance,
-            class_name,
-            atom,
-            class,
-            hwnd,
-            msg_buffer: MSG::default(),
-            last_result: BOOL::default(),
-            keyboard: unsafe { &mut io::KEYBOARD },
-            mouse: unsafe { &mut io::MOUSE },
-            width: window_width,
-            height: window_height,
+    /**
+        A handle to send [Event::User] payloads to this window from other threads. Build the window with
+        [WindowBuilder::build_for()] to choose the payload type. Every proxy stops working when the window is
+        closed.
+    */
+    pub fn create_proxy(&self) -> EventLoopProxy<T> {
+        return EventLoopProxy::new(
+            self.user_sender.clone(),
+            self.backend.create_waker(self.id),
+            self.state.borrow().closed.clone(),
+        );
+    }
+
+    /**
+        Handle a made up window message as if the wndproc got it and return the events it produced. The events are
+        also added to [Window::poll_events()]. Nothing is sent to windows, so this works with every backend and is
+        meant for reproducing input bugs:
+        ```ignore
+        // The second WM_KEYDOWN has the auto-repeat bit (30) set
+        window.inject(WM_KEYDOWN, WPARAM(VK_A.0 as usize), LPARAM(0));
+        let events: Vec<Event> = window.inject(WM_KEYDOWN, WPARAM(VK_A.0 as usize), LPARAM(1 << 30));
+        ```
+        WM_MOUSEWHEEL and the pointer messages use the client origin from the last WM_MOVE and the current
+        [Backend::message_time()] as their time. WM_MOVE doesn't change the origin of a child window, its position
+        is relative to the parent.
+    */
+    pub fn inject(&self, msg: u32, wparam: WPARAM, lparam: LPARAM) -> Vec<Event> {
+        let mut state: RefMut<WindowState> = self.state.borrow_mut();
+        state.message_time = self.backend.message_time();
+        let events: Vec<Event> = translate::translate(msg, wparam, lparam, &mut state);
+        for event in events.iter() {
+            state.push_event(event.clone());
         }
+        return events;
+    }
+
+    /**
+        The keyboard state of this window. The guard borrows the state of the window, drop it before
+        [Window::handle_messages()] or any call that can send a message to the window. Messages that arrive while
+        it is held are not tracked.
+    */
+    pub fn keyboard(&self) -> RefMut<'_, Keyboard> {
+        return RefMut::map(self.state.borrow_mut(), |state| &mut state.keyboard);
+    }
+
+    /// The mouse state of this window. Don't keep the guard around, see [Window::keyboard()].
+    pub fn mouse(&self) -> RefMut<'_, Mouse> {
+        return RefMut::map(self.state.borrow_mut(), |state| &mut state.mouse);
+    }
+
+    /// The touch gestures of this window. Don't keep the guard around, see [Window::keyboard()].
+    pub fn gestures(&self) -> RefMut<'_, GestureRecognizer> {
+        return RefMut::map(self.state.borrow_mut(), |state| &mut state.gestures);
     }
 
     pub fn show_window(&self) {
-        // Sets the specified window's show state.
-        // Check for more info: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-showwindow
-        unsafe {
-            ShowWindow(
-                self.hwnd,
-                windows::Win32::UI::WindowsAndMessaging::SHOW_WINDOW_CMD(1),
-            );
-        };
+        self.backend.set_visible(self.id, true);
     }
 
-    pub fn handle_messages(&mut self) -> Option<usize> {
-        while unsafe { PeekMessageA(&mut self.msg_buffer, None, 0, 0, PM_REMOVE).as_bool() } {
-            if self.msg_buffer.message == WM_QUIT {
-                return Some(self.msg_buffer.wParam.0);
+    pub fn hide_window(&self) {
+        self.backend.set_visible(self.id, false);
+    }
+
+    /// The monitors of the desktop.
+    pub fn available_monitors(&self) -> Vec<Monitor> {
+        return self.backend.monitors();
+    }
+
+    /// The monitor that has the largest part of the window.
+    pub fn current_monitor(&self) -> Option<Monitor> {
+        let placement: Placement = self.backend.placement(self.id)?;
+        let monitors: Vec<Monitor> = self.backend.monitors();
+        if let Some(id) = self.window_mode().monitor() {
+            return monitor::find_monitor(&monitors, id).cloned();
+        }
+        return monitor::monitor_from_rect(&monitors, placement.rect).cloned();
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        return self.state.borrow().window_mode;
+    }
+
+    /**
+        Move the window to another monitor. A windowed window keeps its offset from the top-left corner of the work
+        area, a fullscreen window covers the new monitor instead.
+    */
+    pub fn move_to_monitor(&self, monitor: &Monitor) -> Result<(), ModeError> {
+        match self.window_mode() {
+            WindowMode::Windowed => {}
+            WindowMode::BorderlessFullscreen(_) => {
+                return self.set_window_mode(WindowMode::BorderlessFullscreen(monitor.id));
+            }
+            WindowMode::ExclusiveFullscreen(_, video_mode) => {
+                return self
+                    .set_window_mode(WindowMode::ExclusiveFullscreen(monitor.id, video_mode));
             }
-            unsafe { TranslateMessage(&mut self.msg_buffer) };
-            unsafe { DispatchMessageA(&mut self.msg_buffer) };
         }
 
-        return None;
+        let monitors: Vec<Monitor> = self.backend.monitors();
+        let to: &Monitor =
+            monitor::find_monitor(&monitors, monitor.id).ok_or(ModeError::UnknownMonitor)?;
+        let placement: Placement = match self.backend.placement(self.id) {
+            Some(placement) => placement,
+            None => return Ok(()),
+        };
+        let from: &Monitor = match monitor::monitor_from_rect(&monitors, placement.rect) {
+            Some(from) => from,
+            None => return Ok(()),
+        };
+        self.backend.set_placement(
+            self.id,
+            &Placement {
+                rect: monitor::move_to_monitor(placement.rect, from, to),
+                ..placement
+            },
+        );
+        return Ok(());
     }
 
-    extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-        /*
-            It is very hard to explain how this works without typing a lot of text so i'll just refer you to
-            the great video by ChiliTomatoNoodle (https://youtu.be/UUbXK4G_NCM). It explains how the window
-            messages work and how to build a good system around it.
+    /**
+        Switch between windowed, borderless fullscreen and exclusive fullscreen. The placement of the window is saved
+        when it leaves [WindowMode::Windowed] and restored when it comes back, maximized state included:
+        ```ignore
+        let monitor: Monitor = window.current_monitor().unwrap();
+        window.set_window_mode(WindowMode::BorderlessFullscreen(monitor.id))?;
+        // ...
+        window.set_window_mode(WindowMode::Windowed)?;
+        ```
+        On an error the window keeps the mode it had.
+    */
+    pub fn set_window_mode(&self, mode: WindowMode) -> Result<(), ModeError> {
+        let current: WindowMode = self.window_mode();
+        if mode == current {
+            return Ok(());
+        }
+        if mode != WindowMode::Windowed && self.state.borrow().style.contains(WS_CHILD) {
+            return Err(ModeError::ChildWindow);
+        }
 
-            For more info about wndproc see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nc-winuser-wndproc
-            And for a list with all the messages see: https://wiki.winehq.org/List_Of_Windows_Messages
-        */
+        let mut monitors: Vec<Monitor> = self.backend.monitors();
+        if let Some(id) = mode.monitor() {
+            monitor::find_monitor(&monitors, id).ok_or(ModeError::UnknownMonitor)?;
+        }
 
-        unsafe {
-            match msg {
-                // General window messages
-                WM_KILLFOCUS => {
-                    io::KEYBOARD.reset();
-                }
-                WM_CLOSE => {
-                    println!("WM_CLOSE");
-                    DestroyWindow(hwnd);
-                }
-                WM_DESTROY => {
-                    println!("WM_DESTROY");
-                    PostQuitMessage(0);
+        // Leave the video mode of the old monitor before taking another one
+        if let WindowMode::ExclusiveFullscreen(old_monitor, _) = current {
+            self.backend.set_video_mode(old_monitor, None);
+        }
+        if let WindowMode::ExclusiveFullscreen(id, video_mode) = mode {
+            if !self.backend.set_video_mode(id, Some(video_mode)) {
+                if let WindowMode::ExclusiveFullscreen(old_monitor, old_mode) = current {
+                    self.backend.set_video_mode(old_monitor, Some(old_mode));
                 }
+                return Err(ModeError::VideoModeRejected);
+            }
+            // The bounds of the monitor changed with the mode
+            monitors = self.backend.monitors();
+        }
 
-                // Keyboard messages
-                WM_CHAR => {
-                    io::KEYBOARD.on_char(wparam.0 as u32);
-                }
-                WM_KEYDOWN | WM_SYSKEYDOWN => {
-                    // See https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#keystroke-message-flags
-                    let auto_repeat: bool = (lparam.0 >> 30) & 1 == 1;
+        if current == WindowMode::Windowed {
+            let placement: Option<Placement> = self.backend.placement(self.id);
+            self.state.borrow_mut().windowed_placement = placement;
+        }
+        // Set before the placement, so the events of the move already see the new mode
+        self.state.borrow_mut().window_mode = mode;
 
-                    if auto_repeat {
-                        io::KEYBOARD.enable_auto_repeat();
-                    }
-                    io::KEYBOARD.on_key_press(wparam.0 as u32);
-                }
-                WM_KEYUP | WM_SYSKEYUP => {
-                    io::KEYBOARD.disable_auto_repeat();
-                    io::KEYBOARD.on_key_release(wparam.0 as u32);
-                }
+        let placement: Option<Placement> = match mode.monitor() {
+            Some(id) => {
+                let monitor: &Monitor = monitor::find_monitor(&monitors, id).unwrap();
+                let ex_style: WINDOW_EX_STYLE = self.state.borrow().ex_style;
+                Some(Placement {
+                    rect: monitor.bounds,
+                    maximized: false,
+                    style: WS_POPUP,
+                    ex_style,
+                })
+            }
+            None => self.state.borrow_mut().windowed_placement.take(),
+        };
+        if let Some(placement) = placement {
+            self.backend.set_placement(self.id, &placement);
+        }
+        return Ok(());
+    }
 
-                // Mouse messages
-                WM_MOUSEMOVE => {
-                    let points: POINTS = make_points(lparam);
-                    if points.x >= 0
-                        && points.x <= io::MAX_MOUSE_X
-                        && points.y >= 0
-                        && points.y <= io::MAX_MOUSE_Y
-                    {
-                        io::MOUSE.on_mouse_move(points);
-
-                        if !io::MOUSE.is_in_window {
-                            SetCapture(hwnd);
-                            io::MOUSE.on_mouse_enter();
-                        }
-                    } else {
-                        const MK_LBUTTON: i32 = 0x0001;
-                        const MK_MBUTTON: i32 = 0x0010;
-                        const MK_RBUTTON: i32 = 0x0002;
-
-                        if (wparam.0 as i32) & (MK_LBUTTON | MK_MBUTTON | MK_RBUTTON) > 0 {
-                            io::MOUSE.on_mouse_move(points);
-                        } else {
-                            ReleaseCapture();
-                            io::MOUSE.on_mouse_leave();
-                        }
-                    }
-                }
-                WM_LBUTTONDOWN => {
-                    io::MOUSE.on_left_press();
-                }
-                WM_LBUTTONUP => {
-                    io::MOUSE.on_left_release();
-                }
-                WM_RBUTTONUP => {
-                    io::MOUSE.on_right_release();
-                }
-                WM_RBUTTONDOWN => {
-                    io::MOUSE.on_right_press();
-                }
+    /// The cursor shown while the mouse is over the client area.
+    pub fn set_cursor_icon(&self, icon: CursorIcon) {
+        self.backend.set_cursor_icon(self.id, icon);
+    }
 
-                WM_MBUTTONDOWN => {
-                    io::MOUSE.on_wheel_press();
-                }
-                WM_MBUTTONUP => {
-                    io::MOUSE.on_wheel_release();
-                }
+    /// Show or hide the mouse cursor of the whole app.
+    pub fn set_cursor_visible(&self, visible: bool) {
+        self.backend.set_cursor_visible(visible);
+    }
 
-                WM_MOUSEWHEEL => {
-                    let points: POINTS = make_points(lparam);
-                    let delta: i16 = get_wheel_delta_wparam(wparam);
-                    io::MOUSE.on_wheel_delta(points.x, points.y, delta);
-                    println!("{}", io::MOUSE.event_queue.len());
-                    println!("{:?}\n", io::MOUSE.event_queue);
-                }
+    /**
+        Replace the menu bar, [None] removes it. The client area keeps its size. Picking an item or pressing its
+        accelerator sends [Event::MenuActivated]. Child windows can't have a menu bar, this does nothing for them.
+    */
+    pub fn set_menu(&self, menu: Option<Menu>) {
+        if self.state.borrow().style.contains(WS_CHILD) {
+            return;
+        }
+        self.state.borrow_mut().menu = menu.clone();
+        self.backend.set_menu(self.id, menu.as_ref());
+    }
+
+    /// A copy of the menu bar, with the enabled and checked state of its items.
+    pub fn menu(&self) -> Option<Menu> {
+        return self.state.borrow().menu.clone();
+    }
+
+    /**
+        The menu shown when the user right clicks the client area or presses Shift+F10, [None] for no menu. Its
+        accelerators work like the ones of the menu bar.
+    */
+    pub fn set_context_menu(&self, menu: Option<Menu>) {
+        self.state.borrow_mut().context_menu = menu;
+    }
+
+    /// Enable or disable an item of the menu bar and the context menu. Returns false when neither has the item.
+    pub fn set_menu_item_enabled(&self, item: MenuId, enabled: bool) -> bool {
+        return self.update_menus(|menu| menu.set_enabled(item, enabled));
+    }
+
+    /**
+        Check or uncheck an item of the menu bar and the context menu. Checking a radio item unchecks the rest of
+        its group. Returns false when neither menu has the item with a check mark.
+    */
+    pub fn set_menu_item_checked(&self, item: MenuId, checked: bool) -> bool {
+        return self.update_menus(|menu| menu.set_checked(item, checked));
+    }
+
+    /// Shortcuts that aren't in a menu. They send [Event::MenuActivated] just like the menu items do.
+    pub fn set_accelerators(&self, accelerators: AcceleratorTable) {
+        self.state.borrow_mut().accelerators = accelerators;
+    }
+
+    /**
+        Show a popup menu at `position` and wait until it is closed. Returns the item the user picked, [None] when
+        the menu was closed without picking one. No [Event::MenuActivated] is sent for it.
+    */
+    pub fn show_context_menu(&self, menu: &Menu, position: ClientPoint) -> Option<MenuId> {
+        return self.backend.show_popup_menu(self.id, menu, position);
+    }
+
+    /**
+        Show a message box owned by this window and wait until it is closed. The window doesn't get input while
+        the box is open.
+    */
+    pub fn show_message_box(&self, message_box: MessageBox) -> DialogResult {
+        return self.backend.show_message_box(&message_box.owner(self.id));
+    }
+
+    /// Show a file dialog owned by this window. The picked path, [None] when the user cancelled.
+    pub fn show_file_dialog(&self, dialog: FileDialog) -> Option<PathBuf> {
+        return self
+            .backend
+            .show_file_dialog(&dialog.owner(self.id))
+            .into_iter()
+            .next();
+    }
+
+    /// Show an open dialog owned by this window where the user can pick more than one file. Empty when cancelled.
+    pub fn show_file_dialog_multi(&self, dialog: FileDialog) -> Vec<PathBuf> {
+        return self
+            .backend
+            .show_file_dialog(&dialog.owner(self.id).multi_select(true));
+    }
+
+    /// Change the menu bar and the context menu with `f`, and give the backend the new menu bar.
+    fn update_menus(&self, f: impl Fn(&mut Menu) -> bool) -> bool {
+        let (found_in_bar, found_in_context): (bool, bool) = {
+            let mut state: RefMut<WindowState> = self.state.borrow_mut();
+            (
+                state.menu.as_mut().is_some_and(&f),
+                state.context_menu.as_mut().is_some_and(&f),
+            )
+        };
+        if found_in_bar {
+            let menu: Option<Menu> = self.menu();
+            self.backend.set_menu(self.id, menu.as_ref());
+        }
+        return found_in_bar || found_in_context;
+    }
 
-                _ => {
-                    return DefWindowProcA(hwnd, msg, wparam, lparam);
+    /**
+        Replace the title bar drawn by the app, [None] goes back to the native title bar and borders. The window keeps
+        its outer size, so the client area grows or shrinks by the frame. Child windows can't have a custom chrome,
+        this does nothing for them.
+    */
+    pub fn set_chrome(&self, chrome: Option<ChromeLayout>) {
+        {
+            let mut state: RefMut<WindowState> = self.state.borrow_mut();
+            if state.style.contains(WS_CHILD) {
+                return;
+            }
+            state.chrome = chrome.clone();
+            if state.chrome.is_none() {
+                if let Some(event) = state.update_caption_buttons(CaptionButtonState::on_leave) {
+                    state.push_window_event(event);
                 }
             }
-            LRESULT(0)
         }
+        self.backend.set_chrome(self.id, chrome.as_ref());
     }
 
-    pub fn print_exit_codes(&self) {
-        return println!("{}", self.get_error_desc());
+    pub fn chrome(&self) -> Option<ChromeLayout> {
+        return self.state.borrow().chrome.clone();
     }
 
-    pub fn get_error_desc(&self) -> String {
-        let err_code: u32 = unsafe { GetLastError().0 }; // Get the last WIN32_ERROR and get the id from it (u32)
-        let mut err_buffer: *mut u8 = std::ptr::null_mut(); // Create a buffer for windows where it should store the error message
-        if err_code == 0 {
-            // If the error code == 0, there is no error. So there is no need for priting a succes error :)
-            return format!(
-                "Succesfull exit with codes: last getResult: {:?}, wParam: {}",
-                self.last_result.0, self.msg_buffer.wParam.0
-            );
-        }
+    /// Which part of the window a point in client coordinates is in, what the window answers to WM_NCHITTEST.
+    pub fn hit_test(&self, point: ClientPoint) -> HitTest {
+        return self.state.borrow().hit_test(point);
+    }
 
-        let err_msg_lenght: u32 = unsafe {
-            FormatMessageA(
-                /*
-                    Formats a message string. The function requires a message definition as input.
-
-                    The function finds the message definition in a message table resource based on
-                    a message identifier (HRESULT/GetLastError()) and a language identifier (LCID). The function copies the
-                    formatted message text to an output buffer, processing any embedded insert
-                    sequences if requested.
-
-                    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-formatmessage
-                */
-                FORMAT_MESSAGE_FROM_SYSTEM | // Use system message tables to retrieve error text
-                FORMAT_MESSAGE_ALLOCATE_BUFFER, // Allocate buffer on local heap for error text
-                None, // Location of the message definition. We use the systems error table so it has to be None
-                err_code, // The Errorcode you want a description about
-                0,    // LCID (language code identifier) ->
-                /*
-                This one is a bit weird. In the description the FormatMessage
-                function it says we need an LANGID but there is nothing like that in the windows crate. This crate uses a LCID.
-                0 means that it will use your system languague. 1033 means US.
-                For more info see: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-lcid/70feba9f-294e-491e-b6eb-56532684c37f
-                */
-                PSTR(&mut err_buffer as *mut _ as *mut _), // Man... this took me ages to get working. ->
-                /*
-                A pointer to a buffer that receives the null-terminated string that specifies the formatted message.
-                    This buffer cannot be larger than 64K bytes.
-                    ---
-                    We first create a mutable null pointer and set the type to a u8 like this:
-                    let mut err_buffer: *mut u8 = std::ptr::null_mut();
-                    Then we use the PSTR constructor to create a pointer to a null-terminated string of 8-bit Windows (ANSI) characters.
-                    like this:
-                        PSTR();
-                    Then we put in a mutable reference to the error_buffer and cast it to an mutable pointer (I have no clue how and why this works)
-                        PSTR(&mut err_buffer as *mut _ as *mut _);
-                */
-                0,
-                /*
-                If the FORMAT_MESSAGE_ALLOCATE_BUFFER flag is not set, this parameter specifies the size of the output buffer, in TCHARs. If
-                FORMAT_MESSAGE_ALLOCATE_BUFFER is set, this parameter specifies the minimum number of TCHARs to allocate for an output buffer.
-                */
-                None, /*
-                          An array of values that are used as insert values in the formatted message.
-                          For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-formatmessage#parameters
-                      */
-            )
+    /// The hover and pressed state of the caption buttons of the chrome, to draw them.
+    pub fn caption_buttons(&self) -> CaptionButtonState {
+        return self.state.borrow().caption_buttons;
+    }
+
+    /**
+        Do what a click on a caption button does. The window does this itself for the buttons of the chrome, this is
+        for buttons the app handles on its own, like a maximize entry in a menu.
+    */
+    pub fn click_caption_button(&self, button: CaptionButton) {
+        self.backend.caption_command(self.id, button);
+    }
+
+    pub fn clipboard_text(&self) -> Option<String> {
+        return self.backend.clipboard_text();
+    }
+
+    /// Returns false when the clipboard is in use by another app.
+    pub fn set_clipboard_text(&self, text: &str) -> bool {
+        return self
+            .backend
+            .set_clipboard(&[ClipboardItem::Text(text.to_string())]);
+    }
+
+    /// The image on the clipboard, also when another app copied it as a bitmap without alpha.
+    pub fn clipboard_image(&self) -> Option<ClipboardImage> {
+        return self.backend.clipboard_image();
+    }
+
+    /// Returns false when the clipboard is in use by another app.
+    pub fn set_clipboard_image(&self, image: &ClipboardImage) -> bool {
+        return self
+            .backend
+            .set_clipboard(&[ClipboardItem::Image(image.clone())]);
+    }
+
+    /**
+        Register a format for data of this app, like a selection of shapes. Use a name that is unlikely to clash with
+        other apps, apps that register the same name get the same format:
+        ```ignore
+        let format: ClipboardFormat = window.register_clipboard_format("ExampleApp.Shapes").unwrap();
+        window.set_clipboard(&[
+            ClipboardItem::Data(format.clone(), shapes.to_bytes()),
+            ClipboardItem::Text(shapes.describe()),
+        ]);
+        ```
+    */
+    pub fn register_clipboard_format(&self, name: &str) -> Option<ClipboardFormat> {
+        return self.backend.register_clipboard_format(name);
+    }
+
+    pub fn clipboard_data(&self, format: &ClipboardFormat) -> Option<Vec<u8>> {
+        return self.backend.clipboard_data(format);
+    }
+
+    /// Returns false when the clipboard is in use by another app.
+    pub fn set_clipboard_data(&self, format: &ClipboardFormat, bytes: &[u8]) -> bool {
+        return self
+            .backend
+            .set_clipboard(&[ClipboardItem::Data(format.clone(), bytes.to_vec())]);
+    }
+
+    /// Put several formats of the same contents on the clipboard at once, the app that pastes picks one.
+    pub fn set_clipboard(&self, items: &[ClipboardItem]) -> bool {
+        return self.backend.set_clipboard(items);
+    }
+
+    /// Get a [WindowEvent::ClipboardChanged] every time the clipboard changes, or stop getting it. Returns false
+    /// when the window already was (or wasn't) listening.
+    pub fn set_clipboard_listener(&self, listen: bool) -> bool {
+        return self.backend.listen_clipboard(self.id, listen);
+    }
+
+    /// Show a frame of 0x00RRGGBB pixels in the client area. It is stretched when it isn't the size of the client
+    /// area.
+    pub fn present(&self, width: u32, height: u32, pixels: &[u32]) {
+        self.backend.present(self.id, width, height, pixels);
+    }
+
+    /// The backend this window was created with.
+    pub fn backend(&self) -> &Rc<dyn Backend> {
+        return &self.backend;
+    }
+
+    /**
+        Dispatch the messages of all the windows of this thread. Every message ends up in the state of the
+        window it belongs to, so it doesn't matter which window you call this on.<br>
+        When there are no messages this blocks according to the [ControlFlow] of the window, see
+        [Window::set_control_flow()]. Returns the exit code when WM_QUIT was received.
+    */
+    pub fn handle_messages(&mut self) -> Option<usize> {
+        if let Some(recorder) = self.state.borrow_mut().recorder.as_mut() {
+            recorder.begin_frame(Instant::now());
+        }
+        // Events which were sent while creating the window or by the app itself are already waiting
+        let control_flow: ControlFlow = if self.state.borrow().events.is_empty() {
+            self.control_flow
+        } else {
+            ControlFlow::Poll
         };
+        // The timers can run on another clock, so wait for the time that is left instead of the deadline itself
+        let owners: Vec<LiveTimerOwner> = timer_owners();
+        let remaining: Option<Duration> = owners
+            .iter()
+            .filter_map(|(timers, _)| {
+                let mut timers: RefMut<Timers> = timers.borrow_mut();
+                let deadline: Instant = timers.next_deadline()?;
+                return Some(deadline.saturating_duration_since(timers.now()));
+            })
+            .min();
+        let control_flow: ControlFlow = match remaining {
+            Some(remaining) => control_flow.until(Instant::now() + remaining),
+            None => control_flow,
+        };
+        let exit_code: Option<usize> = self.backend.pump_events(control_flow);
+        if exit_code.is_some() {
+            self.exit_code = exit_code;
+        }
+
+        // A finger that stays still sends no messages, check for a long-press every pump
+        let now: u32 = self.backend.message_time();
+        for (timers, state) in owners {
+            let due: Vec<TimerId> = timers.borrow_mut().fire_due();
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            for id in due {
+                state.push_event(Event::Timer(id));
+            }
+            state.gestures.update(now);
+            for gesture in state.gestures.take_new_events() {
+                state.push_event(Event::Gesture(gesture));
+            }
+        }
+        return exit_code;
+    }
+
+    /**
+        Record every event of this window from now on, a frame per [Window::handle_messages()]. Use this to let
+        testers capture a bug, save the result of [Window::stop_recording()] and play it back with
+        [Window::replay_frame()]. Starting again throws away the current recording.
+    */
+    pub fn start_recording(&self) {
+        self.state.borrow_mut().recorder = Some(InputRecorder::new(Instant::now()));
+    }
+
+    /// Stop recording and return what was recorded. [None] when the window wasn't recording.
+    pub fn stop_recording(&self) -> Option<Recording> {
+        return self
+            .state
+            .borrow_mut()
+            .recorder
+            .take()
+            .map(InputRecorder::finish);
+    }
 
-        if err_msg_lenght == 0 {
-            /*
-                If the message buffer is empty, there is no available error description
-                Could be caused by an invalid error code or an invalid or not correctly installed LCID
-            */
+    pub fn is_recording(&self) -> bool {
+        return self.state.borrow().recorder.is_some();
+    }
 
-            return format!("Code: {}: Unable to find error description", err_code);
+    /**
+        Dispatch the messages like [Window::handle_messages()] and then feed the next frame of `replayer` into the
+        event stream when it is due. The events go through the same state updates as real input, so
+        [Window::keyboard()] and [Window::mouse()] follow the replay.<br>
+        Real input still arrives during a replay, use the [headless](backend::headless::HeadlessBackend) backend
+        when only the recording should be seen. Returns the exit code when WM_QUIT was received.
+    */
+    pub fn replay_frame(&mut self, replayer: &mut Replayer) -> Option<usize> {
+        let exit_code: Option<usize> = self.handle_messages();
+        if let Some(frame) = replayer.advance(Instant::now()) {
+            let mut state: RefMut<WindowState> = self.state.borrow_mut();
+            for event in frame.events.iter() {
+                state.apply_event(event.clone());
+            }
         }
+        return exit_code;
+    }
 
-        /*
-            If there is an error, print all the return codes,
-            println!("Unsuccesfull exit with codes getResult: {:?}, wParam: {}, lastError: {}", self.last_result.0, self.msg_buffer.wParam.0, unsafe { GetLastError().0 });
-            and print out the description of the code
-        */
-        let slice: Vec<u8> =
-            unsafe { std::slice::from_raw_parts(err_buffer, (err_msg_lenght - 2) as _).to_vec() };
+    /// Choose what [Window::handle_messages()] does when there are no messages. The default is [ControlFlow::Poll].
+    pub fn set_control_flow(&mut self, control_flow: ControlFlow) {
+        self.control_flow = control_flow;
+    }
+
+    pub fn control_flow(&self) -> ControlFlow {
+        return self.control_flow;
+    }
+
+    /**
+        The timers of this window. They fire during [Window::handle_messages()] of any window of the thread, the
+        events end up at this window. See [Timers].
+    */
+    pub fn timers(&self) -> RefMut<'_, Timers> {
+        return self.timers.borrow_mut();
+    }
+
+    /**
+        Ask for a [WindowEvent::RedrawRequested]. On Win32 this invalidates the client area so windows sends a
+        WM_PAINT, which also wakes a loop that is blocked in [ControlFlow::Wait]. Calling it more than once before
+        the next frame still gives one event.
+    */
+    pub fn request_redraw(&self) {
+        if self.is_destroyed() {
+            return;
+        }
+        self.backend.request_redraw(self.id);
+    }
+
+    pub fn print_exit_codes(&self) {
+        return println!("{}", self.get_error_desc());
+    }
+
+    pub fn get_error_desc(&self) -> String {
+        return match self.backend.last_error() {
+            Some(description) => description,
+            None => format!(
+                "Succesfull exit with codes: exit code: {}",
+                self.exit_code.unwrap_or(0)
+            ),
+        };
+    }
+}
+
+/**
+    The iterator returned by [Window::poll_events()]. It ends when there are no events left. The user events come
+    after the window events, they are sent from other threads so they have no place among the messages.
+*/
+pub struct PollEvents<'a, T> {
+    state: &'a RefCell<WindowState>,
+    user_events: &'a Receiver<T>,
+}
 
-        return format!("Code {}: {}", err_code, String::from_utf8(slice).unwrap());
+impl<T> Iterator for PollEvents<'_, T> {
+    type Item = Event<T>;
+
+    fn next(&mut self) -> Option<Event<T>> {
+        loop {
+            let event: Option<Event> = self.state.borrow_mut().events.pop_front();
+            match event {
+                Some(event) => {
+                    // An Event::User of the state has no payload, skip it
+                    if let Some(event) = event.with_user_type() {
+                        return Some(event);
+                    }
+                }
+                None => return self.user_events.try_recv().ok().map(Event::User),
+            }
+        }
     }
 }
 
@@ -461,3 +910,860 @@ pub fn get_wheel_delta_wparam(wparam: WPARAM) -> i16 {
 
     return delta;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::headless::fake_monitor;
+    use backend::headless::HeadlessBackend;
+    use drag_drop::DragDropEvent;
+    use error::EventLoopClosed;
+    use gesture::{GestureEvent, GestureKind, GesturePhase};
+    use keyboard::{KeyEvent, KeyState};
+    use monitor::{MonitorId, VideoMode};
+    use mouse::{MouseEvent, MouseState};
+    use timer::ManualClock;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CS_DBLCLKS, WM_CHAR, WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_MOUSEMOVE, WM_POINTERDOWN,
+        WM_POINTERUP, WM_SIZE,
+    };
+
+    fn headless_window(backend: &Rc<HeadlessBackend>) -> Window {
+        let window: Window = WindowBuilder::new("Test")
+            .build_with(backend.clone())
+            .unwrap();
+        return window;
+    }
+
+    /// A window that gets numbers from an [EventLoopProxy].
+    fn proxy_window(backend: &Rc<HeadlessBackend>) -> Window<u32> {
+        let mut window: Window<u32> =
+            Window::create(&WindowBuilder::new("Test"), backend.clone()).unwrap();
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+        return window;
+    }
+
+    fn gestures(window: &Window) -> Vec<GestureEvent> {
+        return window
+            .poll_events()
+            .filter_map(|event| match event {
+                Event::Gesture(gesture) => Some(gesture),
+                _ => None,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn long_press_fires_without_pointer_messages() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        // Pointer 1 goes down at (40, 30) and then stays still
+        window.inject(WM_POINTERDOWN, WPARAM(1), LPARAM(30 << 16 | 40));
+        backend.advance_message_time(200);
+        window.handle_messages();
+        assert!(gestures(&window).is_empty());
+
+        backend.advance_message_time(300);
+        window.handle_messages();
+        let events: Vec<GestureEvent> = gestures(&window);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, GestureKind::LongPress);
+        assert_eq!(events[0].phase, GesturePhase::Begin);
+        assert_eq!((events[0].x, events[0].y), (40.0, 30.0));
+
+        window.inject(WM_POINTERUP, WPARAM(1), LPARAM(30 << 16 | 40));
+        let events: Vec<GestureEvent> = gestures(&window);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].phase, GesturePhase::End);
+    }
+
+    #[test]
+    fn injected_key_codes_out_of_range_are_ignored() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        for key_state in [KeyState::Press, KeyState::Release] {
+            backend.inject(
+                window.id,
+                Event::Key(KeyEvent {
+                    key_state,
+                    key_code: 300,
+                }),
+            );
+        }
+        window.handle_messages();
+        assert_eq!(window.poll_events().count(), 0);
+        assert!(window.keyboard().key_queue.is_empty());
+    }
+
+    #[test]
+    fn every_window_fires_its_timers() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut first: Window = headless_window(&backend);
+        let second: Window = headless_window(&backend);
+        let clock: ManualClock = ManualClock::new();
+        first.timers().set_clock(Rc::new(clock.clone()));
+        second.timers().set_clock(Rc::new(clock.clone()));
+        let first_timer: TimerId = first.timers().once(Duration::from_millis(10));
+        let second_timer: TimerId = second.timers().once(Duration::from_millis(20));
+        first.handle_messages();
+        first.poll_events().for_each(drop);
+        second.poll_events().for_each(drop);
+
+        // Only the first window pumps, the events still go to the window of the timer
+        clock.advance(Duration::from_millis(20));
+        first.handle_messages();
+        let first_events: Vec<Event> = first.poll_events().collect();
+        let second_events: Vec<Event> = second.poll_events().collect();
+        assert_eq!(first_events, vec![Event::Timer(first_timer)]);
+        assert_eq!(second_events, vec![Event::Timer(second_timer)]);
+
+        // A dropped window doesn't keep its timers running
+        let third: Window = headless_window(&backend);
+        third.timers().set_clock(Rc::new(clock.clone()));
+        third.timers().once(Duration::from_millis(5));
+        drop(third);
+        clock.advance(Duration::from_millis(5));
+        first.handle_messages();
+        assert!(first
+            .poll_events()
+            .all(|event| !matches!(event, Event::Timer(_))));
+    }
+
+    /// A 1920x1080 primary monitor with a 1280x720 monitor on its right.
+    fn two_monitors(backend: &Rc<HeadlessBackend>) -> (Monitor, Monitor) {
+        let left: Monitor = fake_monitor(1, 0, 0, 1920, 1080, 96);
+        let right: Monitor = fake_monitor(2, 1920, 0, 1280, 720, 96);
+        backend.set_monitors(vec![left.clone(), right.clone()]);
+        return (left, right);
+    }
+
+    fn placement(backend: &Rc<HeadlessBackend>, window: &Window) -> Placement {
+        return backend.placement(window.id).unwrap();
+    }
+
+    #[test]
+    fn move_between_monitors() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, right): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(100, 100))
+            .inner_size(LogicalSize::new(800.0, 600.0))
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        assert_eq!(window.current_monitor().unwrap().id, left.id);
+
+        window.move_to_monitor(&right).unwrap();
+        window.handle_messages();
+        assert_eq!(
+            placement(&backend, &window).rect,
+            Rect {
+                left: 2020,
+                top: 100,
+                right: 2820,
+                bottom: 700
+            }
+        );
+        assert_eq!(window.current_monitor().unwrap().id, right.id);
+        assert!(window
+            .poll_events()
+            .any(|event| event == Event::Window(WindowEvent::Moved { x: 2020, y: 100 })));
+
+        // Back on the larger monitor at the same offset
+        window.move_to_monitor(&left).unwrap();
+        window.handle_messages();
+        assert_eq!(
+            placement(&backend, &window).rect,
+            Rect {
+                left: 100,
+                top: 100,
+                right: 900,
+                bottom: 700
+            }
+        );
+
+        let unknown: Monitor = fake_monitor(3, 0, 1080, 800, 600, 96);
+        assert_eq!(
+            window.move_to_monitor(&unknown),
+            Err(ModeError::UnknownMonitor)
+        );
+    }
+
+    #[test]
+    fn a_large_window_shrinks_to_the_monitor() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (_, right): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(0, 0))
+            .inner_size(LogicalSize::new(1600.0, 900.0))
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+
+        window.move_to_monitor(&right).unwrap();
+        window.handle_messages();
+        assert_eq!(placement(&backend, &window).rect, right.work_area);
+        assert_eq!(window.client_size(), (1280, 720));
+    }
+
+    #[test]
+    fn switch_window_modes_and_restore() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, right): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(100, 100))
+            .inner_size(LogicalSize::new(800.0, 600.0))
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        let windowed: Placement = placement(&backend, &window);
+
+        window
+            .set_window_mode(WindowMode::BorderlessFullscreen(right.id))
+            .unwrap();
+        window.handle_messages();
+        assert_eq!(
+            window.window_mode(),
+            WindowMode::BorderlessFullscreen(right.id)
+        );
+        assert_eq!(window.current_monitor().unwrap().id, right.id);
+        let fullscreen: Placement = placement(&backend, &window);
+        assert_eq!(fullscreen.rect, right.bounds);
+        assert_eq!(fullscreen.style, WS_POPUP);
+        // The saved placement stays the windowed one
+        assert_eq!(
+            window.saved_placement().unwrap(),
+            SavedPlacement::new(&windowed, &backend.monitors())
+        );
+
+        // Exclusive fullscreen changes the bounds of the monitor to the video mode
+        let video_mode: VideoMode = left.video_modes[1];
+        assert_eq!((video_mode.width, video_mode.height), (1280, 720));
+        window
+            .set_window_mode(WindowMode::ExclusiveFullscreen(left.id, video_mode))
+            .unwrap();
+        window.handle_messages();
+        let monitors: Vec<Monitor> = backend.monitors();
+        assert_eq!(
+            monitors[0].bounds,
+            Rect {
+                left: 0,
+                top: 0,
+                right: 1280,
+                bottom: 720
+            }
+        );
+        assert_eq!(placement(&backend, &window).rect, monitors[0].bounds);
+
+        // Moving a fullscreen window switches the monitor of the mode, and gives the old monitor its mode back
+        window.move_to_monitor(&right).unwrap();
+        window.handle_messages();
+        assert_eq!(
+            window.window_mode(),
+            WindowMode::ExclusiveFullscreen(right.id, video_mode)
+        );
+        assert_eq!(backend.monitors()[0].bounds, left.bounds);
+
+        window.set_window_mode(WindowMode::Windowed).unwrap();
+        window.handle_messages();
+        assert_eq!(window.window_mode(), WindowMode::Windowed);
+        assert_eq!(placement(&backend, &window), windowed);
+        assert_eq!(backend.monitors(), vec![left, right]);
+        assert_eq!(window.client_size(), (800, 600));
+    }
+
+    #[test]
+    fn fullscreen_restores_a_maximized_window() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, _): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(100, 100))
+            .inner_size(LogicalSize::new(800.0, 600.0))
+            .maximized(true)
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        let maximized: Placement = placement(&backend, &window);
+        assert!(maximized.maximized);
+
+        window
+            .set_window_mode(WindowMode::BorderlessFullscreen(left.id))
+            .unwrap();
+        window.handle_messages();
+        assert!(!placement(&backend, &window).maximized);
+
+        window.set_window_mode(WindowMode::Windowed).unwrap();
+        window.handle_messages();
+        assert_eq!(placement(&backend, &window), maximized);
+        assert_eq!(window.client_size(), (1920, 1080));
+    }
+
+    #[test]
+    fn open_fullscreen_on_the_monitor_of_the_position() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (_, right): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(2000, 50))
+            .inner_size(LogicalSize::new(800.0, 600.0))
+            .fullscreen(true)
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        assert_eq!(
+            window.window_mode(),
+            WindowMode::BorderlessFullscreen(right.id)
+        );
+        assert_eq!(placement(&backend, &window).rect, right.bounds);
+
+        // Leaving fullscreen goes to the position the window was built with
+        window.set_window_mode(WindowMode::Windowed).unwrap();
+        window.handle_messages();
+        assert_eq!(
+            placement(&backend, &window).rect,
+            Rect {
+                left: 2000,
+                top: 50,
+                right: 2800,
+                bottom: 650
+            }
+        );
+        assert_eq!(window.current_monitor().unwrap().id, right.id);
+    }
+
+    #[test]
+    fn open_fullscreen_without_a_position_goes_back_to_the_center() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, _): (Monitor, Monitor) = two_monitors(&backend);
+        let mut window: Window = WindowBuilder::new("Test")
+            .inner_size(LogicalSize::new(800.0, 600.0))
+            .fullscreen(true)
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        assert_eq!(
+            window.window_mode(),
+            WindowMode::BorderlessFullscreen(left.id)
+        );
+
+        window.set_window_mode(WindowMode::Windowed).unwrap();
+        window.handle_messages();
+        assert_eq!(
+            placement(&backend, &window).rect,
+            monitor::center_in(left.work_area, 800, 600)
+        );
+    }
+
+    #[test]
+    fn a_failed_mode_switch_keeps_the_mode() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, _): (Monitor, Monitor) = two_monitors(&backend);
+        let window: Window = headless_window(&backend);
+        let windowed: Placement = placement(&backend, &window);
+
+        let unknown: VideoMode = VideoMode {
+            width: 1024,
+            height: 768,
+            refresh_rate: 75,
+            bit_depth: 32,
+        };
+        let rejected: Result<(), ModeError> =
+            window.set_window_mode(WindowMode::ExclusiveFullscreen(left.id, unknown));
+        assert_eq!(rejected, Err(ModeError::VideoModeRejected));
+        let missing: Result<(), ModeError> =
+            window.set_window_mode(WindowMode::BorderlessFullscreen(MonitorId(7)));
+        assert_eq!(missing, Err(ModeError::UnknownMonitor));
+        assert_eq!(window.window_mode(), WindowMode::Windowed);
+        assert_eq!(placement(&backend, &window), windowed);
+    }
+
+    #[test]
+    fn restore_after_a_monitor_was_disconnected() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let (left, right): (Monitor, Monitor) = two_monitors(&backend);
+        let path: PathBuf =
+            std::env::temp_dir().join(format!("placement-test-{}.cfg", std::process::id()));
+        let store: PlacementStore = PlacementStore::new(&path);
+        let builder: WindowBuilder = WindowBuilder::new("Test")
+            .position(PhysicalPoint::new(2200, 100))
+            .inner_size(LogicalSize::new(800.0, 500.0))
+            .placement_store(store.clone());
+
+        let mut window: Window = builder.clone().build_with(backend.clone()).unwrap();
+        window.handle_messages();
+        assert_eq!(window.current_monitor().unwrap().id, right.id);
+        window.close().unwrap();
+        let saved: SavedPlacement = store.load().unwrap();
+        assert_eq!(
+            saved.rect,
+            Rect {
+                left: 2200,
+                top: 100,
+                right: 3000,
+                bottom: 600
+            }
+        );
+        assert_eq!(saved.monitor.as_ref().unwrap().name, right.name);
+
+        // The monitor on the right is gone, the window has to come back on the one that is left
+        backend.set_monitors(vec![left.clone()]);
+        let mut window: Window = builder.build_with(backend.clone()).unwrap();
+        window.handle_messages();
+        let _ = std::fs::remove_file(&path);
+        let rect: Rect = placement(&backend, &window).rect;
+        assert!(placement_store::is_visible(rect, &backend.monitors()));
+        assert_eq!(
+            rect,
+            Rect {
+                left: 1120,
+                top: 100,
+                right: 1920,
+                bottom: 600
+            }
+        );
+        assert_eq!(window.current_monitor().unwrap().id, left.id);
+        assert_eq!(window.client_size(), (800, 500));
+    }
+
+    #[test]
+    fn close_returns_the_save_error_and_still_destroys() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        // The directory of the store is a file, so the placement can't be written
+        let file: PathBuf =
+            std::env::temp_dir().join(format!("placement-file-{}", std::process::id()));
+        std::fs::write(&file, "").unwrap();
+        let store: PlacementStore = PlacementStore::new(file.join("placement.cfg"));
+        let mut window: Window = WindowBuilder::new("Test")
+            .placement_store(store.clone())
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+
+        assert!(window.close().is_err());
+        assert!(window.is_destroyed());
+        assert!(backend.window_ids().is_empty());
+        assert_eq!(store.load(), None);
+        // Closing again doesn't try to save a second time
+        assert!(window.close().is_ok());
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[test]
+    fn a_class_keeps_its_style() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let builder: WindowBuilder = WindowBuilder::new("Test").class_name("Shared");
+        let _first: Window = builder.clone().build_with(backend.clone()).unwrap();
+        let _second: Window = builder.clone().build_with(backend.clone()).unwrap();
+
+        let other_style: Result<Window, BuildError> =
+            builder.class_style(CS_DBLCLKS).build_with(backend.clone());
+        assert_eq!(other_style.err(), Some(BuildError::ClassMismatch));
+    }
+
+    #[test]
+    fn unicode_titles() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let window: Window = headless_window(&backend);
+
+        window.set_title("Überprüfung 日本 🦀").unwrap();
+        assert_eq!(
+            backend.title(window.id).as_deref(),
+            Some("Überprüfung 日本 🦀")
+        );
+        assert_eq!(window.set_title("a\0b"), Err(NulError { position: 1 }));
+    }
+
+    #[test]
+    fn an_ignored_close_request_keeps_the_window_open() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        backend.inject(window.id, Event::Window(WindowEvent::CloseRequested));
+        assert_eq!(window.handle_messages(), None);
+        let events: Vec<Event> = window.poll_events().collect();
+        assert_eq!(events, vec![Event::Window(WindowEvent::CloseRequested)]);
+
+        // The app didn't call close()
+        assert_eq!(window.handle_messages(), None);
+        assert!(!window.is_destroyed());
+        assert_eq!(backend.window_ids(), vec![window.id]);
+        assert_eq!(window.exit_code, None);
+    }
+
+    #[test]
+    fn close_destroys_the_window_and_then_quits() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        backend.inject(window.id, Event::Window(WindowEvent::CloseRequested));
+        window.handle_messages();
+        for event in window.poll_events().collect::<Vec<Event>>() {
+            if event == Event::Window(WindowEvent::CloseRequested) {
+                window.close().unwrap();
+            }
+        }
+        assert!(window.is_destroyed());
+        assert!(backend.window_ids().is_empty());
+        let events: Vec<Event> = window.poll_events().collect();
+        assert_eq!(events, vec![Event::Window(WindowEvent::Destroyed)]);
+
+        // The quit comes with the next pump, after the app saw the window go
+        assert_eq!(window.handle_messages(), Some(0));
+        assert_eq!(window.exit_code, Some(0));
+        // Closing again does nothing
+        window.close().unwrap();
+        assert_eq!(window.poll_events().count(), 0);
+    }
+
+    #[test]
+    fn user_events_arrive_in_order_after_the_window_events() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window<u32> = proxy_window(&backend);
+        let proxy: EventLoopProxy<u32> = window.create_proxy();
+
+        proxy.send_event(1).unwrap();
+        backend.inject(window.id, Event::Char('a'));
+        proxy.clone().send_event(2).unwrap();
+        proxy.send_event(3).unwrap();
+        backend.inject(window.id, Event::Char('b'));
+        window.handle_messages();
+
+        let events: Vec<Event<u32>> = window.poll_events().collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::Char('a'),
+                Event::Char('b'),
+                Event::User(1),
+                Event::User(2),
+                Event::User(3)
+            ]
+        );
+    }
+
+    #[test]
+    fn send_after_close_gives_the_payload_back() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let window: Window<u32> = proxy_window(&backend);
+        let proxy: EventLoopProxy<u32> = window.create_proxy();
+        assert!(!proxy.is_closed());
+
+        window.close().unwrap();
+        assert!(proxy.is_closed());
+        assert_eq!(proxy.send_event(5), Err(EventLoopClosed(5)));
+
+        // A dropped window without a close works the same
+        let dropped: Window<u32> = proxy_window(&backend);
+        let proxy: EventLoopProxy<u32> = dropped.create_proxy();
+        drop(dropped);
+        assert_eq!(proxy.send_event(6), Err(EventLoopClosed(6)));
+    }
+
+    #[test]
+    fn a_send_from_another_thread_wakes_the_loop() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window<u32> = proxy_window(&backend);
+        let proxy: EventLoopProxy<u32> = window.create_proxy();
+        let timeout: Duration = Duration::from_secs(10);
+        window.set_control_flow(ControlFlow::WaitUntil(Instant::now() + timeout));
+
+        let sender: std::thread::JoinHandle<()> = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            proxy.send_event(9).unwrap();
+        });
+        let start: Instant = Instant::now();
+        window.handle_messages();
+        assert!(start.elapsed() < timeout);
+        sender.join().unwrap();
+        assert_eq!(
+            window.poll_events().collect::<Vec<Event<u32>>>(),
+            vec![Event::User(9)]
+        );
+    }
+
+    #[test]
+    fn poll_events_keeps_the_message_order() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        window.inject(WM_KEYDOWN, WPARAM(0x41), LPARAM(1));
+        window.inject(WM_CHAR, WPARAM('a' as usize), LPARAM(1));
+        window.inject(WM_MOUSEMOVE, WPARAM(0), LPARAM(20 << 16 | 10));
+        window.inject(WM_SIZE, WPARAM(0), LPARAM(480 << 16 | 640));
+        window.inject(WM_LBUTTONDOWN, WPARAM(0), LPARAM(20 << 16 | 10));
+        window.inject(WM_KEYUP, WPARAM(0x41), LPARAM(3 << 30 | 1));
+
+        let kinds: Vec<&str> = window
+            .poll_events()
+            .map(|event| match event {
+                Event::Key(KeyEvent {
+                    key_state: KeyState::Press,
+                    ..
+                }) => "key press",
+                Event::Key(_) => "key release",
+                Event::Char('a') => "char",
+                Event::Mouse(mouse) if mouse.mouse_state == MouseState::Move => "move",
+                Event::Mouse(mouse) if mouse.mouse_state == MouseState::Enter => "enter",
+                Event::Mouse(mouse) if mouse.mouse_state == MouseState::LPress => "click",
+                Event::Window(WindowEvent::Resized {
+                    width: 640,
+                    height: 480,
+                }) => "resize",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "key press",
+                "char",
+                "move",
+                "enter",
+                "resize",
+                "click",
+                "key release"
+            ]
+        );
+        // Everything was taken
+        assert_eq!(window.poll_events().count(), 0);
+    }
+
+    #[test]
+    fn poll_events_leaves_the_input_state_up_to_date() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+
+        window.inject(WM_KEYDOWN, WPARAM(0x41), LPARAM(1));
+        window.inject(WM_CHAR, WPARAM('a' as usize), LPARAM(1));
+        window.inject(WM_MOUSEMOVE, WPARAM(0), LPARAM(20 << 16 | 10));
+        window.inject(WM_LBUTTONDOWN, WPARAM(0), LPARAM(25 << 16 | 15));
+        window.poll_events().for_each(drop);
+
+        // Taking the events doesn't take them from the keyboard and the mouse
+        assert!(window.keyboard().key_is_pressed(0x41));
+        assert_eq!(window.keyboard().read_char(), Some('a'));
+        assert!(window.mouse().left_pressed);
+        assert!(window.mouse().is_in_window);
+        assert_eq!(window.mouse().get_pos(), ClientPoint { x: 15, y: 25 });
+        assert_eq!(window.client_size(), (800, 600));
+
+        window.inject(WM_KEYUP, WPARAM(0x41), LPARAM(3 << 30 | 1));
+        window.inject(WM_SIZE, WPARAM(0), LPARAM(480 << 16 | 640));
+        assert!(!window.keyboard().key_is_pressed(0x41));
+        assert_eq!(window.client_size(), (640, 480));
+    }
+
+    #[test]
+    fn injected_clicks_keep_their_position() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = headless_window(&backend);
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        let pos: ClientPoint = ClientPoint { x: 120, y: 45 };
+        let press: MouseEvent = MouseEvent {
+            mouse_state: MouseState::LPress,
+            left_pressed: true,
+            right_pressed: false,
+            wheel_pressed: false,
+            pos,
+        };
+        backend.inject(window.id, Event::Mouse(press));
+        window.handle_messages();
+        assert_eq!(
+            window.poll_events().collect::<Vec<Event>>(),
+            vec![Event::Mouse(press)]
+        );
+        assert_eq!(window.mouse().get_pos(), pos);
+
+        let release: MouseEvent = MouseEvent {
+            mouse_state: MouseState::LRelease,
+            left_pressed: false,
+            pos: ClientPoint { x: 130, y: 50 },
+            ..press
+        };
+        backend.inject(window.id, Event::Mouse(release));
+        window.handle_messages();
+        assert_eq!(
+            window.poll_events().collect::<Vec<Event>>(),
+            vec![Event::Mouse(release)]
+        );
+        assert_eq!(window.mouse().get_pos(), release.pos);
+    }
+
+    #[test]
+    fn every_window_has_its_own_input_state() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut first: Window = headless_window(&backend);
+        let second: Window = headless_window(&backend);
+        first.handle_messages();
+
+        let key: KeyEvent = KeyEvent {
+            key_state: KeyState::Press,
+            key_code: 0x41,
+        };
+        let click: MouseEvent = MouseEvent {
+            mouse_state: MouseState::RPress,
+            left_pressed: false,
+            right_pressed: true,
+            wheel_pressed: false,
+            pos: ClientPoint { x: 7, y: 9 },
+        };
+        backend.inject(first.id, Event::Key(key));
+        backend.inject(second.id, Event::Mouse(click));
+        // One pump delivers the events of both windows
+        first.handle_messages();
+
+        assert!(first.keyboard().key_is_pressed(0x41));
+        assert!(!second.keyboard().key_is_pressed(0x41));
+        assert_eq!(first.keyboard().key_queue, vec![key]);
+        assert!(second.keyboard().key_queue.is_empty());
+
+        assert!(second.mouse().right_pressed);
+        assert!(!first.mouse().right_pressed);
+        assert_eq!(second.mouse().get_pos(), click.pos);
+        assert_eq!(first.mouse().get_pos(), ClientPoint { x: 0, y: 0 });
+        assert!(first.mouse().event_queue.is_empty());
+    }
+
+    /// The windows that got a [WindowEvent::ClipboardChanged] since the last pump.
+    fn clipboard_changes(windows: &mut [&mut Window]) -> Vec<bool> {
+        return windows
+            .iter_mut()
+            .map(|window| {
+                window.handle_messages();
+                return window
+                    .poll_events()
+                    .any(|event| event == Event::Window(WindowEvent::ClipboardChanged));
+            })
+            .collect();
+    }
+
+    #[test]
+    fn clipboard_changes_reach_the_listening_windows() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut listener: Window = headless_window(&backend);
+        let mut other: Window = headless_window(&backend);
+        clipboard_changes(&mut [&mut listener, &mut other]);
+
+        assert!(listener.set_clipboard_listener(true));
+        // Like AddClipboardFormatListener, listening twice fails
+        assert!(!listener.set_clipboard_listener(true));
+        backend.simulate_copy(&[ClipboardItem::Text("Pasted".to_string())]);
+        assert_eq!(
+            clipboard_changes(&mut [&mut listener, &mut other]),
+            vec![true, false]
+        );
+        assert_eq!(listener.clipboard_text(), Some("Pasted".to_string()));
+
+        // A copy of the app itself is a change as well
+        assert!(other.set_clipboard_text("Copied"));
+        assert_eq!(
+            clipboard_changes(&mut [&mut listener, &mut other]),
+            vec![true, false]
+        );
+        assert_eq!(backend.clipboard().text(), Some("Copied".to_string()));
+
+        assert!(listener.set_clipboard_listener(false));
+        assert!(!listener.set_clipboard_listener(false));
+        backend.simulate_copy(&[ClipboardItem::Text("Ignored".to_string())]);
+        assert_eq!(
+            clipboard_changes(&mut [&mut listener, &mut other]),
+            vec![false, false]
+        );
+    }
+
+    #[test]
+    fn clipboard_formats_through_the_window() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let window: Window = headless_window(&backend);
+        let image: ClipboardImage = ClipboardImage::new(1, 1, vec![1, 2, 3, 4]).unwrap();
+        assert!(window.set_clipboard_image(&image));
+        assert_eq!(window.clipboard_image(), Some(image));
+        assert_eq!(window.clipboard_text(), None);
+
+        let format: ClipboardFormat = window.register_clipboard_format("Test.Shapes").unwrap();
+        assert!(window.set_clipboard(&[
+            ClipboardItem::Data(format.clone(), vec![9, 8]),
+            ClipboardItem::Text("Two shapes".to_string()),
+        ]));
+        assert_eq!(window.clipboard_data(&format), Some(vec![9, 8]));
+        assert_eq!(window.clipboard_text(), Some("Two shapes".to_string()));
+        assert_eq!(window.clipboard_image(), None);
+    }
+
+    fn files() -> Vec<PathBuf> {
+        return vec![PathBuf::from("C:\\a.txt"), PathBuf::from("C:\\b.png")];
+    }
+
+    #[test]
+    fn drop_files_on_a_window_that_accepts_them() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = WindowBuilder::new("Test")
+            .accept_files(true)
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        let position: ClientPoint = ClientPoint { x: 30, y: 40 };
+        assert!(backend.simulate_file_drop(window.id, &files(), position));
+        window.handle_messages();
+        let events: Vec<Event> = window.poll_events().collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::DragDrop(DragDropEvent::HoveredFile(files()[0].clone())),
+                Event::DragDrop(DragDropEvent::HoveredFile(files()[1].clone())),
+                Event::DragDrop(DragDropEvent::DroppedFiles {
+                    paths: files(),
+                    position
+                }),
+            ]
+        );
+
+        assert!(backend.simulate_file_drag_cancelled(window.id, &files()[..1]));
+        window.handle_messages();
+        let events: Vec<Event> = window.poll_events().collect();
+        assert_eq!(
+            events,
+            vec![
+                Event::DragDrop(DragDropEvent::HoveredFile(files()[0].clone())),
+                Event::DragDrop(DragDropEvent::HoveredFileCancelled),
+            ]
+        );
+        // A drag without files doesn't reach the window
+        assert!(!backend.simulate_file_drop(window.id, &[], position));
+    }
+
+    #[test]
+    fn a_window_that_doesnt_accept_files_gets_no_drop() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut window: Window = WindowBuilder::new("Test")
+            .accept_files(false)
+            .build_with(backend.clone())
+            .unwrap();
+        window.handle_messages();
+        window.poll_events().for_each(drop);
+
+        assert!(!backend.simulate_file_drop(window.id, &files(), ClientPoint { x: 0, y: 0 }));
+        assert!(!backend.simulate_file_drag_cancelled(window.id, &files()));
+        window.handle_messages();
+        assert!(window
+            .poll_events()
+            .all(|event| !matches!(event, Event::DragDrop(_))));
+    }
+}
@@ -0,0 +1,203 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use super::{
+    builder::WindowBuilder,
+    chrome::{CaptionButton, ChromeLayout},
+    clipboard::{ClipboardFormat, ClipboardImage, ClipboardItem},
+    control_flow::ControlFlow,
+    coords::ClientPoint,
+    dialog::{DialogResult, FileDialog, MessageBox},
+    error::BuildError,
+    manager::WindowId,
+    menu::{Menu, MenuId},
+    monitor::{Monitor, MonitorId, Placement, VideoMode},
+    proxy::Waker,
+    state::WindowState,
+    wide::NulError,
+};
+
+pub mod headless;
+#[cfg(windows)]
+pub mod win32;
+
+/// The shape of the mouse cursor while it is over the client area.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum CursorIcon {
+    #[default]
+    Arrow,
+    /// The text cursor (I-beam).
+    Text,
+    Hand,
+    Crosshair,
+    Wait,
+    /// Resize left and right.
+    ResizeHorizontal,
+    /// Resize up and down.
+    ResizeVertical,
+    Move,
+    NotAllowed,
+}
+
+/**
+    Everything a [Window](super::Window) needs from the platform. The Win32 implementation talks to the real
+    windows, the [headless](headless::HeadlessBackend) one keeps them in memory so the app logic can run and be
+    tested without a desktop.
+
+    A backend writes the events of a window to the [WindowState] it got in [Backend::create_window()], the
+    [Window](super::Window) reads them from there. That way the app code is the same for every backend.<br>
+    All the functions take `&self`: they are called while other windows of the same backend are borrowed, so a
+    backend which keeps state uses a [RefCell] inside.
+*/
+pub trait Backend {
+    /// Create a window for a validated builder. Fill in the sizes and limits of `state` and return the id.
+    fn create_window(
+        &self,
+        builder: &WindowBuilder,
+        state: &Rc<RefCell<WindowState>>,
+    ) -> Result<WindowId, BuildError>;
+
+    /// Destroy a window. It receives [WindowEvent::Destroyed](super::event::WindowEvent::Destroyed) as its last
+    /// event.
+    fn destroy_window(&self, id: WindowId);
+
+    /// Quit when this window is destroyed instead of when the last window is. See
+    /// [WindowManager::set_main_window()](super::manager::WindowManager::set_main_window()).
+    fn set_main_window(&self, id: Option<WindowId>);
+
+    /// Deliver the pending events of all the windows, blocking according to `control_flow` when there are none.
+    /// Returns the exit code when the app has to quit.
+    fn pump_events(&self, control_flow: ControlFlow) -> Option<usize>;
+
+    /// The current time of the message clock in milliseconds, the clock of
+    /// [Contact::time](super::gesture::Contact::time). It wraps around after ~49.7 days.
+    fn message_time(&self) -> u32;
+
+    /**
+        Opt in to per-monitor DPI awareness for the whole process. Call it before the first window is created, windows
+        that already exist keep being scaled by the system. Returns false when the awareness was already set.
+    */
+    fn enable_dpi_awareness(&self) -> bool;
+
+    /// The monitors of the desktop. The primary monitor is the one with [Monitor::primary] set.
+    fn monitors(&self) -> Vec<Monitor>;
+
+    /// Where a window is right now. [None] for an unknown window.
+    fn placement(&self, id: WindowId) -> Option<Placement>;
+
+    /// Give a window another style and move it. The window sends the move, resize and maximize events like it
+    /// does for a user.
+    fn set_placement(&self, id: WindowId, placement: &Placement);
+
+    /**
+        Switch a monitor to another video mode, or back to the mode of its settings for [None]. The change only
+        lasts while the app runs. Returns false when the monitor doesn't support the mode.
+    */
+    fn set_video_mode(&self, monitor: MonitorId, mode: Option<VideoMode>) -> bool;
+
+    fn set_title(&self, id: WindowId, title: &str) -> Result<(), NulError>;
+
+    fn set_visible(&self, id: WindowId, visible: bool);
+
+    /**
+        A [Waker] which makes a blocked [Backend::pump_events()] return, used by
+        [EventLoopProxy](super::proxy::EventLoopProxy). It is called from other threads.
+    */
+    fn create_waker(&self, id: WindowId) -> Waker;
+
+    /// Send a [WindowEvent::RedrawRequested](super::event::WindowEvent::RedrawRequested) with the next pump.
+    fn request_redraw(&self, id: WindowId);
+
+    /// Replace the menu bar of a window, [None] removes it. The client area keeps its size.
+    fn set_menu(&self, id: WindowId, menu: Option<&Menu>);
+
+    /**
+        Show `menu` at `position` and wait until the user picks an item or closes it. Returns the id of the item,
+        [None] when the menu was closed without picking one.
+    */
+    fn show_popup_menu(&self, id: WindowId, menu: &Menu, position: ClientPoint) -> Option<MenuId>;
+
+    /**
+        Switch a window between the native title bar and one drawn by the app, [None] for the native one. The
+        window keeps its outer size, the [WindowState] already has the new chrome.
+    */
+    fn set_chrome(&self, id: WindowId, chrome: Option<&ChromeLayout>);
+
+    /// Do what a click on the native caption button does: minimize, maximize or restore, or ask to close.
+    fn caption_command(&self, id: WindowId, button: CaptionButton);
+
+    /**
+        Show a modal message box and wait until it is closed. When it can't be shown the result is
+        [MessageButtons::close_result()](super::dialog::MessageButtons::close_result()).
+    */
+    fn show_message_box(&self, message_box: &MessageBox) -> DialogResult;
+
+    /// Show a file dialog and wait until it is closed. The picked paths, empty when the user cancelled.
+    fn show_file_dialog(&self, dialog: &FileDialog) -> Vec<PathBuf>;
+
+    fn set_cursor_icon(&self, id: WindowId, icon: CursorIcon);
+
+    fn set_cursor_visible(&self, visible: bool);
+
+    fn clipboard_text(&self) -> Option<String>;
+
+    fn clipboard_image(&self) -> Option<ClipboardImage>;
+
+    /// The bytes of a registered format, [None] when the clipboard doesn't have it.
+    fn clipboard_data(&self, format: &ClipboardFormat) -> Option<Vec<u8>>;
+
+    /// Replace the contents of the clipboard with `items`. Returns false when the clipboard couldn't be opened
+    /// or an item couldn't be stored.
+    fn set_clipboard(&self, items: &[ClipboardItem]) -> bool;
+
+    /// The format for `name`, the same one for every app that registers it. [None] when it couldn't be registered.
+    fn register_clipboard_format(&self, name: &str) -> Option<ClipboardFormat>;
+
+    /// Start or stop sending [WindowEvent::ClipboardChanged](super::event::WindowEvent::ClipboardChanged) to a
+    /// window. Returns false when that didn't work.
+    fn listen_clipboard(&self, id: WindowId, listen: bool) -> bool;
+
+    /// Show a frame of `width` x `height` pixels in the client area. The pixels are 0x00RRGGBB, row by row from
+    /// the top.
+    fn present(&self, id: WindowId, width: u32, height: u32, pixels: &[u32]);
+
+    /// A description of the last platform error, or [None] when there wasn't one.
+    fn last_error(&self) -> Option<String>;
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Rc<dyn Backend>>> = RefCell::new(None);
+}
+
+/// The backend new windows of this thread are created with. Win32 on windows and headless everywhere else.
+pub fn current() -> Rc<dyn Backend> {
+    return CURRENT.with(|current| {
+        return current
+            .borrow_mut()
+            .get_or_insert_with(default_backend)
+            .clone();
+    });
+}
+
+/**
+    Use another backend for the windows that are created after this call. Keep an [Rc] to the backend if you want
+    to talk to it, like injecting events into a [HeadlessBackend](headless::HeadlessBackend):
+    ```ignore
+    let headless: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+    backend::set_current(headless.clone());
+    let mut app: ExampleApp = ExampleApp::create();
+    headless.inject(app.window.id, Event::Char('a'));
+    ```
+*/
+pub fn set_current(backend: Rc<dyn Backend>) {
+    CURRENT.with(|current| *current.borrow_mut() = Some(backend));
+}
+
+#[cfg(windows)]
+fn default_backend() -> Rc<dyn Backend> {
+    return Rc::new(win32::Win32Backend);
+}
+
+#[cfg(not(windows))]
+fn default_backend() -> Rc<dyn Backend> {
+    return Rc::new(headless::HeadlessBackend::new());
+}
@@ -0,0 +1,1201 @@
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    rc::Rc,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use windows::Win32::UI::WindowsAndMessaging::{SIZE_MAXIMIZED, WNDCLASS_STYLES};
+
+use super::{
+    super::{
+        builder::{WindowBuilder, WindowPosition},
+        chrome::{CaptionButton, CaptionButtonState, ChromeLayout, HitTest},
+        clipboard::{ClipboardFormat, ClipboardImage, ClipboardItem, MemoryClipboard},
+        control_flow::ControlFlow,
+        coords::{
+            scale_factor_from_dpi, ClientPoint, PhysicalPoint, PhysicalSize, ScreenPoint, Size,
+            BASE_DPI,
+        },
+        dialog::{
+            DialogResult, FileDialog, FileDialogKind, MessageBox, MessageButtons, MessageIcon,
+        },
+        drag_drop::{self, DragDropEvent},
+        error::BuildError,
+        event::{Event, WindowEvent},
+        keyboard::KeyEvent,
+        manager::{WindowId, WindowRelation},
+        menu::{Menu, MenuId},
+        monitor::{self, Monitor, MonitorId, Placement, VideoMode, WindowMode},
+        proxy::Waker,
+        rect::Rect,
+        state::WindowState,
+        wide::{NulError, WideCString},
+    },
+    Backend, CursorIcon,
+};
+
+/// The size of the monitor of a [HeadlessBackend] when it wasn't given a layout with [HeadlessBackend::set_monitors()].
+pub const SCREEN_SIZE: (i32, i32) = (1920, 1080);
+
+/**
+    A fake monitor for [HeadlessBackend::set_monitors()]. The monitor at (0, 0) is the primary one. The work area is
+    the whole monitor, change [Monitor::work_area] to add a taskbar:
+    ```ignore
+    let mut left: Monitor = fake_monitor(1, 0, 0, 1920, 1080, 96);
+    left.work_area.bottom -= 40;
+    headless.set_monitors(vec![left, fake_monitor(2, 1920, 0, 2560, 1440, 144)]);
+    ```
+*/
+pub fn fake_monitor(id: isize, x: i32, y: i32, width: i32, height: i32, dpi: u32) -> Monitor {
+    let bounds: Rect = Rect {
+        left: x,
+        top: y,
+        right: x + width,
+        bottom: y + height,
+    };
+    let mut video_modes: Vec<VideoMode> = vec![];
+    for (width, height) in [(width as u32, height as u32), (1280, 720), (800, 600)] {
+        let mode: VideoMode = VideoMode {
+            width,
+            height,
+            refresh_rate: 60,
+            bit_depth: 32,
+        };
+        if !video_modes.contains(&mode) {
+            video_modes.push(mode);
+        }
+    }
+    return Monitor {
+        id: MonitorId(id),
+        name: format!("Headless {}", id),
+        bounds,
+        work_area: bounds,
+        dpi,
+        refresh_rate: Some(60),
+        primary: x == 0 && y == 0,
+        video_modes,
+    };
+}
+
+/// The last frame that was passed to [Backend::present()].
+#[derive(Debug, PartialEq, Clone)]
+pub struct PresentedFrame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// A window that only exists in memory.
+pub struct HeadlessWindow {
+    pub id: WindowId,
+    pub relation: WindowRelation,
+    pub title: String,
+    pub visible: bool,
+    /// The top-left corner of the window.
+    pub position: (i32, i32),
+    pub cursor_icon: CursorIcon,
+    pub accept_files: bool,
+    /// The menu bar as the backend was last given it.
+    pub menu: Option<Menu>,
+    /// The last menu shown with [Backend::show_popup_menu()] and where it was shown.
+    pub last_popup_menu: Option<(Menu, ClientPoint)>,
+    pub frame: Option<PresentedFrame>,
+    /// The rect a maximized window is restored to.
+    restore_rect: Option<Rect>,
+    redraw_requested: bool,
+    state: Rc<RefCell<WindowState>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    windows: Vec<HeadlessWindow>,
+    /// Injected events which are delivered on the next pump.
+    pending: VecDeque<(WindowId, Event)>,
+    main_window: Option<WindowId>,
+    /// Every window that was destroyed, in order.
+    destroyed: Vec<WindowId>,
+    /// The class style of every class name, a class can't be registered twice like on Win32.
+    classes: HashMap<String, WNDCLASS_STYLES>,
+    next_id: u64,
+    quit: Option<usize>,
+    /// Only moves with [HeadlessBackend::advance_message_time()].
+    message_time: u32,
+    clipboard: MemoryClipboard,
+    /// The windows which get [WindowEvent::ClipboardChanged].
+    clipboard_listeners: Vec<WindowId>,
+    /// What the user picks in the next popup menus, see [HeadlessBackend::answer_popup_menu()].
+    popup_answers: VecDeque<Option<MenuId>>,
+    /// The script of [HeadlessBackend::answer_message_box()].
+    message_box_answers: VecDeque<DialogResult>,
+    /// Every message box that was shown, in order.
+    message_boxes: Vec<MessageBox>,
+    /// The script of [HeadlessBackend::answer_file_dialog()].
+    file_dialog_answers: VecDeque<Vec<PathBuf>>,
+    /// Every file dialog that was shown, in order.
+    file_dialogs: Vec<FileDialog>,
+    cursor_hidden: bool,
+    dpi_aware: bool,
+    /// Empty until the monitors are used, see [Inner::monitors()].
+    monitors: Vec<Monitor>,
+    /// The monitors as they were before [Backend::set_video_mode()] changed them.
+    saved_monitors: Vec<Monitor>,
+}
+
+impl Inner {
+    fn monitors(&mut self) -> &mut Vec<Monitor> {
+        if self.monitors.is_empty() {
+            self.monitors.push(fake_monitor(
+                1,
+                0,
+                0,
+                SCREEN_SIZE.0,
+                SCREEN_SIZE.1,
+                BASE_DPI,
+            ));
+        }
+        return &mut self.monitors;
+    }
+}
+
+/// Set from other threads by the [Waker]'s of the backend, cuts a [ControlFlow::WaitUntil] sleep short.
+#[derive(Default)]
+struct WakeSignal {
+    woken: Mutex<bool>,
+    condvar: Condvar,
+}
+
+/**
+    A [Backend] without any windows on the screen. Windows are kept in memory, events are injected with
+    [HeadlessBackend::inject()] and delivered on the next [Window::handle_messages()](super::super::Window::handle_messages()),
+    in the order they were injected. Only an [EventLoopProxy](super::super::proxy::EventLoopProxy) can wake a
+    blocked thread, so [ControlFlow::Wait] returns right away and [ControlFlow::WaitUntil] only sleeps until the
+    deadline or the next wake when nothing is pending.
+
+    Windows have no title bar or borders, so the outer size is the client size.
+*/
+#[derive(Default)]
+pub struct HeadlessBackend {
+    inner: RefCell<Inner>,
+    wake: Arc<WakeSignal>,
+}
+
+impl HeadlessBackend {
+    pub fn new() -> HeadlessBackend {
+        return HeadlessBackend::default();
+    }
+
+    /// Queue an event for a window. Unknown windows are ignored when the events are delivered.
+    pub fn inject(&self, id: WindowId, event: Event) {
+        self.inner.borrow_mut().pending.push_back((id, event));
+    }
+
+    /**
+        Move the message clock forward by `millis`. The clock of the headless backend stands still otherwise, so
+        touch timelines and long-presses can be tested without sleeping.
+    */
+    pub fn advance_message_time(&self, millis: u32) {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        inner.message_time = inner.message_time.wrapping_add(millis);
+    }
+
+    /// Make the next pump return `exit_code`, like PostQuitMessage does.
+    pub fn post_quit(&self, exit_code: usize) {
+        self.inner.borrow_mut().quit = Some(exit_code);
+    }
+
+    /// The windows that are not destroyed, in the order they were created.
+    pub fn window_ids(&self) -> Vec<WindowId> {
+        return self
+            .inner
+            .borrow()
+            .windows
+            .iter()
+            .map(|window| window.id)
+            .collect();
+    }
+
+    /// Look at a window. [None] when it doesn't exist (anymore).
+    pub fn with_window<R>(&self, id: WindowId, f: impl FnOnce(&HeadlessWindow) -> R) -> Option<R> {
+        let inner: Ref<Inner> = self.inner.borrow();
+        return inner.windows.iter().find(|window| window.id == id).map(f);
+    }
+
+    /// The windows that were destroyed so far, dependent windows come before their owner.
+    pub fn destroyed_windows(&self) -> Vec<WindowId> {
+        return self.inner.borrow().destroyed.clone();
+    }
+
+    pub fn title(&self, id: WindowId) -> Option<String> {
+        return self.with_window(id, |window| window.title.clone());
+    }
+
+    pub fn last_frame(&self, id: WindowId) -> Option<PresentedFrame> {
+        return self
+            .with_window(id, |window| window.frame.clone())
+            .flatten();
+    }
+
+    /**
+        Replace the monitor layout, see [fake_monitor()]. Without a layout there is a single [SCREEN_SIZE] monitor
+        at 96 DPI. Open windows stay where they are.
+    */
+    pub fn set_monitors(&self, monitors: Vec<Monitor>) {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        inner.monitors = monitors;
+        inner.saved_monitors.clear();
+    }
+
+    /**
+        Pretend the primary monitor has another DPI. Windows that are created after this call open at that DPI when
+        [Backend::enable_dpi_awareness()] was called, use [HeadlessBackend::simulate_dpi_change()] for the windows
+        that are already open.
+    */
+    pub fn set_dpi(&self, dpi: u32) {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let monitors: &mut Vec<Monitor> = inner.monitors();
+        let primary: MonitorId = monitor::primary_monitor(monitors).unwrap().id;
+        for monitor in monitors.iter_mut().filter(|monitor| monitor.id == primary) {
+            monitor.dpi = dpi;
+        }
+    }
+
+    /// The DPI of the primary monitor.
+    pub fn dpi(&self) -> u32 {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        return monitor::primary_monitor(inner.monitors()).unwrap().dpi;
+    }
+
+    /**
+        Act like the window was dragged to a monitor with another DPI: queue a [WindowEvent::ScaleFactorChanged]
+        and the [WindowEvent::Resized] that windows sends after applying the suggested rect.
+    */
+    pub fn simulate_dpi_change(&self, id: WindowId, dpi: u32) {
+        let state: Rc<RefCell<WindowState>> =
+            match self.with_window(id, |window| window.state.clone()) {
+                Some(state) => state,
+                None => return,
+            };
+        let (old_dpi, width, height): (u32, i32, i32) = {
+            let state: Ref<WindowState> = state.borrow();
+            (state.dpi, state.client_width, state.client_height)
+        };
+        let size: PhysicalSize = PhysicalSize::new(width, height).scale_for_dpi(old_dpi, dpi);
+        self.inject(
+            id,
+            Event::Window(WindowEvent::ScaleFactorChanged {
+                scale_factor: scale_factor_from_dpi(dpi),
+                width: size.width,
+                height: size.height,
+            }),
+        );
+        self.inject(
+            id,
+            Event::Window(WindowEvent::Resized {
+                width: size.width,
+                height: size.height,
+            }),
+        );
+    }
+
+    /// A copy of the clipboard, to check what the app copied.
+    pub fn clipboard(&self) -> MemoryClipboard {
+        return self.inner.borrow().clipboard.clone();
+    }
+
+    /**
+        Put `items` on the clipboard like another app does, to test pasting. The listening windows get a
+        [WindowEvent::ClipboardChanged] on the next pump.
+    */
+    pub fn simulate_copy(&self, items: &[ClipboardItem]) {
+        self.set_clipboard(items);
+    }
+
+    /**
+        Drag `paths` onto a window and drop them at `position`, like a user dragging files from the explorer. The
+        window gets the hover events and [DragDropEvent::DroppedFiles] on the next pump. Returns false, and injects
+        nothing, when the window doesn't accept files.
+    */
+    pub fn simulate_file_drop(
+        &self,
+        id: WindowId,
+        paths: &[PathBuf],
+        position: ClientPoint,
+    ) -> bool {
+        if !self.simulate_file_hover(id, paths) {
+            return false;
+        }
+        self.inject(
+            id,
+            Event::DragDrop(DragDropEvent::DroppedFiles {
+                paths: paths.to_vec(),
+                position,
+            }),
+        );
+        return true;
+    }
+
+    /// Drag `paths` onto a window and away again. Returns false when the window doesn't accept files.
+    pub fn simulate_file_drag_cancelled(&self, id: WindowId, paths: &[PathBuf]) -> bool {
+        if !self.simulate_file_hover(id, paths) {
+            return false;
+        }
+        self.inject(id, Event::DragDrop(DragDropEvent::HoveredFileCancelled));
+        return true;
+    }
+
+    fn simulate_file_hover(&self, id: WindowId, paths: &[PathBuf]) -> bool {
+        if self.with_window(id, |window| window.accept_files) != Some(true) || paths.is_empty() {
+            return false;
+        }
+        for event in drag_drop::hover_events(paths) {
+            self.inject(id, Event::DragDrop(event));
+        }
+        return true;
+    }
+
+    /**
+        Queue what the user picks in the next popup menu: an item or [None] to close the menu. Every popup menu takes
+        one answer, a menu without an answer is closed. A disabled item can't be picked, it closes the menu as well.
+    */
+    pub fn answer_popup_menu(&self, answer: Option<MenuId>) {
+        self.inner.borrow_mut().popup_answers.push_back(answer);
+    }
+
+    /**
+        Right click the client area at `position`, like WM_CONTEXTMENU does. When the window has a context menu it
+        is shown and takes the next answer of [HeadlessBackend::answer_popup_menu()], the picked item arrives as
+        [Event::MenuActivated] on the next pump. Returns false when there was no menu to show.
+    */
+    pub fn simulate_context_menu(&self, id: WindowId, position: ClientPoint) -> bool {
+        let state: Rc<RefCell<WindowState>> =
+            match self.with_window(id, |window| window.state.clone()) {
+                Some(state) => state,
+                None => return false,
+            };
+        let context_menu: Option<Menu> = {
+            let state: Ref<WindowState> = state.borrow();
+            state
+                .context_menu
+                .clone()
+                .filter(|_| state.client_rect().contains(position))
+        };
+        let context_menu: Menu = match context_menu {
+            Some(context_menu) => context_menu,
+            None => return false,
+        };
+        if let Some(item) = self.show_popup_menu(id, &context_menu, position) {
+            self.inject(id, Event::MenuActivated(item));
+        }
+        return true;
+    }
+
+    /// Click an item of the menu bar. Returns false, and injects nothing, when the item doesn't exist or is disabled.
+    pub fn simulate_menu_click(&self, id: WindowId, item: MenuId) -> bool {
+        let enabled: Option<bool> = self.with_window(id, |window| match &window.menu {
+            Some(menu) => menu.is_enabled(item),
+            None => false,
+        });
+        if enabled != Some(true) {
+            return false;
+        }
+        self.inject(id, Event::MenuActivated(item));
+        return true;
+    }
+
+    /**
+        Move the cursor over the window like WM_NCMOUSEMOVE does, [None] moves it off the window. Moving onto or off
+        a caption button of the chrome sends [WindowEvent::CaptionButtonsChanged] on the next pump. Returns what the
+        window answers to WM_NCHITTEST for the point, [HitTest::Nowhere] for [None] or an unknown window.
+    */
+    pub fn simulate_chrome_hover(&self, id: WindowId, position: Option<ClientPoint>) -> HitTest {
+        let state: Rc<RefCell<WindowState>> =
+            match self.with_window(id, |window| window.state.clone()) {
+                Some(state) => state,
+                None => return HitTest::Nowhere,
+            };
+        let (hit, mut buttons): (HitTest, CaptionButtonState) = {
+            let state: Ref<WindowState> = state.borrow();
+            let hit: HitTest = position.map_or(HitTest::Nowhere, |point| state.hit_test(point));
+            (hit, state.caption_buttons)
+        };
+        match hit.caption_button() {
+            Some(button) => buttons.on_hover(Some(button)),
+            None => buttons.on_leave(),
+        }
+        self.inject_caption_buttons(id, buttons);
+        return hit;
+    }
+
+    /**
+        Click the window at `position` like windows does outside the client area. A caption button of the chrome is
+        hovered, pressed and released, and then does its command: minimize, maximize or restore, or
+        [WindowEvent::CloseRequested]. Returns what the window answers to WM_NCHITTEST for the point, the click does
+        nothing for the other parts.
+    */
+    pub fn simulate_chrome_click(&self, id: WindowId, position: ClientPoint) -> HitTest {
+        let state: Rc<RefCell<WindowState>> =
+            match self.with_window(id, |window| window.state.clone()) {
+                Some(state) => state,
+                None => return HitTest::Nowhere,
+            };
+        let (hit, mut buttons): (HitTest, CaptionButtonState) = {
+            let state: Ref<WindowState> = state.borrow();
+            (state.hit_test(position), state.caption_buttons)
+        };
+        let button: CaptionButton = match hit.caption_button() {
+            Some(button) => button,
+            None => return hit,
+        };
+        buttons.on_press(button);
+        self.inject_caption_buttons(id, buttons);
+        let clicked: Option<CaptionButton> = buttons.on_release(Some(button));
+        self.inject_caption_buttons(id, buttons);
+        if let Some(clicked) = clicked {
+            self.caption_command(id, clicked);
+        }
+        return hit;
+    }
+
+    /// The state only changes on the pump, where unchanged buttons give no event.
+    fn inject_caption_buttons(&self, id: WindowId, buttons: CaptionButtonState) {
+        self.inject(
+            id,
+            Event::Window(WindowEvent::CaptionButtonsChanged {
+                hovered: buttons.hovered,
+                pressed: buttons.pressed,
+            }),
+        );
+    }
+
+    /**
+        Queue the button the user presses in the next message box, like a script for a "confirm delete" flow:
+        ```ignore
+        headless.answer_message_box(DialogResult::No);
+        app.delete_selection();
+        assert_eq!(app.files.len(), 3);
+        ```
+        Every box takes one answer. A box without an answer gets its default button, like the user pressing Enter.
+        Panics when the answer isn't one of the buttons of the box, the script doesn't match the app then.
+    */
+    pub fn answer_message_box(&self, answer: DialogResult) {
+        self.inner
+            .borrow_mut()
+            .message_box_answers
+            .push_back(answer);
+    }
+
+    /// The message boxes that were shown so far, oldest first.
+    pub fn message_boxes(&self) -> Vec<MessageBox> {
+        return self.inner.borrow().message_boxes.clone();
+    }
+
+    /**
+        Queue the paths the user picks in the next file dialog, an empty list cancels it. Every dialog takes one
+        answer, a dialog without an answer is cancelled. Panics when a dialog without
+        [FileDialog::multi_select] gets more than one path.
+
+        A save dialog works like the real one: a name without an extension gets the
+        [default extension](FileDialog::default_extension()), and a file that exists on disk needs a "Yes" from
+        [HeadlessBackend::answer_message_box()] for the overwrite prompt. Without one the dialog takes the next
+        answer, like a user who picks another name.
+    */
+    pub fn answer_file_dialog(&self, paths: &[PathBuf]) {
+        self.inner
+            .borrow_mut()
+            .file_dialog_answers
+            .push_back(paths.to_vec());
+    }
+
+    /// The file dialogs that were shown so far, oldest first.
+    pub fn file_dialogs(&self) -> Vec<FileDialog> {
+        return self.inner.borrow().file_dialogs.clone();
+    }
+
+    pub fn cursor_visible(&self) -> bool {
+        return !self.inner.borrow().cursor_hidden;
+    }
+
+    fn window_mut<'a>(
+        inner: &'a mut RefMut<Inner>,
+        id: WindowId,
+    ) -> Option<&'a mut HeadlessWindow> {
+        return inner.windows.iter_mut().find(|window| window.id == id);
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn create_window(
+        &self,
+        builder: &WindowBuilder,
+        state: &Rc<RefCell<WindowState>>,
+    ) -> Result<WindowId, BuildError> {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        if let WindowRelation::Owned(owner)
+        | WindowRelation::Child(owner)
+        | WindowRelation::Modal(owner) = builder.relation
+        {
+            if !inner.windows.iter().any(|window| window.id == owner) {
+                return Err(BuildError::UnknownRelatedWindow);
+            }
+        }
+        let class_style: WNDCLASS_STYLES = *inner
+            .classes
+            .entry(builder.class_name.clone())
+            .or_insert(builder.class_style);
+        if class_style != builder.class_style {
+            return Err(BuildError::ClassMismatch);
+        }
+
+        let primary: Monitor = monitor::primary_monitor(inner.monitors()).unwrap().clone();
+        let primary_dpi: u32 = if inner.dpi_aware {
+            primary.dpi
+        } else {
+            BASE_DPI
+        };
+        let corner: Option<PhysicalPoint> =
+            builder.physical_position(scale_factor_from_dpi(primary_dpi));
+        let target: Monitor = match corner {
+            Some(corner) => monitor::monitor_from_point(
+                inner.monitors(),
+                ScreenPoint {
+                    x: corner.x,
+                    y: corner.y,
+                },
+            )
+            .unwrap()
+            .clone(),
+            None => primary,
+        };
+        let dpi: u32 = if inner.dpi_aware {
+            target.dpi
+        } else {
+            BASE_DPI
+        };
+        let scale_factor: f64 = scale_factor_from_dpi(dpi);
+        // Sizes in different units can only be compared in physical pixels
+        builder.to_physical(scale_factor).validate()?;
+        let limit = |size: Size| {
+            let size: PhysicalSize = size.to_physical(scale_factor);
+            return (size.width, size.height);
+        };
+
+        let PhysicalSize {
+            mut width,
+            mut height,
+        } = builder.size.to_physical(scale_factor);
+        let windowed_rect: Rect = match builder.position {
+            // Like CW_USEDEFAULT, the top-left corner of the work area stands in for the spot windows picks
+            WindowPosition::Default if !builder.fullscreen => Rect {
+                left: target.work_area.left,
+                top: target.work_area.top,
+                right: target.work_area.left + width,
+                bottom: target.work_area.top + height,
+            },
+            _ => monitor::windowed_rect(corner, target.work_area, width, height),
+        };
+        let mut position: (i32, i32) = (windowed_rect.left, windowed_rect.top);
+        if builder.fullscreen {
+            position = (target.bounds.left, target.bounds.top);
+            (width, height) = (target.bounds.width(), target.bounds.height());
+        } else if builder.maximized {
+            position = (target.work_area.left, target.work_area.top);
+            (width, height) = (target.work_area.width(), target.work_area.height());
+        }
+
+        inner.next_id += 1;
+        let id: WindowId = WindowId(inner.next_id);
+        {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            state.client_width = width;
+            state.client_height = height;
+            state.dpi = dpi;
+            state.client_origin = ScreenPoint {
+                x: position.0,
+                y: position.1,
+            };
+            if builder.fullscreen {
+                state.window_mode = WindowMode::BorderlessFullscreen(target.id);
+                state.windowed_placement = Some(Placement {
+                    rect: windowed_rect,
+                    maximized: false,
+                    style: WindowBuilder {
+                        fullscreen: false,
+                        ..builder.clone()
+                    }
+                    .window_style(),
+                    ex_style: builder.window_ex_style(),
+                });
+            }
+            state.min_size = builder.min_size.map(limit);
+            state.max_size = builder.max_size.map(limit);
+            state.style = builder.window_style();
+            state.ex_style = builder.window_ex_style();
+            if builder.visible {
+                state.push_window_event(WindowEvent::Shown);
+            }
+            if builder.maximized {
+                if let Some(event) = state.on_size_kind(SIZE_MAXIMIZED) {
+                    state.push_window_event(event);
+                }
+            }
+        }
+
+        if inner.main_window.is_none() && builder.relation == WindowRelation::TopLevel {
+            inner.main_window = Some(id);
+        }
+        inner.windows.push(HeadlessWindow {
+            id,
+            relation: builder.relation,
+            title: builder.title.clone(),
+            visible: builder.visible,
+            position,
+            cursor_icon: CursorIcon::Arrow,
+            accept_files: builder.accept_files,
+            menu: builder.menu.clone(),
+            last_popup_menu: None,
+            frame: None,
+            restore_rect: builder.maximized.then_some(windowed_rect),
+            redraw_requested: builder.visible,
+            state: state.clone(),
+        });
+        return Ok(id);
+    }
+
+    fn destroy_window(&self, id: WindowId) {
+        // Owned and child windows are destroyed before their owner, just like on windows
+        let dependents: Vec<WindowId> = self
+            .inner
+            .borrow()
+            .windows
+            .iter()
+            .filter(|window| match window.relation {
+                WindowRelation::Owned(owner)
+                | WindowRelation::Child(owner)
+                | WindowRelation::Modal(owner) => owner == id,
+                WindowRelation::TopLevel => false,
+            })
+            .map(|window| window.id)
+            .collect();
+        for dependent in dependents {
+            self.destroy_window(dependent);
+        }
+
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let index: usize = match inner.windows.iter().position(|window| window.id == id) {
+            Some(index) => index,
+            None => return,
+        };
+        let window: HeadlessWindow = inner.windows.remove(index);
+        inner.destroyed.push(id);
+        inner.clipboard_listeners.retain(|listener| *listener != id);
+        let mut state: RefMut<WindowState> = window.state.borrow_mut();
+        let event: WindowEvent = state.on_destroy();
+        state.push_window_event(event);
+        drop(state);
+
+        if inner.main_window == Some(id) || inner.windows.is_empty() {
+            inner.quit.get_or_insert(0);
+        }
+    }
+
+    fn set_main_window(&self, id: Option<WindowId>) {
+        self.inner.borrow_mut().main_window = id;
+    }
+
+    fn pump_events(&self, control_flow: ControlFlow) -> Option<usize> {
+        let idle: bool = {
+            let inner: Ref<Inner> = self.inner.borrow();
+            inner.pending.is_empty()
+                && inner.quit.is_none()
+                && !inner.windows.iter().any(|window| window.redraw_requested)
+        };
+        let mut woken: MutexGuard<bool> = self.wake.woken.lock().unwrap();
+        if let (true, ControlFlow::WaitUntil(deadline)) = (idle, control_flow) {
+            while !*woken && Instant::now() < deadline {
+                let timeout: Duration = deadline.saturating_duration_since(Instant::now());
+                woken = self.wake.condvar.wait_timeout(woken, timeout).unwrap().0;
+            }
+        }
+        *woken = false;
+        drop(woken);
+
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let pending: Vec<(WindowId, Event)> = inner.pending.drain(..).collect();
+        let redraws: Vec<Rc<RefCell<WindowState>>> = inner
+            .windows
+            .iter_mut()
+            .filter_map(|window| {
+                std::mem::take(&mut window.redraw_requested).then(|| window.state.clone())
+            })
+            .collect();
+        drop(inner);
+
+        for (id, event) in pending {
+            let state: Option<Rc<RefCell<WindowState>>> =
+                self.with_window(id, |window| window.state.clone());
+            if let Some(state) = state {
+                let mut state: RefMut<WindowState> = state.borrow_mut();
+                let key_event: Option<KeyEvent> = match event {
+                    Event::Key(key_event) => Some(key_event),
+                    _ => None,
+                };
+                state.apply_event(event);
+                // Like translate() does for WM_KEYDOWN, a replay gets the recorded MenuActivated instead
+                if let Some(item) =
+                    key_event.and_then(|key_event| state.match_accelerator(key_event))
+                {
+                    state.push_event(Event::MenuActivated(item));
+                }
+            }
+        }
+        // Windows sends WM_PAINT when the queue is empty, so the redraws come after the input
+        for state in redraws {
+            state
+                .borrow_mut()
+                .push_window_event(WindowEvent::RedrawRequested);
+        }
+
+        return self.inner.borrow_mut().quit.take();
+    }
+
+    fn message_time(&self) -> u32 {
+        return self.inner.borrow().message_time;
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        return self.inner.borrow_mut().monitors().clone();
+    }
+
+    fn placement(&self, id: WindowId) -> Option<Placement> {
+        return self.with_window(id, |window| {
+            let state: Ref<WindowState> = window.state.borrow();
+            let rect: Rect = Rect {
+                left: window.position.0,
+                top: window.position.1,
+                right: window.position.0 + state.client_width,
+                bottom: window.position.1 + state.client_height,
+            };
+            return Placement {
+                rect: window.restore_rect.unwrap_or(rect),
+                maximized: state.maximized,
+                style: state.style,
+                ex_style: state.ex_style,
+            };
+        });
+    }
+
+    fn set_placement(&self, id: WindowId, placement: &Placement) {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let dpi_aware: bool = inner.dpi_aware;
+        let monitor: Monitor = monitor::monitor_from_rect(inner.monitors(), placement.rect)
+            .unwrap()
+            .clone();
+        let window: &mut HeadlessWindow = match Self::window_mut(&mut inner, id) {
+            Some(window) => window,
+            None => return,
+        };
+
+        let rect: Rect = if placement.maximized {
+            window.restore_rect = Some(placement.rect);
+            monitor.work_area
+        } else {
+            window.restore_rect = None;
+            placement.rect
+        };
+        let moved: bool = window.position != (rect.left, rect.top);
+        window.position = (rect.left, rect.top);
+        let mut events: Vec<WindowEvent> = vec![];
+        {
+            let mut state: RefMut<WindowState> = window.state.borrow_mut();
+            state.style = placement.style;
+            state.ex_style = placement.ex_style;
+            state.client_origin = ScreenPoint {
+                x: rect.left,
+                y: rect.top,
+            };
+
+            let mut size: PhysicalSize = PhysicalSize::new(rect.width(), rect.height());
+            if dpi_aware && monitor.dpi != state.dpi {
+                // Like the suggested rect of WM_DPICHANGED, a fullscreen or maximized window keeps covering the monitor
+                if state.window_mode == WindowMode::Windowed && !placement.maximized {
+                    size = size.scale_for_dpi(state.dpi, monitor.dpi);
+                }
+                events.push(WindowEvent::ScaleFactorChanged {
+                    scale_factor: monitor.scale_factor(),
+                    width: size.width,
+                    height: size.height,
+                });
+            }
+            if moved {
+                events.push(WindowEvent::Moved {
+                    x: rect.left,
+                    y: rect.top,
+                });
+            }
+            events.push(WindowEvent::Resized {
+                width: size.width,
+                height: size.height,
+            });
+            events.push(match placement.maximized {
+                true => WindowEvent::Maximized,
+                false => WindowEvent::Restored,
+            });
+        }
+        for event in events {
+            inner.pending.push_back((id, Event::Window(event)));
+        }
+    }
+
+    fn set_video_mode(&self, id: MonitorId, mode: Option<VideoMode>) -> bool {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        let index: usize = match inner.monitors().iter().position(|monitor| monitor.id == id) {
+            Some(index) => index,
+            None => return false,
+        };
+        let saved: Option<usize> = inner
+            .saved_monitors
+            .iter()
+            .position(|monitor| monitor.id == id);
+
+        let mode: VideoMode = match mode {
+            Some(mode) => mode,
+            None => {
+                if let Some(saved) = saved {
+                    inner.monitors[index] = inner.saved_monitors.remove(saved);
+                }
+                return true;
+            }
+        };
+        if !inner.monitors[index].video_modes.contains(&mode) {
+            return false;
+        }
+        if saved.is_none() {
+            let original: Monitor = inner.monitors[index].clone();
+            inner.saved_monitors.push(original);
+        }
+        let monitor: &mut Monitor = &mut inner.monitors[index];
+        monitor.bounds.right = monitor.bounds.left + mode.width as i32;
+        monitor.bounds.bottom = monitor.bounds.top + mode.height as i32;
+        monitor.work_area = monitor.bounds;
+        monitor.refresh_rate = Some(mode.refresh_rate);
+        return true;
+    }
+
+    fn enable_dpi_awareness(&self) -> bool {
+        self.inner.borrow_mut().dpi_aware = true;
+        return true;
+    }
+
+    fn set_title(&self, id: WindowId, title: &str) -> Result<(), NulError> {
+        // Same rules as the Win32 backend, so a title that fails there fails here as well
+        WideCString::new(title)?;
+        if let Some(window) = Self::window_mut(&mut self.inner.borrow_mut(), id) {
+            window.title = title.to_string();
+        }
+        return Ok(());
+    }
+
+    fn set_visible(&self, id: WindowId, visible: bool) {
+        let state: Option<Rc<RefCell<WindowState>>> = {
+            let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+            match Self::window_mut(&mut inner, id) {
+                Some(window) if window.visible != visible => {
+                    window.visible = visible;
+                    Some(window.state.clone())
+                }
+                _ => None,
+            }
+        };
+        if let Some(state) = state {
+            let event: WindowEvent = if visible {
+                WindowEvent::Shown
+            } else {
+                WindowEvent::Hidden
+            };
+            state.borrow_mut().push_window_event(event);
+        }
+    }
+
+    fn request_redraw(&self, id: WindowId) {
+        if let Some(window) = Self::window_mut(&mut self.inner.borrow_mut(), id) {
+            window.redraw_requested = true;
+        }
+    }
+
+    fn create_waker(&self, _id: WindowId) -> Waker {
+        // The payloads go through the channel of the proxy, the wake only has to end the wait
+        let wake: Arc<WakeSignal> = self.wake.clone();
+        return Arc::new(move || {
+            *wake.woken.lock().unwrap() = true;
+            wake.condvar.notify_all();
+            return true;
+        });
+    }
+
+    fn set_menu(&self, id: WindowId, menu: Option<&Menu>) {
+        if let Some(window) = Self::window_mut(&mut self.inner.borrow_mut(), id) {
+            window.menu = menu.cloned();
+        }
+    }
+
+    fn set_chrome(&self, _id: WindowId, _chrome: Option<&ChromeLayout>) {
+        // The windows have no frame, the client area is the whole window already
+    }
+
+    fn caption_command(&self, id: WindowId, button: CaptionButton) {
+        match button {
+            CaptionButton::Minimize => self.inject(id, Event::Window(WindowEvent::Minimized)),
+            CaptionButton::Maximize => {
+                let mut placement: Placement = match self.placement(id) {
+                    Some(placement) => placement,
+                    None => return,
+                };
+                placement.maximized = !placement.maximized;
+                self.set_placement(id, &placement);
+            }
+            CaptionButton::Close => self.inject(id, Event::Window(WindowEvent::CloseRequested)),
+        }
+    }
+
+    fn show_popup_menu(&self, id: WindowId, menu: &Menu, position: ClientPoint) -> Option<MenuId> {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        Self::window_mut(&mut inner, id)?.last_popup_menu = Some((menu.clone(), position));
+        let answer: Option<MenuId> = inner.popup_answers.pop_front().flatten();
+        return answer.filter(|item| menu.is_enabled(*item));
+    }
+
+    fn show_message_box(&self, message_box: &MessageBox) -> DialogResult {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        inner.message_boxes.push(message_box.clone());
+        let buttons: &[DialogResult] = message_box.buttons.results();
+        return match inner.message_box_answers.pop_front() {
+            Some(answer) if buttons.contains(&answer) => answer,
+            Some(answer) => panic!(
+                "The script answers {:?} to the message box {:?}, which has the buttons {:?}",
+                answer, message_box.text, buttons
+            ),
+            None => buttons[message_box.default_index()],
+        };
+    }
+
+    fn show_file_dialog(&self, dialog: &FileDialog) -> Vec<PathBuf> {
+        self.inner.borrow_mut().file_dialogs.push(dialog.clone());
+        loop {
+            let answer: Option<Vec<PathBuf>> =
+                self.inner.borrow_mut().file_dialog_answers.pop_front();
+            let answer: Vec<PathBuf> = match answer {
+                Some(answer) => answer,
+                None => return vec![],
+            };
+            if answer.len() > 1 && !dialog.multi_select {
+                panic!(
+                    "The script picks {} paths in a file dialog without multi select",
+                    answer.len()
+                );
+            }
+            if dialog.kind != FileDialogKind::Save || answer.is_empty() {
+                return answer;
+            }
+
+            let mut path: PathBuf = answer[0].clone();
+            if let (None, Some(extension)) = (path.extension(), dialog.default_extension()) {
+                path.set_extension(extension);
+            }
+            if !dialog.confirm_overwrite || !path.exists() {
+                return vec![path];
+            }
+            let mut confirm: MessageBox = MessageBox::new(&format!(
+                "{} already exists.\nDo you want to replace it?",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ))
+            .title("Confirm Save As")
+            .icon(MessageIcon::Warning)
+            .buttons(MessageButtons::YesNo)
+            .default_button(DialogResult::No);
+            confirm.owner = dialog.owner;
+            if self.show_message_box(&confirm) == DialogResult::Yes {
+                return vec![path];
+            }
+        }
+    }
+
+    fn set_cursor_icon(&self, id: WindowId, icon: CursorIcon) {
+        if let Some(window) = Self::window_mut(&mut self.inner.borrow_mut(), id) {
+            window.cursor_icon = icon;
+        }
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        self.inner.borrow_mut().cursor_hidden = !visible;
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        return self.inner.borrow().clipboard.text();
+    }
+
+    fn clipboard_image(&self) -> Option<ClipboardImage> {
+        return self.inner.borrow().clipboard.image();
+    }
+
+    fn clipboard_data(&self, format: &ClipboardFormat) -> Option<Vec<u8>> {
+        return self.inner.borrow().clipboard.data(format);
+    }
+
+    fn set_clipboard(&self, items: &[ClipboardItem]) -> bool {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        inner.clipboard.set(items);
+        // Windows notifies the listeners after the clipboard is closed, so the event comes with the next pump
+        let listeners: Vec<WindowId> = inner.clipboard_listeners.clone();
+        for id in listeners {
+            inner
+                .pending
+                .push_back((id, Event::Window(WindowEvent::ClipboardChanged)));
+        }
+        return true;
+    }
+
+    fn register_clipboard_format(&self, name: &str) -> Option<ClipboardFormat> {
+        return self.inner.borrow_mut().clipboard.register_format(name);
+    }
+
+    fn listen_clipboard(&self, id: WindowId, listen: bool) -> bool {
+        let mut inner: RefMut<Inner> = self.inner.borrow_mut();
+        if !inner.windows.iter().any(|window| window.id == id) {
+            return false;
+        }
+        let listening: bool = inner.clipboard_listeners.contains(&id);
+        if listen && !listening {
+            inner.clipboard_listeners.push(id);
+        } else if !listen {
+            inner.clipboard_listeners.retain(|listener| *listener != id);
+        }
+        // Like AddClipboardFormatListener, adding twice fails
+        return listen != listening;
+    }
+
+    fn present(&self, id: WindowId, width: u32, height: u32, pixels: &[u32]) {
+        if let Some(window) = Self::window_mut(&mut self.inner.borrow_mut(), id) {
+            window.frame = Some(PresentedFrame {
+                width,
+                height,
+                pixels: pixels.to_vec(),
+            });
+        }
+    }
+
+    fn last_error(&self) -> Option<String> {
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    fn create(backend: &HeadlessBackend, relation: WindowRelation) -> WindowId {
+        let state: Rc<RefCell<WindowState>> = Rc::new(RefCell::new(WindowState::new(0, 0)));
+        return backend
+            .create_window(&WindowBuilder::new("Test").relation(relation), &state)
+            .unwrap();
+    }
+
+    #[test]
+    fn dependent_windows_are_destroyed_first() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let main: WindowId = create(&backend, WindowRelation::TopLevel);
+        let owned: WindowId = create(&backend, WindowRelation::Owned(main));
+        let child: WindowId = create(&backend, WindowRelation::Child(owned));
+        let modal: WindowId = create(&backend, WindowRelation::Modal(main));
+        assert_eq!(backend.window_ids(), vec![main, owned, child, modal]);
+
+        backend.destroy_window(main);
+        assert_eq!(backend.destroyed_windows(), vec![child, owned, modal, main]);
+        assert!(backend.window_ids().is_empty());
+
+        // A window can only be related to a window that exists
+        let state: Rc<RefCell<WindowState>> = Rc::new(RefCell::new(WindowState::new(0, 0)));
+        let orphan: Result<WindowId, BuildError> = backend.create_window(
+            &WindowBuilder::new("Test").relation(WindowRelation::Owned(main)),
+            &state,
+        );
+        assert_eq!(orphan.err(), Some(BuildError::UnknownRelatedWindow));
+    }
+
+    #[test]
+    fn quit_with_the_main_window() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let main: WindowId = create(&backend, WindowRelation::TopLevel);
+        let other: WindowId = create(&backend, WindowRelation::TopLevel);
+
+        backend.destroy_window(other);
+        assert_eq!(backend.pump_events(ControlFlow::Poll), None);
+        backend.destroy_window(main);
+        assert_eq!(backend.pump_events(ControlFlow::Poll), Some(0));
+        // The quit is only reported once
+        assert_eq!(backend.pump_events(ControlFlow::Poll), None);
+    }
+
+    #[test]
+    fn quit_with_the_last_window() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let first: WindowId = create(&backend, WindowRelation::TopLevel);
+        let second: WindowId = create(&backend, WindowRelation::TopLevel);
+        backend.set_main_window(None);
+
+        backend.destroy_window(first);
+        assert_eq!(backend.pump_events(ControlFlow::Poll), None);
+        backend.destroy_window(second);
+        assert_eq!(backend.pump_events(ControlFlow::Poll), Some(0));
+    }
+
+    #[test]
+    fn a_class_style_mismatch_creates_no_window() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let builder: WindowBuilder = WindowBuilder::new("Test").class_name("Shared");
+        let state: Rc<RefCell<WindowState>> = Rc::new(RefCell::new(WindowState::new(0, 0)));
+        let first: WindowId = backend.create_window(&builder, &state).unwrap();
+
+        let other_style: WindowBuilder = builder.class_style(WNDCLASS_STYLES(0));
+        let state: Rc<RefCell<WindowState>> = Rc::new(RefCell::new(WindowState::new(0, 0)));
+        assert_eq!(
+            backend.create_window(&other_style, &state).err(),
+            Some(BuildError::ClassMismatch)
+        );
+        assert_eq!(backend.window_ids(), vec![first]);
+    }
+
+    #[test]
+    fn wait_until_sleeps_until_the_deadline() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        create(&backend, WindowRelation::TopLevel);
+        // The first pump delivers the redraw of the new window
+        backend.pump_events(ControlFlow::Poll);
+
+        let start: Instant = Instant::now();
+        let timeout: Duration = Duration::from_millis(50);
+        backend.pump_events(ControlFlow::WaitUntil(start + timeout));
+        assert!(start.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn wait_until_returns_early_on_a_wake() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let id: WindowId = create(&backend, WindowRelation::TopLevel);
+        backend.pump_events(ControlFlow::Poll);
+
+        let waker: Waker = backend.create_waker(id);
+        let sender: thread::JoinHandle<bool> = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            return waker();
+        });
+        let start: Instant = Instant::now();
+        backend.pump_events(ControlFlow::WaitUntil(start + Duration::from_secs(10)));
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(sender.join().unwrap());
+    }
+}
@@ -0,0 +1,1623 @@
+use std::{
+    cell::{RefCell, RefMut},
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+    time::Instant,
+};
+
+use windows::{
+    core::{PCWSTR, PSTR},
+    Win32::{
+        Foundation::{
+            GetLastError, BOOL, HANDLE, HGLOBAL, HINSTANCE, HWND, LPARAM, LRESULT, POINT, POINTS,
+            RECT, TRUE, WPARAM,
+        },
+        Graphics::Gdi::{
+            ChangeDisplaySettingsExW, ClientToScreen, EnumDisplayDevicesW, EnumDisplayMonitors,
+            EnumDisplaySettingsW, GetDC, GetMonitorInfoW, InvalidateRect, MonitorFromPoint,
+            MonitorFromRect, MonitorFromWindow, ReleaseDC, ScreenToClient, StretchDIBits,
+            BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CDS_FULLSCREEN, CDS_TYPE, DEVMODEW,
+            DIB_RGB_COLORS, DISPLAY_DEVICEW, DISP_CHANGE, DISP_CHANGE_SUCCESSFUL, DM_BITSPERPEL,
+            DM_DISPLAYFREQUENCY, DM_PELSHEIGHT, DM_PELSWIDTH, ENUM_CURRENT_SETTINGS,
+            ENUM_DISPLAY_SETTINGS_MODE, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW,
+            MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY, SRCCOPY,
+        },
+        System::{
+            DataExchange::{
+                AddClipboardFormatListener, CloseClipboard, EmptyClipboard, GetClipboardData,
+                IsClipboardFormatAvailable, OpenClipboard, RegisterClipboardFormatW,
+                RemoveClipboardFormatListener, SetClipboardData,
+            },
+            Diagnostics::Debug::{
+                FormatMessageA, FORMAT_MESSAGE_ALLOCATE_BUFFER, FORMAT_MESSAGE_FROM_SYSTEM,
+            },
+            LibraryLoader::GetModuleHandleW,
+            Memory::{
+                GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+            },
+            SystemInformation::GetTickCount,
+        },
+        UI::{
+            HiDpi::{
+                GetDpiForMonitor, GetDpiForWindow, SetProcessDpiAwarenessContext,
+                DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, MDT_EFFECTIVE_DPI,
+            },
+            Input::KeyboardAndMouse::{
+                EnableWindow, ReleaseCapture, SetCapture, TrackMouseEvent, TME_LEAVE,
+                TME_NONCLIENT, TRACKMOUSEEVENT,
+            },
+            Shell::{DragFinish, DragQueryPoint, HDROP},
+            WindowsAndMessaging::{
+                CreateWindowExW, DefWindowProcW, DestroyMenu, DestroyWindow, DispatchMessageW,
+                DrawMenuBar, GetMenu, GetMessageTime, GetSystemMetrics, GetWindowLongPtrW,
+                GetWindowPlacement, IsWindowVisible, IsZoomed, LoadCursorW, MessageBoxExW,
+                MsgWaitForMultipleObjectsEx, PeekMessageW, PostMessageW, SetCursor, SetMenu,
+                SetWindowLongPtrW, SetWindowPlacement, SetWindowPos, SetWindowTextW, ShowCursor,
+                ShowWindow, TranslateMessage, CW_USEDEFAULT, GWL_EXSTYLE, GWL_STYLE, HCURSOR,
+                HMENU, HTCLIENT, IDABORT, IDCANCEL, IDCONTINUE, IDC_ARROW, IDC_CROSS, IDC_HAND,
+                IDC_IBEAM, IDC_NO, IDC_SIZEALL, IDC_SIZENS, IDC_SIZEWE, IDC_WAIT, IDIGNORE, IDNO,
+                IDOK, IDRETRY, IDTRYAGAIN, IDYES, MB_ABORTRETRYIGNORE, MB_CANCELTRYCONTINUE,
+                MB_DEFBUTTON1, MB_DEFBUTTON2, MB_DEFBUTTON3, MB_ICONERROR, MB_ICONINFORMATION,
+                MB_ICONQUESTION, MB_ICONWARNING, MB_OK, MB_OKCANCEL, MB_RETRYCANCEL, MB_YESNO,
+                MB_YESNOCANCEL, MESSAGEBOX_RESULT, MESSAGEBOX_STYLE, MINMAXINFO,
+                MONITORINFOF_PRIMARY, MSG, MWMO_INPUTAVAILABLE, NCCALCSIZE_PARAMS, PM_REMOVE,
+                QS_ALLINPUT, SC_CLOSE, SC_MAXIMIZE, SC_MINIMIZE, SC_RESTORE, SHOW_WINDOW_CMD,
+                SM_CXSCREEN, SM_CYSCREEN, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE,
+                SWP_NOZORDER, SW_HIDE, SW_SHOW, SW_SHOWMAXIMIZED, SW_SHOWNORMAL, WINDOWPLACEMENT,
+                WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_CONTEXTMENU, WM_DPICHANGED, WM_DROPFILES,
+                WM_GETMINMAXINFO, WM_MOUSEWHEEL, WM_MOVE, WM_NCCALCSIZE, WM_NCCREATE, WM_NCDESTROY,
+                WM_NCHITTEST, WM_NCLBUTTONDOWN, WM_NCLBUTTONUP, WM_NCMOUSELEAVE, WM_NCMOUSEMOVE,
+                WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE, WM_QUIT, WM_SETCURSOR, WM_SIZING,
+                WM_SYSCOMMAND, WM_SYSKEYDOWN, WM_SYSKEYUP, WS_EX_TOOLWINDOW, WS_VISIBLE,
+            },
+        },
+    },
+};
+
+use super::{
+    super::{
+        builder::{WindowBuilder, WindowPosition, WindowSize},
+        chrome::{chrome_client_rect, CaptionButton, CaptionButtonState, ChromeLayout, HitTest},
+        clipboard::{
+            ClipboardFormat, ClipboardImage, ClipboardItem, CF_DIB, CF_DIBV5, CF_UNICODETEXT,
+        },
+        control_flow::ControlFlow,
+        coords::{
+            scale_factor_from_dpi, ClientPoint, PhysicalPoint, PhysicalSize, ScreenPoint, Size,
+            BASE_DPI,
+        },
+        dialog::{DialogResult, FileDialog, MessageBox, MessageButtons, MessageIcon},
+        drag_drop::DragDropEvent,
+        error::BuildError,
+        event::{Event, WindowEvent},
+        make_points,
+        manager::{self, WindowEntry, WindowId, WindowRelation},
+        menu::{Menu, MenuId},
+        monitor::{self, Monitor, MonitorId, Placement, VideoMode, WindowMode},
+        mouse::{MouseEvent, MouseState},
+        proxy::Waker,
+        rect::{constrain_sizing_rect, frame_size, FrameMetrics, Rect},
+        state::{self, WindowState},
+        translate::{self, translate},
+        wide::{from_wide_lossy, NulError, WideCString},
+    },
+    Backend, CursorIcon,
+};
+
+mod drop_target;
+mod file_dialog;
+mod native_menu;
+
+/**
+    The [Backend] for real Win32 windows. All the windows of a thread are kept by the
+    [WindowManager](manager::WindowManager) of that thread, so this type has no state of its own.
+*/
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Win32Backend;
+
+impl Backend for Win32Backend {
+    fn create_window(
+        &self,
+        builder: &WindowBuilder,
+        state: &Rc<RefCell<WindowState>>,
+    ) -> Result<WindowId, BuildError> {
+        /*
+            hInstance is the handle to an instance or handle to a module. The
+            operating system uses this value to identify the executable or EXE
+            when it's loaded in memory.
+        */
+        // The instance is only needed to register the class
+        let instance: HINSTANCE = unsafe { GetModuleHandleW(None) }
+            .map_err(|_| BuildError::RegisterClassFailed(unsafe { GetLastError().0 }))?;
+
+        // The class is only registered for the first window with this name
+        let (_, class_name): (u16, PCWSTR) = manager::with_manager(|manager| {
+            manager.register_class(
+                &builder.class_name,
+                builder.class_style,
+                Some(wndproc),
+                instance,
+            )
+        })?;
+
+        // The title only has to live until CreateWindowEx returns, windows copies it
+        let title: WideCString =
+            WideCString::new(&builder.title).map_err(|_| BuildError::InteriorNul("title"))?;
+
+        // The parent (for a child window) or owner (for an owned window) has to exist already
+        let related_hwnd: Option<HWND> = match builder.relation {
+            WindowRelation::TopLevel => None,
+            WindowRelation::Owned(id) | WindowRelation::Child(id) | WindowRelation::Modal(id) => {
+                Some(
+                    manager::with_manager(|manager| manager.hwnd(id))
+                        .ok_or(BuildError::UnknownRelatedWindow)?,
+                )
+            }
+        };
+
+        // A logical position goes by the primary monitor, logical sizes by the monitor the window opens on
+        let corner: Option<PhysicalPoint> =
+            builder.physical_position(scale_factor_from_dpi(monitor_dpi(monitor_at(None))));
+        let target: HMONITOR = monitor_at(corner);
+        let dpi: u32 = monitor_dpi(target);
+        let scale_factor: f64 = scale_factor_from_dpi(dpi);
+        // Sizes in different units can only be compared in physical pixels
+        builder.to_physical(scale_factor).validate()?;
+        let limit = |size: Size| {
+            let size: PhysicalSize = size.to_physical(scale_factor);
+            return (size.width, size.height);
+        };
+        // The outer size of the window for a frame of `frame` pixels
+        let outer_size = |frame: (i32, i32)| match builder.size {
+            WindowSize::Inner(size) => {
+                let size: PhysicalSize = size.to_physical(scale_factor);
+                (size.width + frame.0, size.height + frame.1)
+            }
+            WindowSize::Outer(size) => {
+                let size: PhysicalSize = size.to_physical(scale_factor);
+                (size.width, size.height)
+            }
+        };
+
+        let window_style: WINDOW_STYLE = builder.window_style();
+        let window_ex_style: WINDOW_EX_STYLE = builder.window_ex_style();
+
+        // The size of the title bar, menu bar and borders. A custom chrome has none, the whole window is client area
+        let has_menu: bool = builder.menu.is_some();
+        let has_chrome: bool = builder.chrome.is_some();
+        let window_frame_size = |style: WINDOW_STYLE| match has_chrome {
+            true => (0, 0),
+            false => frame_size(
+                style,
+                window_ex_style,
+                has_menu,
+                &FrameMetrics::for_dpi(dpi),
+            ),
+        };
+        let (frame_width, frame_height): (i32, i32) = window_frame_size(window_style);
+
+        // The whole monitor the window opens on and the part of it that isn't covered by the taskbar
+        let (bounds, work_area): (Rect, Rect) = match monitor_info(target) {
+            Some(monitor) => (monitor.bounds, monitor.work_area),
+            None => {
+                let screen: Rect =
+                    Rect::from_size(unsafe { GetSystemMetrics(SM_CXSCREEN) }, unsafe {
+                        GetSystemMetrics(SM_CYSCREEN)
+                    });
+                (screen, screen)
+            }
+        };
+
+        let (mut outer_width, mut outer_height): (i32, i32) =
+            outer_size((frame_width, frame_height));
+        let (mut x, mut y): (i32, i32) = match (corner, builder.position) {
+            (Some(corner), _) => (corner.x, corner.y),
+            (None, WindowPosition::Centered) => {
+                let rect: Rect = monitor::center_in(work_area, outer_width, outer_height);
+                (rect.left, rect.top)
+            }
+            (None, _) => (CW_USEDEFAULT, CW_USEDEFAULT),
+        };
+        // Where the window goes when it leaves fullscreen, see Window::set_window_mode()
+        let mut windowed: Option<(MonitorId, Placement)> = None;
+        if builder.fullscreen {
+            let windowed_builder: WindowBuilder = WindowBuilder {
+                fullscreen: false,
+                ..builder.clone()
+            };
+            let style: WINDOW_STYLE = windowed_builder.window_style();
+            let frame: (i32, i32) = window_frame_size(style);
+            let (width, height): (i32, i32) = outer_size(frame);
+            windowed = Some((
+                MonitorId(target.0),
+                Placement {
+                    rect: monitor::windowed_rect(corner, work_area, width, height),
+                    maximized: false,
+                    style,
+                    ex_style: window_ex_style,
+                },
+            ));
+
+            // Cover the whole monitor the window opens on
+            (x, y, outer_width, outer_height) =
+                (bounds.left, bounds.top, bounds.width(), bounds.height());
+        }
+
+        {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            state.client_width = outer_width - frame_width;
+            state.client_height = outer_height - frame_height;
+            state.dpi = dpi;
+            if let Some((monitor, placement)) = windowed {
+                state.window_mode = WindowMode::BorderlessFullscreen(monitor);
+                state.windowed_placement = Some(placement);
+            }
+            state.min_size = builder.min_size.map(limit);
+            state.max_size = builder.max_size.map(limit);
+            state.style = window_style;
+            state.ex_style = window_ex_style;
+        }
+
+        /*
+            Creates an overlapped, pop-up, or child window. It specifies the window class, window title, window
+            style, and (optionally) the initial position and size of the window. The function also specifies
+            the window's parent or owner, if any, and the window's menu.
+
+            If the function succeeds, the return value is a handle to the new window. If the function fails, the
+            return value is NULL. We can get the error info by calling GetLastError. See GetExitCodes().
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-createwindowexw
+        */
+        // The window destroys its menu bar when it is destroyed itself
+        let menu_bar: Option<HMENU> = builder.menu.as_ref().and_then(native_menu::create_menu_bar);
+        let hwnd: HWND = unsafe {
+            CreateWindowExW(
+                window_ex_style,
+                class_name,
+                title.as_pcwstr(),
+                window_style,
+                x,
+                y,
+                outer_width,
+                outer_height,
+                related_hwnd.unwrap_or_default(),
+                menu_bar.unwrap_or_default(),
+                instance,
+                // The wndproc picks this up on WM_NCCREATE, see state::attach()
+                Some(state::into_create_param(state)),
+            )
+        };
+
+        if hwnd.0 == 0 {
+            if let Some(menu_bar) = menu_bar {
+                unsafe { DestroyMenu(menu_bar) };
+            }
+            return Err(BuildError::CreateWindowFailed(unsafe { GetLastError().0 }));
+        }
+
+        let id: WindowId = manager::with_manager(|manager| {
+            let id: WindowId = manager.next_window_id();
+            manager.insert(WindowEntry {
+                id,
+                hwnd,
+                relation: builder.relation,
+                state: state.clone(),
+            });
+            return id;
+        });
+
+        // A window at the default position can end up on another monitor than the one we scaled for
+        let window_dpi: u32 = unsafe { GetDpiForWindow(hwnd) };
+        if window_dpi != 0 && window_dpi != dpi && !builder.fullscreen {
+            let (width, height, frame): (i32, i32, (i32, i32)) = {
+                let mut state: RefMut<WindowState> = state.borrow_mut();
+                let size: PhysicalSize = PhysicalSize::new(state.client_width, state.client_height)
+                    .scale_for_dpi(dpi, window_dpi);
+                state.on_dpi_changed(window_dpi, size.width, size.height);
+                (size.width, size.height, state.frame_size())
+            };
+            unsafe {
+                SetWindowPos(
+                    hwnd,
+                    HWND::default(),
+                    0,
+                    0,
+                    width + frame.0,
+                    height + frame.1,
+                    SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+                )
+            };
+        }
+
+        if builder.accept_files {
+            drop_target::register(hwnd);
+        }
+
+        if let (WindowRelation::Modal(_), Some(owner)) = (builder.relation, related_hwnd) {
+            // A modal window blocks the input of its owner until it is destroyed, see manager::on_destroy()
+            unsafe { EnableWindow(owner, false) };
+        }
+
+        if builder.visible {
+            let show: SHOW_WINDOW_CMD = if builder.maximized {
+                SW_SHOWMAXIMIZED
+            } else {
+                SW_SHOW
+            };
+            unsafe { ShowWindow(hwnd, show) };
+        }
+
+        return Ok(id);
+    }
+
+    fn destroy_window(&self, id: WindowId) {
+        // Don't hold on to the manager, DestroyWindow sends WM_DESTROY to the wndproc right away
+        let (hwnd, modal_owner): (Option<HWND>, Option<HWND>) =
+            manager::with_manager(|manager| (manager.hwnd(id), manager.modal_owner(id)));
+        if let Some(hwnd) = hwnd {
+            if let Some(owner) = modal_owner {
+                /*
+                    Enable the owner before the modal window is destroyed. Otherwise windows activates a window of
+                    another app, because the owner can't be activated while it is disabled.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-enablewindow
+                */
+                unsafe { EnableWindow(owner, true) };
+            }
+            // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-destroywindow
+            unsafe { DestroyWindow(hwnd) };
+        }
+    }
+
+    fn set_main_window(&self, id: Option<WindowId>) {
+        manager::with_manager(|manager| manager.set_main_window(id));
+    }
+
+    fn pump_events(&self, control_flow: ControlFlow) -> Option<usize> {
+        let (dispatched, exit_code): (bool, Option<usize>) = dispatch_pending();
+        if dispatched || exit_code.is_some() {
+            return exit_code;
+        }
+        let timeout: u32 = control_flow.timeout_ms(Instant::now());
+        if timeout > 0 {
+            /*
+                Sleeps until a message is posted to the queue of this thread, or the timeout expires. We don't pass
+                any handles so this only waits for messages. MWMO_INPUTAVAILABLE also returns for messages that
+                are in the queue already but were seen by an earlier PeekMessage.
+
+                For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-msgwaitformultipleobjectsex
+            */
+            unsafe {
+                MsgWaitForMultipleObjectsEx(None, timeout, QS_ALLINPUT, MWMO_INPUTAVAILABLE);
+            }
+        }
+        return dispatch_pending().1;
+    }
+
+    fn message_time(&self) -> u32 {
+        /*
+            GetMessageTime is the tick count of when the current message was posted, so it runs on the same clock.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/sysinfoapi/nf-sysinfoapi-gettickcount
+        */
+        return unsafe { GetTickCount() };
+    }
+
+    fn monitors(&self) -> Vec<Monitor> {
+        let mut handles: Vec<HMONITOR> = vec![];
+        /*
+            Calls the callback once for every monitor of the desktop.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-enumdisplaymonitors
+        */
+        unsafe {
+            EnumDisplayMonitors(
+                HDC::default(),
+                None,
+                Some(collect_monitor),
+                LPARAM(&mut handles as *mut Vec<HMONITOR> as isize),
+            )
+        };
+        return handles.into_iter().filter_map(monitor_info).collect();
+    }
+
+    fn placement(&self, id: WindowId) -> Option<Placement> {
+        let hwnd: HWND = manager::with_manager(|manager| manager.hwnd(id))?;
+        /*
+            The placement has the rect the window has when it isn't maximized or minimized, that is the one we want
+            to restore. It is in workspace coordinates, see Monitor::workspace_offset().
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getwindowplacement
+        */
+        let mut placement: WINDOWPLACEMENT = WINDOWPLACEMENT {
+            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetWindowPlacement(hwnd, &mut placement) }.as_bool() {
+            return None;
+        }
+        let style: WINDOW_STYLE =
+            WINDOW_STYLE(unsafe { GetWindowLongPtrW(hwnd, GWL_STYLE) } as u32);
+        let ex_style: WINDOW_EX_STYLE =
+            WINDOW_EX_STYLE(unsafe { GetWindowLongPtrW(hwnd, GWL_EXSTYLE) } as u32);
+        let monitor: HMONITOR = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        let (dx, dy): (i32, i32) = workspace_offset(monitor, ex_style);
+        let rect: Rect = placement.rcNormalPosition.into();
+
+        return Some(Placement {
+            rect: Rect {
+                left: rect.left + dx,
+                top: rect.top + dy,
+                right: rect.right + dx,
+                bottom: rect.bottom + dy,
+            },
+            maximized: unsafe { IsZoomed(hwnd) }.as_bool(),
+            // Visibility is not part of the placement, set_visible() is
+            style: WINDOW_STYLE(style.0 & !WS_VISIBLE.0),
+            ex_style,
+        });
+    }
+
+    fn set_placement(&self, id: WindowId, placement: &Placement) {
+        let hwnd: HWND = match manager::with_manager(|manager| manager.hwnd(id)) {
+            Some(hwnd) => hwnd,
+            None => return,
+        };
+        if let Some(state) = unsafe { state::lookup(hwnd) } {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            state.style = placement.style;
+            state.ex_style = placement.ex_style;
+        }
+
+        let visible: bool = unsafe { IsWindowVisible(hwnd) }.as_bool();
+        let mut style: WINDOW_STYLE = placement.style;
+        if visible {
+            style |= WS_VISIBLE;
+        }
+        /*
+            A new style only takes effect after SetWindowPos with SWP_FRAMECHANGED, which makes windows calculate
+            the frame again. The rect below is an outer rect for the new frame, so this has to come first.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowlongptrw
+        */
+        unsafe {
+            SetWindowLongPtrW(hwnd, GWL_STYLE, style.0 as isize);
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, placement.ex_style.0 as isize);
+            SetWindowPos(
+                hwnd,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+        }
+
+        let rect: RECT = placement.rect.into();
+        let monitor: HMONITOR = unsafe { MonitorFromRect(&rect, MONITOR_DEFAULTTONEAREST) };
+        let (dx, dy): (i32, i32) = workspace_offset(monitor, placement.ex_style);
+        let show: SHOW_WINDOW_CMD = match (visible, placement.maximized) {
+            (false, _) => SW_HIDE,
+            (true, true) => SW_SHOWMAXIMIZED,
+            (true, false) => SW_SHOWNORMAL,
+        };
+        /*
+            SetWindowPlacement sets the restored rect and the maximized state at once, so a window that is maximized
+            again still remembers where it came from.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowplacement
+        */
+        let window_placement: WINDOWPLACEMENT = WINDOWPLACEMENT {
+            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+            showCmd: show,
+            rcNormalPosition: RECT {
+                left: rect.left - dx,
+                top: rect.top - dy,
+                right: rect.right - dx,
+                bottom: rect.bottom - dy,
+            },
+            ..Default::default()
+        };
+        unsafe { SetWindowPlacement(hwnd, &window_placement) };
+    }
+
+    fn set_video_mode(&self, id: MonitorId, mode: Option<VideoMode>) -> bool {
+        let mut info: MONITORINFOEXW = MONITORINFOEXW::default();
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if !unsafe { GetMonitorInfoW(HMONITOR(id.0), &mut info as *mut _ as *mut MONITORINFO) }
+            .as_bool()
+        {
+            return false;
+        }
+        let device: PCWSTR = PCWSTR(info.szDevice.as_ptr());
+
+        /*
+            CDS_FULLSCREEN makes the change temporary: windows goes back to the mode of the settings when the app
+            exits. Passing no mode goes back right away.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-changedisplaysettingsexw
+        */
+        let result: DISP_CHANGE = match mode {
+            None => unsafe {
+                ChangeDisplaySettingsExW(device, None, HWND::default(), CDS_TYPE(0), None)
+            },
+            Some(mode) => {
+                let devmode: DEVMODEW = DEVMODEW {
+                    dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+                    dmFields: DM_PELSWIDTH | DM_PELSHEIGHT | DM_BITSPERPEL | DM_DISPLAYFREQUENCY,
+                    dmPelsWidth: mode.width,
+                    dmPelsHeight: mode.height,
+                    dmBitsPerPel: mode.bit_depth,
+                    dmDisplayFrequency: mode.refresh_rate,
+                    ..Default::default()
+                };
+                unsafe {
+                    ChangeDisplaySettingsExW(
+                        device,
+                        Some(&devmode),
+                        HWND::default(),
+                        CDS_FULLSCREEN,
+                        None,
+                    )
+                }
+            }
+        };
+        return result == DISP_CHANGE_SUCCESSFUL;
+    }
+
+    fn enable_dpi_awareness(&self) -> bool {
+        /*
+            Per monitor v2 makes windows send WM_DPICHANGED when a window moves to a monitor with another DPI and
+            scales the title bar and borders for us. It needs Windows 10 1703, older versions return false.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/hidpi/high-dpi-desktop-application-development-on-windows
+        */
+        return unsafe {
+            SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).as_bool()
+        };
+    }
+
+    fn set_title(&self, id: WindowId, title: &str) -> Result<(), NulError> {
+        let title: WideCString = WideCString::new(title)?;
+        if let Some(hwnd) = manager::with_manager(|manager| manager.hwnd(id)) {
+            // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setwindowtextw
+            unsafe { SetWindowTextW(hwnd, title.as_pcwstr()) };
+        }
+        return Ok(());
+    }
+
+    fn set_visible(&self, id: WindowId, visible: bool) {
+        if let Some(hwnd) = manager::with_manager(|manager| manager.hwnd(id)) {
+            // Sets the specified window's show state.
+            // Check for more info: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-showwindow
+            unsafe { ShowWindow(hwnd, if visible { SW_SHOW } else { SW_HIDE }) };
+        }
+    }
+
+    fn request_redraw(&self, id: WindowId) {
+        if let Some(hwnd) = manager::with_manager(|manager| manager.hwnd(id)) {
+            /*
+                Invalidating the client area makes windows send a WM_PAINT once the queue is empty. More calls
+                before that still give one WM_PAINT.
+
+                For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-invalidaterect
+            */
+            unsafe { InvalidateRect(hwnd, None, false) };
+        }
+    }
+
+    fn create_waker(&self, id: WindowId) -> Waker {
+        // HWND isn't Send, but the handle itself can be used from any thread
+        let hwnd: isize = manager::with_manager(|manager| manager.hwnd(id))
+            .map(|hwnd| hwnd.0)
+            .unwrap_or(0);
+        return Arc::new(move || {
+            /*
+                WM_APP is free for the app to use. The message itself does nothing, it only makes
+                MsgWaitForMultipleObjects return. PostMessage fails once the window is destroyed.
+
+                For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-postmessagew
+            */
+            return unsafe { PostMessageW(HWND(hwnd), WM_APP, WPARAM(0), LPARAM(0)).as_bool() };
+        });
+    }
+
+    fn set_menu(&self, id: WindowId, menu: Option<&Menu>) {
+        let (hwnd, state): (HWND, Rc<RefCell<WindowState>>) =
+            match manager::with_manager(|manager| {
+                manager
+                    .get(id)
+                    .map(|entry| (entry.hwnd, entry.state.clone()))
+            }) {
+                Some(entry) => entry,
+                None => return,
+            };
+        /*
+            SetMenu doesn't destroy the menu bar it replaces, so we do. DrawMenuBar is needed when the window is
+            already visible.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setmenu
+        */
+        unsafe {
+            let old: HMENU = GetMenu(hwnd);
+            let new: HMENU = menu
+                .and_then(native_menu::create_menu_bar)
+                .unwrap_or_default();
+            SetMenu(hwnd, new);
+            if old.0 != 0 {
+                DestroyMenu(old);
+            }
+            DrawMenuBar(hwnd);
+        }
+
+        // The window keeps its outer size, grow or shrink it so the client area keeps its size instead
+        let size: Option<(i32, i32)> = {
+            let state: RefMut<WindowState> = state.borrow_mut();
+            let windowed: bool =
+                state.window_mode == WindowMode::Windowed && !state.maximized && !state.minimized;
+            windowed.then(|| {
+                let frame: (i32, i32) = state.frame_size();
+                (state.client_width + frame.0, state.client_height + frame.1)
+            })
+        };
+        if let Some((width, height)) = size {
+            unsafe {
+                SetWindowPos(
+                    hwnd,
+                    HWND::default(),
+                    0,
+                    0,
+                    width,
+                    height,
+                    SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+                )
+            };
+        }
+    }
+
+    fn show_popup_menu(&self, id: WindowId, menu: &Menu, position: ClientPoint) -> Option<MenuId> {
+        let hwnd: HWND = manager::with_manager(|manager| manager.hwnd(id))?;
+        return native_menu::show_popup(hwnd, menu, position);
+    }
+
+    fn set_chrome(&self, id: WindowId, _chrome: Option<&ChromeLayout>) {
+        let hwnd: HWND = match manager::with_manager(|manager| manager.hwnd(id)) {
+            Some(hwnd) => hwnd,
+            None => return,
+        };
+        // Sends WM_NCCALCSIZE, which answers from the chrome in the state, and WM_SIZE for the new client size
+        unsafe {
+            SetWindowPos(
+                hwnd,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )
+        };
+    }
+
+    fn caption_command(&self, id: WindowId, button: CaptionButton) {
+        if let Some(hwnd) = manager::with_manager(|manager| manager.hwnd(id)) {
+            post_caption_command(hwnd, button);
+        }
+    }
+
+    fn show_message_box(&self, message_box: &MessageBox) -> DialogResult {
+        let owner: HWND = message_box
+            .owner
+            .and_then(|owner| manager::with_manager(|manager| manager.hwnd(owner)))
+            .unwrap_or_default();
+        let buttons: MESSAGEBOX_STYLE = match message_box.buttons {
+            MessageButtons::Ok => MB_OK,
+            MessageButtons::OkCancel => MB_OKCANCEL,
+            MessageButtons::YesNo => MB_YESNO,
+            MessageButtons::YesNoCancel => MB_YESNOCANCEL,
+            MessageButtons::RetryCancel => MB_RETRYCANCEL,
+            MessageButtons::AbortRetryIgnore => MB_ABORTRETRYIGNORE,
+            MessageButtons::CancelTryContinue => MB_CANCELTRYCONTINUE,
+        };
+        let icon: MESSAGEBOX_STYLE = match message_box.icon {
+            MessageIcon::None => MESSAGEBOX_STYLE(0),
+            MessageIcon::Information => MB_ICONINFORMATION,
+            MessageIcon::Warning => MB_ICONWARNING,
+            MessageIcon::Error => MB_ICONERROR,
+            MessageIcon::Question => MB_ICONQUESTION,
+        };
+        let default_button: MESSAGEBOX_STYLE =
+            [MB_DEFBUTTON1, MB_DEFBUTTON2, MB_DEFBUTTON3][message_box.default_index()];
+        // Both strings have to live until the message box is closed
+        let text: WideCString = WideCString::new_truncated(&message_box.text);
+        let title: WideCString = WideCString::new_truncated(&message_box.title);
+
+        /*
+            Creates, displays, and operates a message box. The buttons are in the language of the system user
+            interface, language 0. The owner is disabled while the box is open. It returns 0 when the box
+            couldn't be created.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxexw
+        */
+        let result: MESSAGEBOX_RESULT = unsafe {
+            MessageBoxExW(
+                owner,
+                text.as_pcwstr(),
+                title.as_pcwstr(),
+                buttons | icon | default_button,
+                0,
+            )
+        };
+        return match result {
+            IDOK => DialogResult::Ok,
+            IDCANCEL => DialogResult::Cancel,
+            IDYES => DialogResult::Yes,
+            IDNO => DialogResult::No,
+            IDRETRY => DialogResult::Retry,
+            IDABORT => DialogResult::Abort,
+            IDIGNORE => DialogResult::Ignore,
+            IDTRYAGAIN => DialogResult::TryAgain,
+            IDCONTINUE => DialogResult::Continue,
+            _ => message_box.buttons.close_result(),
+        };
+    }
+
+    fn show_file_dialog(&self, dialog: &FileDialog) -> Vec<PathBuf> {
+        let owner: HWND = dialog
+            .owner
+            .and_then(|owner| manager::with_manager(|manager| manager.hwnd(owner)))
+            .unwrap_or_default();
+        return file_dialog::show(owner, dialog);
+    }
+
+    fn set_cursor_icon(&self, id: WindowId, icon: CursorIcon) {
+        let state: Option<Rc<RefCell<WindowState>>> =
+            manager::with_manager(|manager| manager.get(id).map(|entry| entry.state.clone()));
+        if let Some(state) = state {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            state.cursor_icon = icon;
+            if state.mouse.is_in_window {
+                // Don't wait for the next WM_SETCURSOR when the cursor is already over the window
+                unsafe { SetCursor(load_cursor(icon)) };
+            }
+        }
+    }
+
+    fn set_cursor_visible(&self, visible: bool) {
+        /*
+            ShowCursor increments or decrements a display counter, the cursor is shown when it is 0 or higher.
+            We keep calling it until the counter is on the right side of 0 so it doesn't matter how often this
+            was called before.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-showcursor
+        */
+        unsafe {
+            if visible {
+                while ShowCursor(true) < 0 {}
+            } else {
+                while ShowCursor(false) >= 0 {}
+            }
+        }
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        let bytes: Vec<u8> = read_clipboard(&[CF_UNICODETEXT])?.1;
+        let text: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return Some(from_wide_lossy(&text));
+    }
+
+    fn clipboard_image(&self) -> Option<ClipboardImage> {
+        // Windows makes CF_DIBV5 out of a CF_DIB and the other way around, the V5 one can have the alpha
+        let bytes: Vec<u8> = read_clipboard(&[CF_DIBV5, CF_DIB])?.1;
+        return ClipboardImage::from_dib(&bytes);
+    }
+
+    fn clipboard_data(&self, format: &ClipboardFormat) -> Option<Vec<u8>> {
+        return read_clipboard(&[format.id]).map(|(_, bytes)| bytes);
+    }
+
+    fn set_clipboard(&self, items: &[ClipboardItem]) -> bool {
+        /*
+            EmptyClipboard gives the clipboard to us, after that every SetClipboardData adds a format. Apps that
+            paste pick the first format they understand.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/dataxchg/using-the-clipboard#copying-information-to-the-clipboard
+        */
+        unsafe {
+            if !OpenClipboard(None).as_bool() {
+                return false;
+            }
+            EmptyClipboard();
+            let mut stored: bool = true;
+            for item in items {
+                stored &= match item {
+                    ClipboardItem::Text(text) => {
+                        let text: WideCString = WideCString::new_truncated(text);
+                        let bytes: Vec<u8> = text
+                            .as_slice_with_nul()
+                            .iter()
+                            .flat_map(|unit| unit.to_le_bytes())
+                            .collect();
+                        write_clipboard(CF_UNICODETEXT, &bytes)
+                    }
+                    ClipboardItem::Image(image) => write_clipboard(CF_DIBV5, &image.to_dib_v5()),
+                    ClipboardItem::Data(format, bytes) => write_clipboard(format.id, bytes),
+                };
+            }
+            CloseClipboard();
+            return stored;
+        }
+    }
+
+    fn register_clipboard_format(&self, name: &str) -> Option<ClipboardFormat> {
+        let wide_name: WideCString = WideCString::new(name).ok()?;
+        // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerclipboardformatw
+        let id: u32 = unsafe { RegisterClipboardFormatW(wide_name.as_pcwstr()) };
+        if id == 0 {
+            return None;
+        }
+        return Some(ClipboardFormat {
+            id,
+            name: name.to_string(),
+        });
+    }
+
+    fn listen_clipboard(&self, id: WindowId, listen: bool) -> bool {
+        let hwnd: HWND = match manager::with_manager(|manager| manager.hwnd(id)) {
+            Some(hwnd) => hwnd,
+            None => return false,
+        };
+        /*
+            A listener gets WM_CLIPBOARDUPDATE after every change of the clipboard, also the ones of this app.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/dataxchg/using-the-clipboard#creating-a-clipboard-format-listener
+        */
+        unsafe {
+            if listen {
+                return AddClipboardFormatListener(hwnd).as_bool();
+            }
+            return RemoveClipboardFormatListener(hwnd).as_bool();
+        }
+    }
+
+    fn present(&self, id: WindowId, width: u32, height: u32, pixels: &[u32]) {
+        let (hwnd, client_width, client_height): (HWND, i32, i32) =
+            match manager::with_manager(|manager| {
+                manager.get(id).map(|entry| {
+                    let state: std::cell::Ref<WindowState> = entry.state.borrow();
+                    (entry.hwnd, state.client_width, state.client_height)
+                })
+            }) {
+                Some(window) => window,
+                None => return,
+            };
+        if pixels.len() < (width * height) as usize {
+            return;
+        }
+
+        let info: BITMAPINFO = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width as i32,
+                // A negative height means the rows go from the top to the bottom
+                biHeight: -(height as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        /*
+            Copies the pixels to the client area and stretches them when the frame isn't the size of the client
+            area.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/api/wingdi/nf-wingdi-stretchdibits
+        */
+        unsafe {
+            let hdc: HDC = GetDC(hwnd);
+            StretchDIBits(
+                hdc,
+                0,
+                0,
+                client_width,
+                client_height,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                Some(pixels.as_ptr() as *const std::ffi::c_void),
+                &info,
+                DIB_RGB_COLORS,
+                SRCCOPY,
+            );
+            ReleaseDC(hwnd, hdc);
+        }
+    }
+
+    fn last_error(&self) -> Option<String> {
+        let err_code: u32 = unsafe { GetLastError().0 }; // Get the last WIN32_ERROR and get the id from it (u32)
+        let mut err_buffer: *mut u8 = std::ptr::null_mut(); // Create a buffer for windows where it should store the error message
+        if err_code == 0 {
+            // If the error code == 0, there is no error. So there is no need for priting a succes error :)
+            return None;
+        }
+
+        let err_msg_lenght: u32 = unsafe {
+            FormatMessageA(
+                /*
+                    Formats a message string. The function requires a message definition as input.
+
+                    The function finds the message definition in a message table resource based on
+                    a message identifier (HRESULT/GetLastError()) and a language identifier (LCID). The function copies the
+                    formatted message text to an output buffer, processing any embedded insert
+                    sequences if requested.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-formatmessage
+                */
+                FORMAT_MESSAGE_FROM_SYSTEM | // Use system message tables to retrieve error text
+                FORMAT_MESSAGE_ALLOCATE_BUFFER, // Allocate buffer on local heap for error text
+                None, // Location of the message definition. We use the systems error table so it has to be None
+                err_code, // The Errorcode you want a description about
+                0,    // LCID (language code identifier) ->
+                /*
+                This one is a bit weird. In the description the FormatMessage
+                function it says we need an LANGID but there is nothing like that in the windows crate. This crate uses a LCID.
+                0 means that it will use your system languague. 1033 means US.
+                For more info see: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-lcid/70feba9f-294e-491e-b6eb-56532684c37f
+                */
+                PSTR(&mut err_buffer as *mut _ as *mut _), // Man... this took me ages to get working. ->
+                /*
+                A pointer to a buffer that receives the null-terminated string that specifies the formatted message.
+                    This buffer cannot be larger than 64K bytes.
+                    ---
+                    We first create a mutable null pointer and set the type to a u8 like this:
+                    let mut err_buffer: *mut u8 = std::ptr::null_mut();
+                    Then we use the PSTR constructor to create a pointer to a null-terminated string of 8-bit Windows (ANSI) characters.
+                    like this:
+                        PSTR();
+                    Then we put in a mutable reference to the error_buffer and cast it to an mutable pointer (I have no clue how and why this works)
+                        PSTR(&mut err_buffer as *mut _ as *mut _);
+                */
+                0,
+                /*
+                If the FORMAT_MESSAGE_ALLOCATE_BUFFER flag is not set, this parameter specifies the size of the output buffer, in TCHARs. If
+                FORMAT_MESSAGE_ALLOCATE_BUFFER is set, this parameter specifies the minimum number of TCHARs to allocate for an output buffer.
+                */
+                None, /*
+                          An array of values that are used as insert values in the formatted message.
+                          For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-formatmessage#parameters
+                      */
+            )
+        };
+
+        if err_msg_lenght == 0 {
+            /*
+                If the message buffer is empty, there is no available error description
+                Could be caused by an invalid error code or an invalid or not correctly installed LCID
+            */
+
+            return Some(format!(
+                "Code: {}: Unable to find error description",
+                err_code
+            ));
+        }
+
+        /*
+            If there is an error, print all the return codes,
+            println!("Unsuccesfull exit with codes lastError: {}", unsafe { GetLastError().0 });
+            and print out the description of the code
+        */
+        let slice: Vec<u8> =
+            unsafe { std::slice::from_raw_parts(err_buffer, (err_msg_lenght - 2) as _).to_vec() };
+
+        return Some(format!(
+            "Code {}: {}",
+            err_code,
+            String::from_utf8(slice).unwrap()
+        ));
+    }
+}
+
+/// Dispatch all the messages that are in the queue. Returns whether a message was dispatched and the exit code
+/// on WM_QUIT.
+fn dispatch_pending() -> (bool, Option<usize>) {
+    let mut msg: MSG = MSG::default();
+    let mut dispatched: bool = false;
+    while unsafe { PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() } {
+        if msg.message == WM_QUIT {
+            return (dispatched, Some(msg.wParam.0));
+        }
+        unsafe { TranslateMessage(&msg) };
+        unsafe { DispatchMessageW(&msg) };
+        dispatched = true;
+    }
+    return (dispatched, None);
+}
+
+/**
+    Copy the bytes of the first of `formats` the clipboard has. The data is a global memory handle that belongs to
+    the clipboard, so we only lock it to copy the bytes and don't free it.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/dataxchg/using-the-clipboard#pasting-information-from-the-clipboard
+*/
+fn read_clipboard(formats: &[u32]) -> Option<(u32, Vec<u8>)> {
+    unsafe {
+        if !OpenClipboard(None).as_bool() {
+            return None;
+        }
+        let mut result: Option<(u32, Vec<u8>)> = None;
+        for &format in formats {
+            if !IsClipboardFormatAvailable(format).as_bool() {
+                continue;
+            }
+            if let Ok(handle) = GetClipboardData(format) {
+                let data: *const u8 = GlobalLock(HGLOBAL(handle.0)) as *const u8;
+                if !data.is_null() {
+                    let length: usize = GlobalSize(HGLOBAL(handle.0));
+                    result = Some((format, std::slice::from_raw_parts(data, length).to_vec()));
+                    GlobalUnlock(HGLOBAL(handle.0));
+                    break;
+                }
+            }
+        }
+        CloseClipboard();
+        return result;
+    }
+}
+
+/**
+    Put `bytes` on the opened clipboard. The clipboard takes ownership of the memory when SetClipboardData succeeds.
+    It has to be GMEM_MOVEABLE memory, see https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-setclipboarddata
+*/
+unsafe fn write_clipboard(format: u32, bytes: &[u8]) -> bool {
+    let memory: HGLOBAL = match GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1)) {
+        Ok(memory) => memory,
+        Err(_) => return false,
+    };
+    let data: *mut u8 = GlobalLock(memory) as *mut u8;
+    if !data.is_null() {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+        GlobalUnlock(memory);
+    }
+    let stored: bool = !data.is_null() && SetClipboardData(format, HANDLE(memory.0)).is_ok();
+    if !stored {
+        // We still own the memory when the clipboard didn't take it
+        let _ = GlobalFree(memory);
+    }
+    return stored;
+}
+
+/// The system cursor for a [CursorIcon].
+fn load_cursor(icon: CursorIcon) -> HCURSOR {
+    let name: PCWSTR = match icon {
+        CursorIcon::Arrow => IDC_ARROW,
+        CursorIcon::Text => IDC_IBEAM,
+        CursorIcon::Hand => IDC_HAND,
+        CursorIcon::Crosshair => IDC_CROSS,
+        CursorIcon::Wait => IDC_WAIT,
+        CursorIcon::ResizeHorizontal => IDC_SIZEWE,
+        CursorIcon::ResizeVertical => IDC_SIZENS,
+        CursorIcon::Move => IDC_SIZEALL,
+        CursorIcon::NotAllowed => IDC_NO,
+    };
+    return unsafe { LoadCursorW(None, name).unwrap_or_default() };
+}
+
+unsafe extern "system" fn collect_monitor(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    data: LPARAM,
+) -> BOOL {
+    let handles: &mut Vec<HMONITOR> = &mut *(data.0 as *mut Vec<HMONITOR>);
+    handles.push(monitor);
+    return TRUE;
+}
+
+/**
+    Everything we know about a monitor. The name comes from the monitor device behind the display device, like
+    "Generic PnP Monitor", and falls back to the display device name, like "\\.\DISPLAY1".
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-getmonitorinfow
+*/
+fn monitor_info(monitor: HMONITOR) -> Option<Monitor> {
+    let mut info: MONITORINFOEXW = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    if !unsafe { GetMonitorInfoW(monitor, &mut info as *mut _ as *mut MONITORINFO) }.as_bool() {
+        return None;
+    }
+    let device: PCWSTR = PCWSTR(info.szDevice.as_ptr());
+
+    let mut display_device: DISPLAY_DEVICEW = DISPLAY_DEVICEW {
+        cb: std::mem::size_of::<DISPLAY_DEVICEW>() as u32,
+        ..Default::default()
+    };
+    let name: String =
+        match unsafe { EnumDisplayDevicesW(device, 0, &mut display_device, 0) }.as_bool() {
+            true => from_wide_lossy(&display_device.DeviceString),
+            false => from_wide_lossy(&info.szDevice),
+        };
+
+    // A frequency of 0 or 1 means the hardware default
+    let mut devmode: DEVMODEW = DEVMODEW {
+        dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    let refresh_rate: Option<u32> = match unsafe {
+        EnumDisplaySettingsW(device, ENUM_CURRENT_SETTINGS, &mut devmode)
+    }
+    .as_bool()
+    {
+        true if devmode.dmDisplayFrequency > 1 => Some(devmode.dmDisplayFrequency),
+        _ => None,
+    };
+
+    let mut video_modes: Vec<VideoMode> = vec![];
+    let mut index: u32 = 0;
+    while unsafe { EnumDisplaySettingsW(device, ENUM_DISPLAY_SETTINGS_MODE(index), &mut devmode) }
+        .as_bool()
+    {
+        let mode: VideoMode = VideoMode {
+            width: devmode.dmPelsWidth,
+            height: devmode.dmPelsHeight,
+            refresh_rate: devmode.dmDisplayFrequency,
+            bit_depth: devmode.dmBitsPerPel,
+        };
+        // Modes that only differ in scaling or orientation are listed more than once
+        if !video_modes.contains(&mode) {
+            video_modes.push(mode);
+        }
+        index += 1;
+    }
+
+    let (mut dpi_x, mut dpi_y): (u32, u32) = (0, 0);
+    let dpi: u32 =
+        match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+            Ok(()) if dpi_x != 0 => dpi_x,
+            _ => BASE_DPI,
+        };
+
+    return Some(Monitor {
+        id: MonitorId(monitor.0),
+        name,
+        bounds: info.monitorInfo.rcMonitor.into(),
+        work_area: info.monitorInfo.rcWork.into(),
+        dpi,
+        refresh_rate,
+        primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+        video_modes,
+    });
+}
+
+/// See [Monitor::workspace_offset()]. Tool windows use screen coordinates in their placement.
+fn workspace_offset(monitor: HMONITOR, ex_style: WINDOW_EX_STYLE) -> (i32, i32) {
+    if ex_style.contains(WS_EX_TOOLWINDOW) {
+        return (0, 0);
+    }
+    return match monitor_info(monitor) {
+        Some(monitor) => monitor.workspace_offset(),
+        None => (0, 0),
+    };
+}
+
+/**
+    The monitor a window with its top-left corner at `position` opens on. [None] gives the primary monitor, where
+    windows at the default position and centered windows open.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-monitorfrompoint
+*/
+fn monitor_at(position: Option<PhysicalPoint>) -> HMONITOR {
+    return match position {
+        Some(position) => unsafe {
+            MonitorFromPoint(
+                POINT {
+                    x: position.x,
+                    y: position.y,
+                },
+                MONITOR_DEFAULTTONEAREST,
+            )
+        },
+        None => unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) },
+    };
+}
+
+/**
+    The DPI of `monitor`. A process that isn't DPI aware always gets [BASE_DPI].
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/shellscalingapi/nf-shellscalingapi-getdpiformonitor
+*/
+fn monitor_dpi(monitor: HMONITOR) -> u32 {
+    let (mut dpi_x, mut dpi_y): (u32, u32) = (0, 0);
+    return match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x != 0 => dpi_x,
+        _ => BASE_DPI,
+    };
+}
+
+extern "system" fn wndproc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    /*
+        It is very hard to explain how this works without typing a lot of text so i'll just refer you to
+        the great video by ChiliTomatoNoodle (https://youtu.be/UUbXK4G_NCM). It explains how the window
+        messages work and how to build a good system around it.
+
+        For more info about wndproc see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nc-winuser-wndproc
+        And for a list with all the messages see: https://wiki.winehq.org/List_Of_Windows_Messages
+    */
+
+    unsafe {
+        match msg {
+            WM_NCCREATE => {
+                state::attach(hwnd, lparam);
+                return DefWindowProcW(hwnd, msg, wparam, lparam);
+            }
+            WM_NCDESTROY => {
+                drop_target::revoke(hwnd);
+                state::release(hwnd);
+                return DefWindowProcW(hwnd, msg, wparam, lparam);
+            }
+            _ => {}
+        }
+
+        /*
+            The borrows of the state below end before any Win32 call that can send a message. Functions like SetCapture and
+            DestroyWindow send messages to this wndproc right away, so we can't hold a borrow while calling them.
+        */
+        let state: &RefCell<WindowState> = match state::lookup(hwnd) {
+            // Still borrowed means the app holds a guard like Window::keyboard() across a call that sends this
+            // message. A panic can't unwind out of the wndproc, so the state misses the message instead.
+            Some(state) if state.try_borrow_mut().is_ok() => state,
+            _ => return DefWindowProcW(hwnd, msg, wparam, lparam),
+        };
+
+        match msg {
+            WM_GETMINMAXINFO => {
+                /*
+                    Windows asks for the minimum and maximum tracking size before the user resizes the window.
+                    Those are outer sizes, our limits are client sizes, so we add the title bar and borders.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-getminmaxinfo
+                */
+                let state: RefMut<WindowState> = state.borrow_mut();
+                let frame: (i32, i32) = state.frame_size();
+                let info: &mut MINMAXINFO = &mut *(lparam.0 as *mut MINMAXINFO);
+                if let Some((width, height)) = state.min_size {
+                    info.ptMinTrackSize.x = width + frame.0;
+                    info.ptMinTrackSize.y = height + frame.1;
+                }
+                if let Some((width, height)) = state.max_size {
+                    info.ptMaxTrackSize.x = width + frame.0;
+                    info.ptMaxTrackSize.y = height + frame.1;
+                }
+                return LRESULT(0);
+            }
+            WM_SIZING => {
+                /*
+                    Sent while the user drags a border. The lParam points to the window rect that is about to be
+                    used, we can change it to keep the window within its limits.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-sizing
+                */
+                let state: RefMut<WindowState> = state.borrow_mut();
+                let rect: &mut RECT = &mut *(lparam.0 as *mut RECT);
+                *rect = constrain_sizing_rect(
+                    (*rect).into(),
+                    wparam.0 as u32,
+                    state.frame_size(),
+                    state.min_size,
+                    state.max_size,
+                )
+                .into();
+                return LRESULT(1);
+            }
+            WM_DPICHANGED => {
+                /*
+                    Sent when the window moved to a monitor with another DPI or the scale of its monitor changed. The
+                    low word of the wParam is the new DPI, the lParam points to the window rect windows suggests for
+                    it. Using that rect keeps the window under the cursor while it is dragged across monitors.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/hidpi/wm-dpichanged
+                */
+                let dpi: u32 = (wparam.0 & 0xFFFF) as u32;
+                let suggested: RECT = *(lparam.0 as *const RECT);
+                let fullscreen: bool = state.borrow().window_mode != WindowMode::Windowed;
+                if fullscreen {
+                    // The window already covers the monitor, the suggested rect would scale it past the edges
+                    let mut state: RefMut<WindowState> = state.borrow_mut();
+                    let (width, height): (i32, i32) = (state.client_width, state.client_height);
+                    let event: WindowEvent = state.on_dpi_changed(dpi, width, height);
+                    state.push_window_event(event);
+                    return LRESULT(0);
+                }
+                {
+                    let mut state: RefMut<WindowState> = state.borrow_mut();
+                    let frame: (i32, i32) = state.frame_size_for_dpi(dpi);
+                    let event: WindowEvent = state.on_dpi_changed(
+                        dpi,
+                        suggested.right - suggested.left - frame.0,
+                        suggested.bottom - suggested.top - frame.1,
+                    );
+                    state.push_window_event(event);
+                }
+                // Sends WM_SIZE, which updates the client size
+                SetWindowPos(
+                    hwnd,
+                    HWND::default(),
+                    suggested.left,
+                    suggested.top,
+                    suggested.right - suggested.left,
+                    suggested.bottom - suggested.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE,
+                );
+                return LRESULT(0);
+            }
+            WM_DROPFILES => {
+                /*
+                    Sent for a drop on a window without an OLE drop target, see drop_target::register(). The wParam
+                    is a HDROP with the paths, which we have to free.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/shell/wm-dropfiles
+                */
+                let hdrop: HDROP = HDROP(wparam.0 as isize);
+                let paths: Vec<PathBuf> = drop_target::paths_from_hdrop(hdrop);
+                let mut position: POINT = POINT { x: 0, y: 0 };
+                DragQueryPoint(hdrop, &mut position);
+                DragFinish(hdrop);
+                if !paths.is_empty() {
+                    state
+                        .borrow_mut()
+                        .push_event(Event::DragDrop(DragDropEvent::DroppedFiles {
+                            paths,
+                            position: ClientPoint {
+                                x: position.x,
+                                y: position.y,
+                            },
+                        }));
+                }
+                return LRESULT(0);
+            }
+            WM_CONTEXTMENU => {
+                /*
+                    Sent on a right click and for Shift+F10 or the menu key, the lParam is the cursor in screen
+                    coordinates or -1, -1 for the keyboard. Clicks outside of the client area go to DefWindowProc,
+                    which shows the window menu for the title bar.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/menurc/wm-contextmenu
+                */
+                let context_menu: Option<Menu> = state.borrow().context_menu.clone();
+                let context_menu: Menu = match context_menu {
+                    Some(context_menu) => context_menu,
+                    None => return DefWindowProcW(hwnd, msg, wparam, lparam),
+                };
+                let points: POINTS = make_points(lparam);
+                let keyboard: bool = points.x == -1 && points.y == -1;
+                let position: ClientPoint = if keyboard {
+                    ClientPoint { x: 0, y: 0 }
+                } else {
+                    let mut point: POINT = POINT {
+                        x: points.x.into(),
+                        y: points.y.into(),
+                    };
+                    ScreenToClient(hwnd, &mut point);
+                    ClientPoint {
+                        x: point.x,
+                        y: point.y,
+                    }
+                };
+                // The title bar of a custom chrome shows the window menu as well
+                if !keyboard && state.borrow().hit_test(position) != HitTest::Client {
+                    return DefWindowProcW(hwnd, msg, wparam, lparam);
+                }
+                // No borrow while the menu is open, its message loop calls this wndproc
+                if let Some(id) = native_menu::show_popup(hwnd, &context_menu, position) {
+                    if context_menu.is_enabled(id) {
+                        state.borrow_mut().push_event(Event::MenuActivated(id));
+                    }
+                }
+                return LRESULT(0);
+            }
+            WM_NCCALCSIZE => {
+                /*
+                    Sent when windows calculates the client rect. With a wParam of TRUE the first rect of the lParam is
+                    the new window rect, and what we leave in it is the client rect. A custom chrome keeps the whole
+                    window, there is no native frame then but the style still has one for snapping and animations.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-nccalcsize
+                */
+                let frame: Option<(WINDOW_STYLE, WINDOW_EX_STYLE, u32)> = {
+                    let state: RefMut<WindowState> = state.borrow_mut();
+                    state
+                        .chrome
+                        .as_ref()
+                        .map(|_| (state.style, state.ex_style, state.dpi))
+                };
+                let (style, ex_style, dpi): (WINDOW_STYLE, WINDOW_EX_STYLE, u32) = match frame {
+                    Some(frame) if wparam.0 != 0 => frame,
+                    _ => return DefWindowProcW(hwnd, msg, wparam, lparam),
+                };
+                let params: &mut NCCALCSIZE_PARAMS = &mut *(lparam.0 as *mut NCCALCSIZE_PARAMS);
+                params.rgrc[0] = chrome_client_rect(
+                    params.rgrc[0].into(),
+                    style,
+                    ex_style,
+                    IsZoomed(hwnd).as_bool(),
+                    &FrameMetrics::for_dpi(dpi),
+                )
+                .into();
+                return LRESULT(0);
+            }
+            WM_NCHITTEST => {
+                /*
+                    Sent to find out which part of the window is under the cursor, the lParam is the cursor in screen
+                    coordinates. With a custom chrome the layout of the app answers, see ChromeLayout::hit_test().
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/inputdev/wm-nchittest
+                */
+                if state.borrow().chrome.is_none() {
+                    return DefWindowProcW(hwnd, msg, wparam, lparam);
+                }
+                let points: POINTS = make_points(lparam);
+                let mut point: POINT = POINT {
+                    x: points.x.into(),
+                    y: points.y.into(),
+                };
+                ScreenToClient(hwnd, &mut point);
+                let hit: HitTest = state.borrow().hit_test(ClientPoint {
+                    x: point.x,
+                    y: point.y,
+                });
+                return LRESULT(hit.code() as isize);
+            }
+            WM_NCMOUSEMOVE | WM_NCMOUSELEAVE | WM_NCLBUTTONDOWN | WM_NCLBUTTONUP => {
+                /*
+                    The mouse messages outside of the client area, the wParam is the hit-test code. For the caption
+                    buttons of a custom chrome we track the hover and pressed state and do the command on a click.
+                    DefWindowProc would draw the native buttons there. Everything else goes to DefWindowProc, which
+                    moves and resizes the window.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/inputdev/wm-ncmousemove
+                */
+                if state.borrow().chrome.is_none() {
+                    return DefWindowProcW(hwnd, msg, wparam, lparam);
+                }
+                let button: Option<CaptionButton> = match msg {
+                    WM_NCMOUSELEAVE => None,
+                    _ => HitTest::from_code(wparam.0 as u32).caption_button(),
+                };
+                let mut clicked: Option<CaptionButton> = None;
+                {
+                    let mut state: RefMut<WindowState> = state.borrow_mut();
+                    let event: Option<WindowEvent> = state.update_caption_buttons(
+                        |buttons: &mut CaptionButtonState| match msg {
+                            WM_NCMOUSEMOVE => buttons.on_hover(button),
+                            WM_NCMOUSELEAVE => buttons.on_leave(),
+                            WM_NCLBUTTONDOWN => match button {
+                                Some(button) => buttons.on_press(button),
+                                None => buttons.on_leave(),
+                            },
+                            _ => clicked = buttons.on_release(button),
+                        },
+                    );
+                    if let Some(event) = event {
+                        state.push_window_event(event);
+                    }
+                }
+                if msg == WM_NCMOUSEMOVE && button.is_some() {
+                    // Windows only sends WM_NCMOUSELEAVE after asking for it, and only once
+                    let mut track: TRACKMOUSEEVENT = TRACKMOUSEEVENT {
+                        cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                        dwFlags: TME_LEAVE | TME_NONCLIENT,
+                        hwndTrack: hwnd,
+                        dwHoverTime: 0,
+                    };
+                    TrackMouseEvent(&mut track);
+                }
+                if let Some(clicked) = clicked {
+                    post_caption_command(hwnd, clicked);
+                }
+                if button.is_some() {
+                    return LRESULT(0);
+                }
+                return DefWindowProcW(hwnd, msg, wparam, lparam);
+            }
+            WM_SETCURSOR => {
+                /*
+                    Sent when the cursor moves over the window. The low word of the lParam is the hit-test code, we only
+                    set our own cursor over the client area and let windows show the resize arrows on the borders.
+
+                    For more info see: https://learn.microsoft.com/en-us/windows/win32/menurc/wm-setcursor
+                */
+                if (lparam.0 & 0xFFFF) as u32 == HTCLIENT {
+                    let icon: CursorIcon = state.borrow().cursor_icon;
+                    SetCursor(load_cursor(icon));
+                    return LRESULT(1);
+                }
+                return DefWindowProcW(hwnd, msg, wparam, lparam);
+            }
+            _ => {}
+        }
+
+        // The decoding lives in translate(), here we only give it what it can't know and do the Win32 side effects
+        let events: Vec<Event> = {
+            let mut state: RefMut<WindowState> = state.borrow_mut();
+            if matches!(
+                msg,
+                WM_MOVE | WM_MOUSEWHEEL | WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP
+            ) {
+                state.client_origin = client_origin(hwnd);
+            }
+            state.message_time = GetMessageTime() as u32;
+
+            let events: Vec<Event> = translate(msg, wparam, lparam, &mut state);
+            for event in events.iter() {
+                state.push_event(event.clone());
+            }
+            events
+        };
+
+        for event in events.iter() {
+            match event {
+                // Capture the mouse so we keep getting WM_MOUSEMOVE (and thus the leave) outside of the window
+                Event::Mouse(MouseEvent {
+                    mouse_state: MouseState::Enter,
+                    ..
+                }) => {
+                    SetCapture(hwnd);
+                }
+                Event::Mouse(MouseEvent {
+                    mouse_state: MouseState::Leave,
+                    ..
+                }) => {
+                    ReleaseCapture();
+                }
+                Event::Window(WindowEvent::Destroyed) => {
+                    // Only quits when this was the last window or the main window
+                    manager::on_destroy(hwnd);
+                }
+                _ => {}
+            }
+        }
+
+        // With a menu bar Alt and F10 open it and Alt+letter opens a menu by its mnemonic, DefWindowProc does that
+        let menu_keys: bool =
+            matches!(msg, WM_SYSKEYDOWN | WM_SYSKEYUP) && state.borrow().menu.is_some();
+        if translate::is_handled(msg) && !menu_keys {
+            return LRESULT(0);
+        }
+        // WM_PAINT is validated by DefWindowProc and the pointer messages generate the legacy mouse messages there
+        return DefWindowProcW(hwnd, msg, wparam, lparam);
+    }
+}
+
+/**
+    Do what a click on a native caption button does. WM_SYSCOMMAND is what the native buttons send as well, so
+    DefWindowProc animates the window and asks to close with WM_CLOSE. It is posted so it never runs in the middle
+    of the message that clicked the button.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/menurc/wm-syscommand
+*/
+fn post_caption_command(hwnd: HWND, button: CaptionButton) {
+    unsafe {
+        let command: u32 = match button {
+            CaptionButton::Minimize => SC_MINIMIZE,
+            CaptionButton::Maximize if IsZoomed(hwnd).as_bool() => SC_RESTORE,
+            CaptionButton::Maximize => SC_MAXIMIZE,
+            CaptionButton::Close => SC_CLOSE,
+        };
+        PostMessageW(hwnd, WM_SYSCOMMAND, WPARAM(command as usize), LPARAM(0));
+    }
+}
+
+/// Get the top-left corner of the client area in screen coordinates. Used to turn the screen
+/// coordinates of WM_MOUSEWHEEL and the pointer messages into client coordinates.
+pub fn client_origin(hwnd: HWND) -> ScreenPoint {
+    let mut origin: POINT = POINT { x: 0, y: 0 };
+    // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-clienttoscreen
+    unsafe { ClientToScreen(hwnd, &mut origin) };
+    return origin.into();
+}
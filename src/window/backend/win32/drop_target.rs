@@ -0,0 +1,282 @@
+use std::{
+    cell::{Cell, RefCell},
+    ffi::c_void,
+    path::PathBuf,
+};
+
+use windows::{
+    core::{ComInterface, IUnknown, IUnknown_Vtbl, Interface, GUID, HRESULT},
+    Win32::{
+        Foundation::{E_NOINTERFACE, E_POINTER, HWND, POINT, POINTL, S_OK},
+        Graphics::Gdi::ScreenToClient,
+        System::{
+            Com::{IDataObject, DVASPECT_CONTENT, FORMATETC, STGMEDIUM, TYMED_HGLOBAL},
+            Ole::{
+                IDropTarget, IDropTarget_Vtbl, OleInitialize, RegisterDragDrop, ReleaseStgMedium,
+                RevokeDragDrop, CF_HDROP, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_NONE,
+            },
+            SystemServices::MODIFIERKEYS_FLAGS,
+        },
+        UI::Shell::{DragAcceptFiles, DragQueryFileW, HDROP},
+    },
+};
+
+use super::super::super::{
+    coords::ClientPoint,
+    drag_drop::{self, DragDropEvent},
+    event::Event,
+    state::{self, WindowState},
+};
+
+thread_local! {
+    /// Whether OleInitialize succeeded on this thread, [None] before the first try.
+    static OLE_INITIALIZED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/**
+    Let a window accept files. With OLE the window gets a drop target, which also tells us about the files while
+    they are dragged over it. OleInitialize fails when the thread already joined the multithreaded COM apartment,
+    then the window gets WM_DROPFILES instead, see [paths_from_hdrop()].
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/ole2/nf-ole2-registerdragdrop
+*/
+pub fn register(hwnd: HWND) {
+    unsafe {
+        if ole_initialized() {
+            let target: IDropTarget = DropTarget::create(hwnd);
+            // OLE keeps its own reference until RevokeDragDrop, ours is released when `target` is dropped
+            if RegisterDragDrop(hwnd, &target).is_ok() {
+                return;
+            }
+        }
+        DragAcceptFiles(hwnd, true);
+    }
+}
+
+/// Remove the drop target of a window before it is destroyed. Does nothing for a window without one.
+pub fn revoke(hwnd: HWND) {
+    if OLE_INITIALIZED.with(|initialized| initialized.get()) == Some(true) {
+        // See https://learn.microsoft.com/en-us/windows/win32/api/ole2/nf-ole2-revokedragdrop
+        let _ = unsafe { RevokeDragDrop(hwnd) };
+    }
+}
+
+/**
+    The paths in a HDROP, the data of CF_HDROP and the wParam of WM_DROPFILES.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-dragqueryfilew
+*/
+pub fn paths_from_hdrop(hdrop: HDROP) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = vec![];
+    unsafe {
+        // Index 0xFFFFFFFF returns the number of files
+        let count: u32 = DragQueryFileW(hdrop, u32::MAX, None);
+        for index in 0..count {
+            // Without a buffer it returns the length without the NUL
+            let length: u32 = DragQueryFileW(hdrop, index, None);
+            let mut buffer: Vec<u16> = vec![0; length as usize + 1];
+            let copied: u32 = DragQueryFileW(hdrop, index, Some(&mut buffer));
+            if copied > 0 {
+                paths.push(PathBuf::from(String::from_utf16_lossy(
+                    &buffer[..copied as usize],
+                )));
+            }
+        }
+    }
+    return paths;
+}
+
+/// Initialize OLE, and with it a single-threaded COM apartment, once for this thread. False when that failed.
+pub fn ole_initialized() -> bool {
+    return OLE_INITIALIZED.with(|initialized| {
+        // See https://learn.microsoft.com/en-us/windows/win32/api/ole2/nf-ole2-oleinitialize
+        let result: bool = initialized
+            .get()
+            .unwrap_or_else(|| unsafe { OleInitialize(std::ptr::null()).is_ok() });
+        initialized.set(Some(result));
+        return result;
+    });
+}
+
+/**
+    The IDropTarget of a window. The windows crate can only implement COM interfaces with a proc macro, so this is
+    the object laid out by hand: a pointer to the vtable first and our fields after it. OLE calls it on the thread
+    of the window, from the message loop, so it doesn't have to be thread safe.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/oleidl/nn-oleidl-idroptarget
+*/
+#[repr(C)]
+struct DropTarget {
+    vtable: *const IDropTarget_Vtbl,
+    references: Cell<u32>,
+    hwnd: HWND,
+    /// Whether the files that are dragged over the window got hover events, so they need a cancel or a drop.
+    hovering: Cell<bool>,
+}
+
+static VTABLE: IDropTarget_Vtbl = IDropTarget_Vtbl {
+    base__: IUnknown_Vtbl {
+        QueryInterface: DropTarget::query_interface,
+        AddRef: DropTarget::add_ref,
+        Release: DropTarget::release,
+    },
+    DragEnter: DropTarget::drag_enter,
+    DragOver: DropTarget::drag_over,
+    DragLeave: DropTarget::drag_leave,
+    Drop: DropTarget::drop_files,
+};
+
+impl DropTarget {
+    fn create(hwnd: HWND) -> IDropTarget {
+        let target: Box<DropTarget> = Box::new(DropTarget {
+            vtable: &VTABLE,
+            references: Cell::new(1),
+            hwnd,
+            hovering: Cell::new(false),
+        });
+        return unsafe { IDropTarget::from_raw(Box::into_raw(target) as *mut c_void) };
+    }
+
+    unsafe fn from_this<'a>(this: *mut c_void) -> &'a DropTarget {
+        return &*(this as *const DropTarget);
+    }
+
+    fn push(&self, event: DragDropEvent) {
+        let state: Option<&RefCell<WindowState>> = unsafe { state::lookup(self.hwnd) };
+        if let Some(state) = state {
+            state.borrow_mut().push_event(Event::DragDrop(event));
+        }
+    }
+
+    unsafe extern "system" fn query_interface(
+        this: *mut c_void,
+        iid: &GUID,
+        interface: *mut *const c_void,
+    ) -> HRESULT {
+        if interface.is_null() {
+            return E_POINTER;
+        }
+        if *iid == IUnknown::IID || *iid == IDropTarget::IID {
+            Self::add_ref(this);
+            *interface = this;
+            return S_OK;
+        }
+        *interface = std::ptr::null();
+        return E_NOINTERFACE;
+    }
+
+    unsafe extern "system" fn add_ref(this: *mut c_void) -> u32 {
+        let target: &DropTarget = Self::from_this(this);
+        target.references.set(target.references.get() + 1);
+        return target.references.get();
+    }
+
+    unsafe extern "system" fn release(this: *mut c_void) -> u32 {
+        let target: &DropTarget = Self::from_this(this);
+        let references: u32 = target.references.get() - 1;
+        target.references.set(references);
+        if references == 0 {
+            drop(Box::from_raw(this as *mut DropTarget));
+        }
+        return references;
+    }
+
+    unsafe extern "system" fn drag_enter(
+        this: *mut c_void,
+        data: *mut c_void,
+        _key_state: MODIFIERKEYS_FLAGS,
+        _point: POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> HRESULT {
+        let target: &DropTarget = Self::from_this(this);
+        let paths: Vec<PathBuf> = paths_from_data(data);
+        // Text and the other kinds of data can't be dropped here
+        target.hovering.set(!paths.is_empty());
+        for event in drag_drop::hover_events(&paths) {
+            target.push(event);
+        }
+        return set_effect(effect, target.hovering.get());
+    }
+
+    unsafe extern "system" fn drag_over(
+        this: *mut c_void,
+        _key_state: MODIFIERKEYS_FLAGS,
+        _point: POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> HRESULT {
+        return set_effect(effect, Self::from_this(this).hovering.get());
+    }
+
+    unsafe extern "system" fn drag_leave(this: *mut c_void) -> HRESULT {
+        let target: &DropTarget = Self::from_this(this);
+        if target.hovering.replace(false) {
+            target.push(DragDropEvent::HoveredFileCancelled);
+        }
+        return S_OK;
+    }
+
+    unsafe extern "system" fn drop_files(
+        this: *mut c_void,
+        data: *mut c_void,
+        _key_state: MODIFIERKEYS_FLAGS,
+        point: POINTL,
+        effect: *mut DROPEFFECT,
+    ) -> HRESULT {
+        let target: &DropTarget = Self::from_this(this);
+        target.hovering.set(false);
+        let paths: Vec<PathBuf> = paths_from_data(data);
+        if paths.is_empty() {
+            return set_effect(effect, false);
+        }
+        // The point is in screen coordinates
+        let mut position: POINT = POINT {
+            x: point.x,
+            y: point.y,
+        };
+        ScreenToClient(target.hwnd, &mut position);
+        target.push(DragDropEvent::DroppedFiles {
+            paths,
+            position: ClientPoint {
+                x: position.x,
+                y: position.y,
+            },
+        });
+        return set_effect(effect, true);
+    }
+}
+
+/// Files are copied, not moved: the app that dragged them keeps them.
+unsafe fn set_effect(effect: *mut DROPEFFECT, accept: bool) -> HRESULT {
+    if effect.is_null() {
+        return E_POINTER;
+    }
+    *effect = if accept {
+        DROPEFFECT_COPY
+    } else {
+        DROPEFFECT_NONE
+    };
+    return S_OK;
+}
+
+/// The paths of the CF_HDROP data in a data object, empty when the data isn't files.
+unsafe fn paths_from_data(data: *mut c_void) -> Vec<PathBuf> {
+    let data: &IDataObject = match IDataObject::from_raw_borrowed(&data) {
+        Some(data) => data,
+        None => return vec![],
+    };
+    let format: FORMATETC = FORMATETC {
+        cfFormat: CF_HDROP.0,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    };
+    let mut medium: STGMEDIUM = match data.GetData(&format) {
+        Ok(medium) => medium,
+        Err(_) => return vec![],
+    };
+    let paths: Vec<PathBuf> = paths_from_hdrop(HDROP(medium.Anonymous.hGlobal.0));
+    // See https://learn.microsoft.com/en-us/windows/win32/api/ole2/nf-ole2-releasestgmedium
+    ReleaseStgMedium(&mut medium);
+    return paths;
+}
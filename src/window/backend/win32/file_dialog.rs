@@ -0,0 +1,127 @@
+use std::{ffi::c_void, path::PathBuf};
+
+use windows::{
+    core::{ComInterface, PWSTR},
+    Win32::{
+        Foundation::HWND,
+        System::Com::{CoCreateInstance, CoTaskMemFree, IBindCtx, CLSCTX_INPROC_SERVER},
+        UI::Shell::{
+            Common::COMDLG_FILTERSPEC, FileOpenDialog, FileSaveDialog, IFileDialog,
+            IFileOpenDialog, IShellItem, IShellItemArray, SHCreateItemFromParsingName,
+            FILEOPENDIALOGOPTIONS, FOS_ALLOWMULTISELECT, FOS_FILEMUSTEXIST, FOS_FORCEFILESYSTEM,
+            FOS_OVERWRITEPROMPT, FOS_PATHMUSTEXIST, FOS_PICKFOLDERS, SIGDN_FILESYSPATH,
+        },
+    },
+};
+
+use super::{
+    super::super::{
+        dialog::{FileDialog, FileDialogKind},
+        wide::WideCString,
+    },
+    drop_target,
+};
+
+/**
+    Show the common item dialog, the one of the explorer. It needs COM on the thread, we initialize it the same way
+    the drop targets do. Returns the picked paths, empty when the user cancelled or the dialog couldn't be shown.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/shobjidl_core/nn-shobjidl_core-ifiledialog
+*/
+pub fn show(owner: HWND, dialog: &FileDialog) -> Vec<PathBuf> {
+    // In a multithreaded apartment the dialog still works, so the result doesn't matter
+    drop_target::ole_initialized();
+    return unsafe { show_dialog(owner, dialog) }.unwrap_or_default();
+}
+
+unsafe fn show_dialog(owner: HWND, dialog: &FileDialog) -> windows::core::Result<Vec<PathBuf>> {
+    let class: &windows::core::GUID = match dialog.kind {
+        FileDialogKind::Save => &FileSaveDialog,
+        FileDialogKind::Open | FileDialogKind::Folder => &FileOpenDialog,
+    };
+    let file_dialog: IFileDialog = CoCreateInstance(class, None, CLSCTX_INPROC_SERVER)?;
+
+    // Only paths on disk, not the virtual folders like "This PC" which have no path
+    let mut options: FILEOPENDIALOGOPTIONS = file_dialog.GetOptions()? | FOS_FORCEFILESYSTEM;
+    match dialog.kind {
+        FileDialogKind::Open => {
+            options |= FOS_FILEMUSTEXIST | FOS_PATHMUSTEXIST;
+            if dialog.multi_select {
+                options |= FOS_ALLOWMULTISELECT;
+            }
+        }
+        FileDialogKind::Save => {
+            // A save dialog asks by default
+            options = match dialog.confirm_overwrite {
+                true => options | FOS_OVERWRITEPROMPT,
+                false => FILEOPENDIALOGOPTIONS(options.0 & !FOS_OVERWRITEPROMPT.0),
+            };
+        }
+        FileDialogKind::Folder => options = options | FOS_PICKFOLDERS | FOS_PATHMUSTEXIST,
+    }
+    file_dialog.SetOptions(options)?;
+
+    // The dialog copies the strings, they only have to live until the calls return
+    if !dialog.title.is_empty() {
+        file_dialog.SetTitle(WideCString::new_truncated(&dialog.title).as_pcwstr())?;
+    }
+    if dialog.kind != FileDialogKind::Folder && !dialog.filters.is_empty() {
+        let strings: Vec<(WideCString, WideCString)> = dialog
+            .filters
+            .iter()
+            .map(|filter| {
+                (
+                    WideCString::new_truncated(&filter.name),
+                    WideCString::new_truncated(&filter.spec()),
+                )
+            })
+            .collect();
+        let specs: Vec<COMDLG_FILTERSPEC> = strings
+            .iter()
+            .map(|(name, spec)| COMDLG_FILTERSPEC {
+                pszName: name.as_pcwstr(),
+                pszSpec: spec.as_pcwstr(),
+            })
+            .collect();
+        file_dialog.SetFileTypes(&specs)?;
+    }
+    if let Some(extension) = dialog.default_extension() {
+        file_dialog.SetDefaultExtension(WideCString::new_truncated(extension).as_pcwstr())?;
+    }
+    let (folder, name): (Option<PathBuf>, Option<String>) = dialog.default_folder_and_name();
+    if let Some(folder) = folder {
+        // A folder that doesn't exist has no shell item, the dialog opens in the last used folder then
+        let folder: WideCString = WideCString::new_truncated(&folder.to_string_lossy());
+        let item: windows::core::Result<IShellItem> =
+            SHCreateItemFromParsingName(folder.as_pcwstr(), None::<&IBindCtx>);
+        if let Ok(item) = item {
+            file_dialog.SetFolder(&item)?;
+        }
+    }
+    if let Some(name) = name {
+        file_dialog.SetFileName(WideCString::new_truncated(&name).as_pcwstr())?;
+    }
+
+    // Fails with ERROR_CANCELLED when the user closes the dialog
+    if file_dialog.Show(owner).is_err() {
+        return Ok(vec![]);
+    }
+    if dialog.kind == FileDialogKind::Open && dialog.multi_select {
+        let open_dialog: IFileOpenDialog = file_dialog.cast()?;
+        let items: IShellItemArray = open_dialog.GetResults()?;
+        let mut paths: Vec<PathBuf> = vec![];
+        for index in 0..items.GetCount()? {
+            paths.push(path_of(&items.GetItemAt(index)?)?);
+        }
+        return Ok(paths);
+    }
+    return Ok(vec![path_of(&file_dialog.GetResult()?)?]);
+}
+
+/// The path of a shell item. The string is allocated by the shell, we have to free it.
+unsafe fn path_of(item: &IShellItem) -> windows::core::Result<PathBuf> {
+    let name: PWSTR = item.GetDisplayName(SIGDN_FILESYSPATH)?;
+    let path: PathBuf = PathBuf::from(String::from_utf16_lossy(name.as_wide()));
+    CoTaskMemFree(Some(name.0 as *const c_void));
+    return Ok(path);
+}
@@ -0,0 +1,146 @@
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Foundation::{HWND, LPARAM, POINT, WPARAM},
+        Graphics::Gdi::ClientToScreen,
+        UI::WindowsAndMessaging::{
+            CreateMenu, CreatePopupMenu, DestroyMenu, InsertMenuItemW, PostMessageW,
+            SetForegroundWindow, TrackPopupMenu, HMENU, MENUITEMINFOW, MENU_ITEM_STATE,
+            MFS_CHECKED, MFS_DISABLED, MFT_RADIOCHECK, MFT_SEPARATOR, MFT_STRING, MIIM_FTYPE,
+            MIIM_ID, MIIM_STATE, MIIM_STRING, MIIM_SUBMENU, TPM_RETURNCMD, TPM_RIGHTBUTTON,
+            WM_NULL,
+        },
+    },
+};
+
+use super::super::super::{
+    coords::ClientPoint,
+    menu::{Menu, MenuCheck, MenuId, MenuItem},
+    wide::WideCString,
+};
+
+/**
+    Build the HMENU of a menu bar. The window owns it after SetMenu and destroys it along with itself, a menu bar
+    that is replaced has to be destroyed by us. [None] when windows couldn't create it.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-createmenu
+*/
+pub fn create_menu_bar(menu: &Menu) -> Option<HMENU> {
+    let hmenu: HMENU = unsafe { CreateMenu() }.ok()?;
+    if !fill(hmenu, menu) {
+        unsafe { DestroyMenu(hmenu) };
+        return None;
+    }
+    return Some(hmenu);
+}
+
+/**
+    Show `menu` at `position` and wait until it is closed. TrackPopupMenu runs its own message loop, so the wndproc
+    is called while we wait: don't hold a borrow of the state. With TPM_RETURNCMD it returns the id instead of
+    sending WM_COMMAND, 0 when nothing was picked.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-trackpopupmenu
+*/
+pub fn show_popup(hwnd: HWND, menu: &Menu, position: ClientPoint) -> Option<MenuId> {
+    unsafe {
+        let hmenu: HMENU = CreatePopupMenu().ok()?;
+        if !fill(hmenu, menu) {
+            DestroyMenu(hmenu);
+            return None;
+        }
+        let mut point: POINT = POINT {
+            x: position.x,
+            y: position.y,
+        };
+        ClientToScreen(hwnd, &mut point);
+        // Without this the menu doesn't close when the user clicks outside of it
+        SetForegroundWindow(hwnd);
+        let id: i32 = TrackPopupMenu(
+            hmenu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON,
+            point.x,
+            point.y,
+            0,
+            hwnd,
+            None,
+        )
+        .0;
+        // See the remarks of TrackPopupMenu, the menu has to see a message before it works right the next time
+        PostMessageW(hwnd, WM_NULL, WPARAM(0), LPARAM(0));
+        DestroyMenu(hmenu);
+        if id <= 0 || id > u16::MAX as i32 {
+            return None;
+        }
+        return Some(MenuId(id as u16));
+    }
+}
+
+/**
+    Append the items of `menu` to `hmenu`, with a popup menu for every submenu. Returns false when an item couldn't
+    be added, the submenus that were added are destroyed along with `hmenu`.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-insertmenuitemw
+*/
+fn fill(hmenu: HMENU, menu: &Menu) -> bool {
+    for (position, item) in menu.items.iter().enumerate() {
+        let text: String = match item {
+            MenuItem::Action(action) => action.full_label(),
+            MenuItem::Submenu { label, .. } => label.clone(),
+            MenuItem::Separator => String::new(),
+        };
+        // Windows copies the label, it only has to live until InsertMenuItem returns
+        let label: WideCString = WideCString::new_truncated(&text);
+        let mut info: MENUITEMINFOW = MENUITEMINFOW {
+            cbSize: std::mem::size_of::<MENUITEMINFOW>() as u32,
+            fMask: MIIM_FTYPE | MIIM_STATE | MIIM_STRING,
+            fType: MFT_STRING,
+            dwTypeData: PWSTR(label.as_pcwstr().0 as *mut u16),
+            ..Default::default()
+        };
+        match item {
+            MenuItem::Action(action) => {
+                info.fMask |= MIIM_ID;
+                info.wID = action.id.0 as u32;
+                if let MenuCheck::Radio(_) = action.check {
+                    info.fType |= MFT_RADIOCHECK;
+                }
+                info.fState = item_state(action.enabled, action.checked());
+            }
+            MenuItem::Submenu { menu, enabled, .. } => {
+                let submenu: HMENU = match unsafe { CreatePopupMenu() } {
+                    Ok(submenu) => submenu,
+                    Err(_) => return false,
+                };
+                if !fill(submenu, menu) {
+                    unsafe { DestroyMenu(submenu) };
+                    return false;
+                }
+                info.fMask |= MIIM_SUBMENU;
+                info.fState = item_state(*enabled, false);
+                info.hSubMenu = submenu;
+            }
+            MenuItem::Separator => {
+                info.fMask = MIIM_FTYPE;
+                info.fType = MFT_SEPARATOR;
+            }
+        }
+        if !unsafe { InsertMenuItemW(hmenu, position as u32, true, &info) }.as_bool() {
+            if info.hSubMenu.0 != 0 {
+                unsafe { DestroyMenu(info.hSubMenu) };
+            }
+            return false;
+        }
+    }
+    return true;
+}
+
+fn item_state(enabled: bool, checked: bool) -> MENU_ITEM_STATE {
+    let mut state: MENU_ITEM_STATE = MENU_ITEM_STATE(0);
+    if !enabled {
+        state |= MFS_DISABLED;
+    }
+    if checked {
+        state |= MFS_CHECKED;
+    }
+    return state;
+}
@@ -0,0 +1,637 @@
+use windows::Win32::UI::WindowsAndMessaging::{
+    CS_OWNDC, WINDOW_EX_STYLE, WINDOW_STYLE, WNDCLASS_STYLES, WS_CAPTION, WS_CHILD, WS_EX_TOPMOST,
+    WS_MAXIMIZEBOX, WS_MINIMIZEBOX, WS_POPUP, WS_SYSMENU, WS_THICKFRAME,
+};
+
+use std::rc::Rc;
+
+use super::{
+    backend::{self, Backend},
+    chrome::ChromeLayout,
+    coords::{LogicalSize, PhysicalPoint, PhysicalSize, Position, Size},
+    error::BuildError,
+    manager::WindowRelation,
+    menu::Menu,
+    placement_store::PlacementStore,
+    Window,
+};
+
+/// Where the window is placed when it is created.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum WindowPosition {
+    /// Let windows pick a position (CW_USEDEFAULT).
+    Default,
+    /// The top-left corner of the window in screen coordinates, see [WindowBuilder::position()].
+    At(Position),
+    /// Center the window on the primary monitor.
+    Centered,
+}
+
+/**
+    Whether a size is the size of the client area or of the whole window with title bar and borders. The backend
+    scales a logical size to the DPI of the monitor the window opens on.
+*/
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum WindowSize {
+    Inner(Size),
+    Outer(Size),
+}
+
+impl WindowSize {
+    /// The inner or outer size in physical pixels at `scale_factor`.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize {
+        return match self {
+            WindowSize::Inner(size) | WindowSize::Outer(size) => size.to_physical(scale_factor),
+        };
+    }
+}
+
+/**
+    Describes a window before it is created. Every setter takes and returns the builder so you can chain them:
+    ```ignore
+    let window: Window = WindowBuilder::new("Editor")
+        .inner_size(LogicalSize::new(800.0, 600.0))
+        .centered()
+        .resizable(true)
+        .build()?;
+    ```
+    [WindowBuilder::build()] validates the whole configuration first, so an impossible combination gives a
+    [BuildError] instead of a window in a weird state.
+*/
+#[derive(Debug, Clone)]
+pub struct WindowBuilder {
+    pub title: String,
+    /// The name of the window class. Windows with the same class name share the class.
+    pub class_name: String,
+    pub class_style: WNDCLASS_STYLES,
+    pub position: WindowPosition,
+    pub size: WindowSize,
+    /// Minimum size of the client area.
+    pub min_size: Option<Size>,
+    /// Maximum size of the client area.
+    pub max_size: Option<Size>,
+    pub resizable: bool,
+    /// Title bar and borders. Without them a resizable window keeps its sizing border.
+    pub decorations: bool,
+    pub always_on_top: bool,
+    /// Show the window as soon as it is created.
+    pub visible: bool,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    pub relation: WindowRelation,
+    /// Send [DragDropEvent](super::drag_drop::DragDropEvent)'s for files dragged onto the window.
+    pub accept_files: bool,
+    /// The menu bar below the title bar. Not for child windows.
+    pub menu: Option<Menu>,
+    /// A title bar drawn by the app instead of the native one. Not for child windows.
+    pub chrome: Option<ChromeLayout>,
+    /// Restore the placement of the last run and save it on close. Ignored for child windows.
+    pub placement_store: Option<PlacementStore>,
+}
+
+impl WindowBuilder {
+    pub fn new(title: &str) -> WindowBuilder {
+        return WindowBuilder {
+            title: title.to_string(),
+            class_name: "Win32ExampleWindow".to_string(),
+            class_style: CS_OWNDC,
+            position: WindowPosition::Default,
+            size: WindowSize::Inner(Size::Logical(LogicalSize::new(800.0, 600.0))),
+            min_size: None,
+            max_size: None,
+            resizable: true,
+            decorations: true,
+            always_on_top: false,
+            visible: true,
+            maximized: false,
+            fullscreen: false,
+            relation: WindowRelation::TopLevel,
+            accept_files: false,
+            menu: None,
+            chrome: None,
+            placement_store: None,
+        };
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        return self;
+    }
+
+    pub fn class_name(mut self, class_name: &str) -> Self {
+        self.class_name = class_name.to_string();
+        return self;
+    }
+
+    pub fn class_style(mut self, style: WNDCLASS_STYLES) -> Self {
+        self.class_style = style;
+        return self;
+    }
+
+    /**
+        The top-left corner of the window in screen coordinates. Use a [PhysicalPoint] to open on a specific
+        monitor. The monitors of the desktop can have different scale factors, so a
+        [LogicalPoint](super::coords::LogicalPoint) is scaled with the scale factor of the primary monitor.
+    */
+    pub fn position(mut self, position: impl Into<Position>) -> Self {
+        self.position = WindowPosition::At(position.into());
+        return self;
+    }
+
+    pub fn centered(mut self) -> Self {
+        self.position = WindowPosition::Centered;
+        return self;
+    }
+
+    /**
+        The size of the client area, without the title bar and borders. A [LogicalSize] is scaled to the DPI of the
+        monitor the window opens on, a [PhysicalSize] is used as it is.
+    */
+    pub fn inner_size(mut self, size: impl Into<Size>) -> Self {
+        self.size = WindowSize::Inner(size.into());
+        return self;
+    }
+
+    /// The size of the whole window, including the title bar and borders. See [WindowBuilder::inner_size()].
+    pub fn outer_size(mut self, size: impl Into<Size>) -> Self {
+        self.size = WindowSize::Outer(size.into());
+        return self;
+    }
+
+    /// The minimum size of the client area. See [WindowBuilder::inner_size()].
+    pub fn min_size(mut self, size: impl Into<Size>) -> Self {
+        self.min_size = Some(size.into());
+        return self;
+    }
+
+    /// The maximum size of the client area. See [WindowBuilder::inner_size()].
+    pub fn max_size(mut self, size: impl Into<Size>) -> Self {
+        self.max_size = Some(size.into());
+        return self;
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        return self;
+    }
+
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        return self;
+    }
+
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        return self;
+    }
+
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        return self;
+    }
+
+    pub fn maximized(mut self, maximized: bool) -> Self {
+        self.maximized = maximized;
+        return self;
+    }
+
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        return self;
+    }
+
+    /// Make the window owned by, child of or modal to another window. See [WindowRelation].
+    pub fn relation(mut self, relation: WindowRelation) -> Self {
+        self.relation = relation;
+        return self;
+    }
+
+    /**
+        Let the user drop files onto the window. The Win32 backend initializes OLE on the thread for this, which
+        needs a single-threaded COM apartment. When the thread already joined the multithreaded one the window
+        falls back to WM_DROPFILES, which has no hover events.
+    */
+    pub fn accept_files(mut self, accept_files: bool) -> Self {
+        self.accept_files = accept_files;
+        return self;
+    }
+
+    /// Give the window a menu bar, see [Menu]. The size of the builder is still the client size, below the menu.
+    pub fn menu(mut self, menu: Menu) -> Self {
+        self.menu = Some(menu);
+        return self;
+    }
+
+    /**
+        Draw the title bar yourself, see [ChromeLayout]. The native title bar and borders are gone but the style
+        keeps them, so keep the decorations on for the snapping, the animations and the window menu. The size of the
+        builder is the size of the whole window then.
+    */
+    pub fn chrome(mut self, chrome: ChromeLayout) -> Self {
+        self.chrome = Some(chrome);
+        return self;
+    }
+
+    /**
+        Open the window where it was when it closed last time, see [PlacementStore]. The position and size of
+        the builder are used when there is nothing to restore.
+    */
+    pub fn placement_store(mut self, store: PlacementStore) -> Self {
+        self.placement_store = Some(store);
+        return self;
+    }
+
+    /**
+        Check the configuration without creating anything. Sizes in different units can only be compared once the
+        scale factor is known, the backend checks those for the monitor the window opens on.
+    */
+    pub fn validate(&self) -> Result<(), BuildError> {
+        if self.title.contains('\0') {
+            return Err(BuildError::InteriorNul("title"));
+        }
+        if self.class_name.contains('\0') {
+            return Err(BuildError::InteriorNul("class name"));
+        }
+        if self.class_name.is_empty() {
+            return Err(BuildError::EmptyClassName);
+        }
+
+        let size: Size = match self.size {
+            WindowSize::Inner(size) | WindowSize::Outer(size) => size,
+        };
+        for size in [Some(size), self.min_size, self.max_size]
+            .into_iter()
+            .flatten()
+        {
+            if !is_positive(size) {
+                return Err(BuildError::InvalidSize(size));
+            }
+        }
+
+        if let Some((min, max)) = self.min_size.zip(self.max_size).and_then(same_unit) {
+            if min.0 > max.0 || min.1 > max.1 {
+                return Err(BuildError::MinLargerThanMax);
+            }
+        }
+        // Only the inner size can be compared, the limits are client sizes as well
+        if let WindowSize::Inner(size) = self.size {
+            let too_small: bool = match self.min_size.and_then(|min| same_unit((size, min))) {
+                Some((size, min)) => size.0 < min.0 || size.1 < min.1,
+                None => false,
+            };
+            let too_large: bool = match self.max_size.and_then(|max| same_unit((size, max))) {
+                Some((size, max)) => size.0 > max.0 || size.1 > max.1,
+                None => false,
+            };
+            if too_small || too_large {
+                return Err(BuildError::SizeOutsideLimits);
+            }
+        }
+
+        if self.maximized && self.fullscreen {
+            return Err(BuildError::MaximizedAndFullscreen);
+        }
+        if let WindowRelation::Child(_) = self.relation {
+            if self.fullscreen {
+                return Err(BuildError::InvalidChildOption("fullscreen"));
+            }
+            if self.maximized {
+                return Err(BuildError::InvalidChildOption("maximized"));
+            }
+            if self.always_on_top {
+                return Err(BuildError::InvalidChildOption("always on top"));
+            }
+            if self.menu.is_some() {
+                return Err(BuildError::InvalidChildOption("given a menu bar"));
+            }
+            if self.chrome.is_some() {
+                return Err(BuildError::InvalidChildOption("given a custom chrome"));
+            }
+        }
+
+        return Ok(());
+    }
+
+    /**
+        The same configuration with the sizes in physical pixels for `scale_factor`. Backends call this with the
+        scale factor of the monitor the window opens on. The position stays as it is, see
+        [WindowBuilder::physical_position()].
+    */
+    pub fn to_physical(&self, scale_factor: f64) -> WindowBuilder {
+        let scale = |size: Size| Size::Physical(size.to_physical(scale_factor));
+
+        let mut builder: WindowBuilder = self.clone();
+        builder.size = match self.size {
+            WindowSize::Inner(size) => WindowSize::Inner(scale(size)),
+            WindowSize::Outer(size) => WindowSize::Outer(scale(size)),
+        };
+        builder.min_size = self.min_size.map(scale);
+        builder.max_size = self.max_size.map(scale);
+        return builder;
+    }
+
+    /**
+        The top-left corner of [WindowPosition::At] in physical screen coordinates. A logical position is scaled
+        with `primary_scale_factor`, the scale factor of the primary monitor.
+    */
+    pub fn physical_position(&self, primary_scale_factor: f64) -> Option<PhysicalPoint> {
+        return match self.position {
+            WindowPosition::At(position) => Some(position.to_physical(primary_scale_factor)),
+            WindowPosition::Default | WindowPosition::Centered => None,
+        };
+    }
+
+    /// The WS_* style for this configuration.
+    pub fn window_style(&self) -> WINDOW_STYLE {
+        if let WindowRelation::Child(_) = self.relation {
+            return WS_CHILD;
+        }
+        if self.fullscreen {
+            // No title bar and no borders
+            return WS_POPUP;
+        }
+        if !self.decorations {
+            // Without WS_THICKFRAME the user couldn't resize the window at all
+            return match self.resizable {
+                true => WS_POPUP | WS_THICKFRAME,
+                false => WS_POPUP,
+            };
+        }
+
+        let mut style: WINDOW_STYLE = WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX;
+        if self.resizable {
+            style |= WS_THICKFRAME | WS_MAXIMIZEBOX;
+        }
+        return style;
+    }
+
+    /// The WS_EX_* style for this configuration.
+    pub fn window_ex_style(&self) -> WINDOW_EX_STYLE {
+        if self.always_on_top {
+            return WS_EX_TOPMOST;
+        }
+        return WINDOW_EX_STYLE(0);
+    }
+
+    /// Validate the configuration and create the window with the [current](super::backend::current()) backend.
+    pub fn build(&self) -> Result<Window, BuildError> {
+        return self.build_with(backend::current());
+    }
+
+    /// Validate the configuration and create the window with `backend`.
+    pub fn build_with(&self, backend: Rc<dyn Backend>) -> Result<Window, BuildError> {
+        self.validate()?;
+        return Window::create(self, backend);
+    }
+
+    /**
+        Like [WindowBuilder::build()] for a window which receives payloads of type `T` as
+        [Event::User](super::event::Event::User), see
+        [Window::create_proxy()].
+    */
+    pub fn build_for<T>(&self) -> Result<Window<T>, BuildError> {
+        self.validate()?;
+        return Window::create(self, backend::current());
+    }
+}
+
+/// Zero, negative, NaN and infinite sizes can't be a window.
+fn is_positive(size: Size) -> bool {
+    return match size {
+        Size::Logical(size) => {
+            size.width > 0.0
+                && size.height > 0.0
+                && size.width.is_finite()
+                && size.height.is_finite()
+        }
+        Size::Physical(size) => size.width > 0 && size.height > 0,
+    };
+}
+
+/// Two sizes as (width, height) when they have the same unit, sizes in different units can't be compared.
+fn same_unit((a, b): (Size, Size)) -> Option<((f64, f64), (f64, f64))> {
+    return match (a, b) {
+        (Size::Logical(a), Size::Logical(b)) => Some(((a.width, a.height), (b.width, b.height))),
+        (Size::Physical(a), Size::Physical(b)) => Some((
+            (a.width as f64, a.height as f64),
+            (b.width as f64, b.height as f64),
+        )),
+        _ => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{coords::LogicalPoint, manager::WindowId};
+
+    #[test]
+    fn reject_nul_and_empty_names() {
+        assert_eq!(
+            WindowBuilder::new("Te\0st").validate(),
+            Err(BuildError::InteriorNul("title"))
+        );
+        assert_eq!(
+            WindowBuilder::new("Test").class_name("Cl\0ass").validate(),
+            Err(BuildError::InteriorNul("class name"))
+        );
+        assert_eq!(
+            WindowBuilder::new("Test").class_name("").validate(),
+            Err(BuildError::EmptyClassName)
+        );
+    }
+
+    fn logical(width: f64, height: f64) -> Size {
+        return Size::Logical(LogicalSize::new(width, height));
+    }
+
+    fn physical(width: i32, height: i32) -> Size {
+        return Size::Physical(PhysicalSize::new(width, height));
+    }
+
+    #[test]
+    fn reject_non_positive_sizes() {
+        let builder: WindowBuilder = WindowBuilder::new("Test");
+        for size in [
+            logical(0.0, 600.0),
+            logical(800.0, -1.0),
+            logical(800.0, f64::INFINITY),
+            physical(0, 600),
+            physical(800, -1),
+        ] {
+            assert_eq!(
+                builder.clone().inner_size(size).validate(),
+                Err(BuildError::InvalidSize(size))
+            );
+            assert_eq!(
+                builder.clone().outer_size(size).validate(),
+                Err(BuildError::InvalidSize(size))
+            );
+        }
+        // NaN never compares equal, so only the variant is checked
+        assert!(matches!(
+            builder
+                .clone()
+                .inner_size(logical(f64::NAN, 600.0))
+                .validate(),
+            Err(BuildError::InvalidSize(_))
+        ));
+        assert_eq!(
+            builder.clone().min_size(logical(-5.0, 10.0)).validate(),
+            Err(BuildError::InvalidSize(logical(-5.0, 10.0)))
+        );
+        assert_eq!(
+            builder.max_size(physical(10, 0)).validate(),
+            Err(BuildError::InvalidSize(physical(10, 0)))
+        );
+    }
+
+    #[test]
+    fn reject_sizes_outside_of_the_limits() {
+        let builder: WindowBuilder = WindowBuilder::new("Test").inner_size(logical(800.0, 600.0));
+        assert_eq!(
+            builder
+                .clone()
+                .min_size(logical(500.0, 400.0))
+                .max_size(logical(400.0, 500.0))
+                .validate(),
+            Err(BuildError::MinLargerThanMax)
+        );
+        assert_eq!(
+            builder.clone().min_size(logical(400.0, 700.0)).validate(),
+            Err(BuildError::SizeOutsideLimits)
+        );
+        assert_eq!(
+            builder.clone().max_size(logical(700.0, 600.0)).validate(),
+            Err(BuildError::SizeOutsideLimits)
+        );
+        assert_eq!(
+            builder
+                .clone()
+                .min_size(logical(800.0, 600.0))
+                .max_size(logical(800.0, 600.0))
+                .validate(),
+            Ok(())
+        );
+        // The outer size includes the frame, it can't be compared with the client size limits
+        assert_eq!(
+            builder
+                .clone()
+                .outer_size(logical(300.0, 300.0))
+                .min_size(logical(400.0, 400.0))
+                .validate(),
+            Ok(())
+        );
+
+        // Physical sizes are compared with each other as well
+        let physical_builder: WindowBuilder = builder.clone().inner_size(physical(800, 600));
+        assert_eq!(
+            physical_builder
+                .clone()
+                .max_size(physical(700, 600))
+                .validate(),
+            Err(BuildError::SizeOutsideLimits)
+        );
+        // Different units only once the scale factor is known
+        let mixed: WindowBuilder = physical_builder.max_size(logical(500.0, 400.0));
+        assert_eq!(mixed.validate(), Ok(()));
+        assert_eq!(
+            mixed.to_physical(1.5).validate(),
+            Err(BuildError::SizeOutsideLimits)
+        );
+        let mixed: WindowBuilder = builder.max_size(physical(1000, 800));
+        assert_eq!(mixed.to_physical(1.25).validate(), Ok(()));
+        assert_eq!(
+            mixed.to_physical(1.5).validate(),
+            Err(BuildError::SizeOutsideLimits)
+        );
+    }
+
+    #[test]
+    fn physical_sizes_and_positions_stay_as_they_are() {
+        let builder: WindowBuilder = WindowBuilder::new("Test")
+            .inner_size(physical(800, 600))
+            .min_size(logical(200.0, 100.0))
+            .position(PhysicalPoint::new(-300, 40));
+        let scaled: WindowBuilder = builder.to_physical(2.0);
+        assert_eq!(scaled.size, WindowSize::Inner(physical(800, 600)));
+        assert_eq!(scaled.min_size, Some(physical(400, 200)));
+        assert_eq!(
+            builder.physical_position(2.0),
+            Some(PhysicalPoint::new(-300, 40))
+        );
+
+        // A logical position goes by the primary monitor
+        let logical_position: WindowBuilder = builder.position(LogicalPoint::new(100.0, 50.5));
+        assert_eq!(
+            logical_position.physical_position(1.5),
+            Some(PhysicalPoint::new(150, 76))
+        );
+        assert_eq!(logical_position.centered().physical_position(1.5), None);
+    }
+
+    #[test]
+    fn reject_maximized_and_fullscreen() {
+        let builder: WindowBuilder = WindowBuilder::new("Test").maximized(true);
+        assert_eq!(builder.clone().validate(), Ok(()));
+        assert_eq!(
+            builder.fullscreen(true).validate(),
+            Err(BuildError::MaximizedAndFullscreen)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_child_options() {
+        let child: WindowBuilder =
+            WindowBuilder::new("Test").relation(WindowRelation::Child(WindowId(1)));
+        assert_eq!(child.validate(), Ok(()));
+
+        let options: [(WindowBuilder, &str); 5] = [
+            (child.clone().fullscreen(true), "fullscreen"),
+            (child.clone().maximized(true), "maximized"),
+            (child.clone().always_on_top(true), "always on top"),
+            (child.clone().menu(Menu::new()), "given a menu bar"),
+            (
+                child.clone().chrome(ChromeLayout::new()),
+                "given a custom chrome",
+            ),
+        ];
+        for (builder, option) in options {
+            assert_eq!(
+                builder.validate(),
+                Err(BuildError::InvalidChildOption(option))
+            );
+        }
+
+        // Owned windows can do all of that
+        let owned: WindowBuilder = WindowBuilder::new("Test")
+            .relation(WindowRelation::Owned(WindowId(1)))
+            .always_on_top(true)
+            .menu(Menu::new());
+        assert_eq!(owned.validate(), Ok(()));
+    }
+
+    #[test]
+    fn undecorated_windows_keep_their_sizing_border() {
+        let builder: WindowBuilder = WindowBuilder::new("Test").decorations(false);
+        assert_eq!(builder.validate(), Ok(()));
+        assert_eq!(builder.window_style(), WS_POPUP | WS_THICKFRAME);
+
+        let fixed: WindowBuilder = builder.resizable(false);
+        assert_eq!(fixed.window_style(), WS_POPUP);
+    }
+
+    #[test]
+    fn window_style() {
+        let builder: WindowBuilder = WindowBuilder::new("Test");
+        assert_eq!(
+            builder.window_style(),
+            WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX | WS_THICKFRAME | WS_MAXIMIZEBOX
+        );
+        assert_eq!(
+            builder.clone().resizable(false).window_style(),
+            WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX
+        );
+        assert_eq!(builder.fullscreen(true).window_style(), WS_POPUP);
+    }
+}
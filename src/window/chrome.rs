@@ -0,0 +1,518 @@
+use windows::Win32::UI::WindowsAndMessaging::{
+    HTBOTTOM, HTBOTTOMLEFT, HTBOTTOMRIGHT, HTCAPTION, HTCLIENT, HTCLOSE, HTLEFT, HTMAXBUTTON,
+    HTMINBUTTON, HTNOWHERE, HTRIGHT, HTTOP, HTTOPLEFT, HTTOPRIGHT, WINDOW_EX_STYLE, WINDOW_STYLE,
+};
+
+use super::{
+    coords::ClientPoint,
+    rect::{adjust_window_rect, FrameMetrics, Rect},
+};
+
+/// A button of the title bar. The app draws them, the window makes them behave like the native ones.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CaptionButton {
+    Minimize,
+    /// Maximizes the window, or restores it when it is maximized already.
+    Maximize,
+    /// Sends [WindowEvent::CloseRequested](super::event::WindowEvent::CloseRequested), like the native one.
+    Close,
+}
+
+/**
+    The part of the window a point is in, this is what the window answers to WM_NCHITTEST. Windows moves the window
+    on [HitTest::Caption], resizes it on the borders and shows the snap layouts of Windows 11 on the maximize button.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/inputdev/wm-nchittest
+*/
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum HitTest {
+    /// Outside of the window.
+    Nowhere,
+    /// The content of the app, it gets the mouse events.
+    Client,
+    /// The title bar, dragging it moves the window and a double click maximizes it.
+    Caption,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Button(CaptionButton),
+}
+
+impl HitTest {
+    /// The HT* code for WM_NCHITTEST.
+    pub fn code(&self) -> u32 {
+        return match self {
+            HitTest::Nowhere => HTNOWHERE,
+            HitTest::Client => HTCLIENT,
+            HitTest::Caption => HTCAPTION,
+            HitTest::Left => HTLEFT,
+            HitTest::Right => HTRIGHT,
+            HitTest::Top => HTTOP,
+            HitTest::Bottom => HTBOTTOM,
+            HitTest::TopLeft => HTTOPLEFT,
+            HitTest::TopRight => HTTOPRIGHT,
+            HitTest::BottomLeft => HTBOTTOMLEFT,
+            HitTest::BottomRight => HTBOTTOMRIGHT,
+            HitTest::Button(CaptionButton::Minimize) => HTMINBUTTON,
+            HitTest::Button(CaptionButton::Maximize) => HTMAXBUTTON,
+            HitTest::Button(CaptionButton::Close) => HTCLOSE,
+        };
+    }
+
+    /// The inverse of [HitTest::code()], for the wParam of the WM_NC* mouse messages. Unknown codes are [HitTest::Nowhere].
+    pub fn from_code(code: u32) -> HitTest {
+        return match code {
+            HTCLIENT => HitTest::Client,
+            HTCAPTION => HitTest::Caption,
+            HTLEFT => HitTest::Left,
+            HTRIGHT => HitTest::Right,
+            HTTOP => HitTest::Top,
+            HTBOTTOM => HitTest::Bottom,
+            HTTOPLEFT => HitTest::TopLeft,
+            HTTOPRIGHT => HitTest::TopRight,
+            HTBOTTOMLEFT => HitTest::BottomLeft,
+            HTBOTTOMRIGHT => HitTest::BottomRight,
+            HTMINBUTTON => HitTest::Button(CaptionButton::Minimize),
+            HTMAXBUTTON => HitTest::Button(CaptionButton::Maximize),
+            HTCLOSE => HitTest::Button(CaptionButton::Close),
+            _ => HitTest::Nowhere,
+        };
+    }
+
+    pub fn caption_button(&self) -> Option<CaptionButton> {
+        return match self {
+            HitTest::Button(button) => Some(*button),
+            _ => None,
+        };
+    }
+}
+
+/**
+    A title bar drawn by the app. The window has no native title bar and borders then, the whole window is client
+    area, and this layout tells windows which parts of it act like the title bar:
+    ```ignore
+    let chrome: ChromeLayout = ChromeLayout::new()
+        .client_region(Rect { left: 8, top: 0, right: 300, bottom: 32 }) // the tabs
+        .scaled(window.scale_factor());
+    window.set_chrome(Some(chrome));
+    ```
+    All sizes are physical pixels in client coordinates, like the mouse positions. Scale the layout again on
+    [WindowEvent::ScaleFactorChanged](super::event::WindowEvent::ScaleFactorChanged). The caption buttons follow
+    the right edge by themselves, regions that depend on the size have to be updated on a resize.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/dwm/customframe
+*/
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChromeLayout {
+    /// The height of the title bar at the top of the window. 0 for no title bar, only the drag regions.
+    pub caption_height: i32,
+    /// The caption buttons from left to right, in the top-right corner. They are as high as the title bar.
+    pub buttons: Vec<CaptionButton>,
+    pub button_width: i32,
+    /// Other areas that move the window, like the empty part of a toolbar.
+    pub drag_regions: Vec<Rect>,
+    /// Areas in the title bar that are part of the app, like tabs or a search box. They get the mouse events.
+    pub client_regions: Vec<Rect>,
+    /// How far from the edges the resize borders reach into the window. 0 for a window the user can't resize.
+    pub resize_border: i32,
+}
+
+impl Default for ChromeLayout {
+    fn default() -> Self {
+        return ChromeLayout::new();
+    }
+}
+
+impl ChromeLayout {
+    /// A title bar with the sizes of the native one of Windows 11 at 100%, and all three buttons.
+    pub fn new() -> ChromeLayout {
+        return ChromeLayout {
+            caption_height: 32,
+            buttons: vec![
+                CaptionButton::Minimize,
+                CaptionButton::Maximize,
+                CaptionButton::Close,
+            ],
+            button_width: 46,
+            drag_regions: vec![],
+            client_regions: vec![],
+            resize_border: 8,
+        };
+    }
+
+    pub fn caption_height(mut self, caption_height: i32) -> Self {
+        self.caption_height = caption_height;
+        return self;
+    }
+
+    pub fn buttons(mut self, buttons: &[CaptionButton]) -> Self {
+        self.buttons = buttons.to_vec();
+        return self;
+    }
+
+    pub fn button_width(mut self, button_width: i32) -> Self {
+        self.button_width = button_width;
+        return self;
+    }
+
+    pub fn drag_region(mut self, region: Rect) -> Self {
+        self.drag_regions.push(region);
+        return self;
+    }
+
+    pub fn client_region(mut self, region: Rect) -> Self {
+        self.client_regions.push(region);
+        return self;
+    }
+
+    pub fn resize_border(mut self, resize_border: i32) -> Self {
+        self.resize_border = resize_border;
+        return self;
+    }
+
+    /// The same layout for `scale_factor`, for a layout made in logical pixels. Rounded to the nearest pixel.
+    pub fn scaled(&self, scale_factor: f64) -> ChromeLayout {
+        let scale = |value: i32| (value as f64 * scale_factor).round() as i32;
+        let scale_rect = |rect: &Rect| Rect {
+            left: scale(rect.left),
+            top: scale(rect.top),
+            right: scale(rect.right),
+            bottom: scale(rect.bottom),
+        };
+        return ChromeLayout {
+            caption_height: scale(self.caption_height),
+            buttons: self.buttons.clone(),
+            button_width: scale(self.button_width),
+            drag_regions: self.drag_regions.iter().map(scale_rect).collect(),
+            client_regions: self.client_regions.iter().map(scale_rect).collect(),
+            resize_border: scale(self.resize_border),
+        };
+    }
+
+    /// The title bar of a window with the client rect `client`, the caption buttons included.
+    pub fn caption_rect(&self, client: Rect) -> Rect {
+        return Rect {
+            bottom: client.top + self.caption_height,
+            ..client
+        };
+    }
+
+    /// Where `button` is in a window with the client rect `client`, so the app can draw it. [None] when it has no such button.
+    pub fn button_rect(&self, button: CaptionButton, client: Rect) -> Option<Rect> {
+        let index: usize = self.buttons.iter().position(|b| *b == button)?;
+        let right: i32 = client.right - (self.buttons.len() - index - 1) as i32 * self.button_width;
+        return Some(Rect {
+            left: right - self.button_width,
+            top: client.top,
+            right,
+            bottom: client.top + self.caption_height,
+        });
+    }
+
+    /**
+        Which part of the window `point` is in, for a window with the client rect `client`. The resize borders go
+        first, like the native ones reach over the title bar, then the caption buttons, the client regions and
+        the title bar and drag regions. `resizable` is false for a maximized window and one without WS_THICKFRAME,
+        those don't have resize borders.
+    */
+    pub fn hit_test(&self, point: ClientPoint, client: Rect, resizable: bool) -> HitTest {
+        if !client.contains(point) {
+            return HitTest::Nowhere;
+        }
+
+        if resizable && self.resize_border > 0 {
+            let left: bool = point.x < client.left + self.resize_border;
+            let right: bool = point.x >= client.right - self.resize_border;
+            let top: bool = point.y < client.top + self.resize_border;
+            let bottom: bool = point.y >= client.bottom - self.resize_border;
+            let border: Option<HitTest> = match (left, right, top, bottom) {
+                (true, _, true, _) => Some(HitTest::TopLeft),
+                (_, true, true, _) => Some(HitTest::TopRight),
+                (true, _, _, true) => Some(HitTest::BottomLeft),
+                (_, true, _, true) => Some(HitTest::BottomRight),
+                (true, ..) => Some(HitTest::Left),
+                (_, true, ..) => Some(HitTest::Right),
+                (_, _, true, _) => Some(HitTest::Top),
+                (.., true) => Some(HitTest::Bottom),
+                _ => None,
+            };
+            if let Some(border) = border {
+                return border;
+            }
+        }
+
+        for button in self.buttons.iter() {
+            if let Some(rect) = self.button_rect(*button, client) {
+                if rect.contains(point) {
+                    return HitTest::Button(*button);
+                }
+            }
+        }
+        if self
+            .client_regions
+            .iter()
+            .any(|region| region.contains(point))
+        {
+            return HitTest::Client;
+        }
+        if self.caption_rect(client).contains(point)
+            || self
+                .drag_regions
+                .iter()
+                .any(|region| region.contains(point))
+        {
+            return HitTest::Caption;
+        }
+        return HitTest::Client;
+    }
+}
+
+/**
+    The client rect to answer WM_NCCALCSIZE with for a window with a custom chrome: the whole window rect. Windows
+    puts the borders of a maximized window beyond the edges of the monitor, that part is cut off so the title bar
+    of the app stays on the screen.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-nccalcsize
+*/
+pub fn chrome_client_rect(
+    window: Rect,
+    style: WINDOW_STYLE,
+    ex_style: WINDOW_EX_STYLE,
+    maximized: bool,
+    metrics: &FrameMetrics,
+) -> Rect {
+    if !maximized {
+        return window;
+    }
+    // The borders are the same on every side, the top one only has the title bar on top of it
+    let frame: Rect = adjust_window_rect(Rect::default(), style, ex_style, false, metrics);
+    let border: i32 = -frame.left;
+    return window.inflate(-border, -border);
+}
+
+/**
+    The hover and pressed state of the caption buttons, for drawing them. A button looks pressed while it is both
+    pressed and hovered, like the native ones: moving off a pressed button and releasing there doesn't click it.
+*/
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct CaptionButtonState {
+    pub hovered: Option<CaptionButton>,
+    pub pressed: Option<CaptionButton>,
+}
+
+impl CaptionButtonState {
+    /// The cursor moved over `button`, or off the buttons.
+    pub fn on_hover(&mut self, button: Option<CaptionButton>) {
+        self.hovered = button;
+    }
+
+    pub fn on_press(&mut self, button: CaptionButton) {
+        self.hovered = Some(button);
+        self.pressed = Some(button);
+    }
+
+    /// The left button went up over `button`. Returns the clicked button: the one it was pressed on as well.
+    pub fn on_release(&mut self, button: Option<CaptionButton>) -> Option<CaptionButton> {
+        let pressed: Option<CaptionButton> = self.pressed.take();
+        self.hovered = button;
+        return pressed.filter(|pressed| Some(*pressed) == button);
+    }
+
+    /// The cursor left the title bar, a press that is still held is cancelled.
+    pub fn on_leave(&mut self) {
+        self.hovered = None;
+        self.pressed = None;
+    }
+
+    pub fn is_hovered(&self, button: CaptionButton) -> bool {
+        return self.hovered == Some(button);
+    }
+
+    pub fn is_pressed(&self, button: CaptionButton) -> bool {
+        return self.pressed == Some(button) && self.hovered == Some(button);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIENT: Rect = Rect {
+        left: 0,
+        top: 0,
+        right: 800,
+        bottom: 600,
+    };
+
+    fn hit(layout: &ChromeLayout, x: i32, y: i32) -> HitTest {
+        return layout.hit_test(ClientPoint { x, y }, CLIENT, true);
+    }
+
+    #[test]
+    fn corners_and_edges() {
+        let layout: ChromeLayout = ChromeLayout::new();
+        assert_eq!(hit(&layout, 0, 0), HitTest::TopLeft);
+        assert_eq!(hit(&layout, 7, 7), HitTest::TopLeft);
+        assert_eq!(hit(&layout, 799, 0), HitTest::TopRight);
+        assert_eq!(hit(&layout, 0, 599), HitTest::BottomLeft);
+        assert_eq!(hit(&layout, 799, 599), HitTest::BottomRight);
+
+        assert_eq!(hit(&layout, 400, 2), HitTest::Top);
+        assert_eq!(hit(&layout, 400, 599), HitTest::Bottom);
+        assert_eq!(hit(&layout, 0, 300), HitTest::Left);
+        assert_eq!(hit(&layout, 799, 300), HitTest::Right);
+        assert_eq!(hit(&layout, 792, 300), HitTest::Right);
+        assert_eq!(hit(&layout, 791, 300), HitTest::Client);
+        assert_eq!(hit(&layout, 400, 8), HitTest::Caption);
+    }
+
+    #[test]
+    fn caption_buttons() {
+        let layout: ChromeLayout = ChromeLayout::new();
+        assert_eq!(
+            layout.button_rect(CaptionButton::Minimize, CLIENT),
+            Some(Rect {
+                left: 662,
+                top: 0,
+                right: 708,
+                bottom: 32
+            })
+        );
+        assert_eq!(
+            hit(&layout, 662, 10),
+            HitTest::Button(CaptionButton::Minimize)
+        );
+        assert_eq!(
+            hit(&layout, 700, 10),
+            HitTest::Button(CaptionButton::Minimize)
+        );
+        assert_eq!(
+            hit(&layout, 708, 10),
+            HitTest::Button(CaptionButton::Maximize)
+        );
+        assert_eq!(hit(&layout, 760, 10), HitTest::Button(CaptionButton::Close));
+        assert_eq!(hit(&layout, 790, 31), HitTest::Button(CaptionButton::Close));
+        assert_eq!(hit(&layout, 661, 10), HitTest::Caption);
+        assert_eq!(hit(&layout, 760, 32), HitTest::Client);
+        // The resize border reaches over the buttons
+        assert_eq!(hit(&layout, 760, 3), HitTest::Top);
+
+        let close_only: ChromeLayout = ChromeLayout::new().buttons(&[CaptionButton::Close]);
+        assert_eq!(
+            close_only.button_rect(CaptionButton::Minimize, CLIENT),
+            None
+        );
+        assert_eq!(hit(&close_only, 700, 10), HitTest::Caption);
+    }
+
+    #[test]
+    fn drag_and_client_regions() {
+        let layout: ChromeLayout = ChromeLayout::new()
+            .client_region(Rect {
+                left: 8,
+                top: 0,
+                right: 300,
+                bottom: 32,
+            })
+            .drag_region(Rect {
+                left: 0,
+                top: 40,
+                right: 100,
+                bottom: 60,
+            });
+        assert_eq!(hit(&layout, 100, 10), HitTest::Client);
+        assert_eq!(hit(&layout, 400, 10), HitTest::Caption);
+        assert_eq!(hit(&layout, 50, 50), HitTest::Caption);
+        assert_eq!(hit(&layout, 150, 50), HitTest::Client);
+        assert_eq!(hit(&layout, 400, 300), HitTest::Client);
+        // Only a drag region, without a title bar
+        let toolbar: ChromeLayout = layout.caption_height(0).buttons(&[]);
+        assert_eq!(hit(&toolbar, 400, 10), HitTest::Client);
+        assert_eq!(hit(&toolbar, 50, 50), HitTest::Caption);
+    }
+
+    #[test]
+    fn not_resizable() {
+        let layout: ChromeLayout = ChromeLayout::new();
+        let hit_fixed = |x: i32, y: i32| layout.hit_test(ClientPoint { x, y }, CLIENT, false);
+        assert_eq!(hit_fixed(0, 0), HitTest::Caption);
+        assert_eq!(hit_fixed(799, 0), HitTest::Button(CaptionButton::Close));
+        assert_eq!(hit_fixed(0, 300), HitTest::Client);
+        assert_eq!(hit_fixed(799, 599), HitTest::Client);
+
+        let no_border: ChromeLayout = ChromeLayout::new().resize_border(0);
+        assert_eq!(hit(&no_border, 0, 0), HitTest::Caption);
+        assert_eq!(hit(&no_border, 400, 599), HitTest::Client);
+    }
+
+    #[test]
+    fn outside_the_window() {
+        let layout: ChromeLayout = ChromeLayout::new();
+        assert_eq!(hit(&layout, 900, 10), HitTest::Nowhere);
+        assert_eq!(hit(&layout, -1, 10), HitTest::Nowhere);
+        assert_eq!(hit(&layout, 400, 600), HitTest::Nowhere);
+    }
+
+    #[test]
+    fn scaled_layout() {
+        let layout: ChromeLayout = ChromeLayout::new().scaled(1.5);
+        assert_eq!(
+            (
+                layout.caption_height,
+                layout.button_width,
+                layout.resize_border
+            ),
+            (48, 69, 12)
+        );
+        assert_eq!(hit(&layout, 400, 40), HitTest::Caption);
+        assert_eq!(hit(&layout, 740, 40), HitTest::Button(CaptionButton::Close));
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for hit_test in [
+            HitTest::Client,
+            HitTest::Caption,
+            HitTest::Left,
+            HitTest::Right,
+            HitTest::Top,
+            HitTest::Bottom,
+            HitTest::TopLeft,
+            HitTest::TopRight,
+            HitTest::BottomLeft,
+            HitTest::BottomRight,
+            HitTest::Button(CaptionButton::Minimize),
+            HitTest::Button(CaptionButton::Maximize),
+            HitTest::Button(CaptionButton::Close),
+            HitTest::Nowhere,
+        ] {
+            assert_eq!(HitTest::from_code(hit_test.code()), hit_test);
+        }
+        assert_eq!(HitTest::from_code(0xFFFF), HitTest::Nowhere);
+    }
+
+    #[test]
+    fn a_button_clicks_when_released_on_it() {
+        let mut state: CaptionButtonState = CaptionButtonState::default();
+        state.on_press(CaptionButton::Close);
+        assert!(state.is_pressed(CaptionButton::Close));
+        state.on_hover(Some(CaptionButton::Maximize));
+        assert!(!state.is_pressed(CaptionButton::Close));
+        state.on_hover(Some(CaptionButton::Close));
+        assert_eq!(
+            state.on_release(Some(CaptionButton::Close)),
+            Some(CaptionButton::Close)
+        );
+
+        state.on_press(CaptionButton::Close);
+        assert_eq!(state.on_release(Some(CaptionButton::Maximize)), None);
+        state.on_press(CaptionButton::Minimize);
+        state.on_leave();
+        assert_eq!(state.on_release(Some(CaptionButton::Minimize)), None);
+    }
+}
@@ -0,0 +1,422 @@
+/*
+    The standard clipboard formats we use. They live in the Ole module of the windows crate, which we don't need
+    for anything else. See https://learn.microsoft.com/en-us/windows/win32/dataxchg/standard-clipboard-formats
+*/
+/// UTF-16 text with a NUL at the end.
+pub const CF_UNICODETEXT: u32 = 13;
+/// A BITMAPINFO followed by the pixels.
+pub const CF_DIB: u32 = 8;
+/// A BITMAPV5HEADER followed by the pixels. Windows converts between this and [CF_DIB] for us.
+pub const CF_DIBV5: u32 = 17;
+/// Registered formats get an id from here up to 0xFFFF.
+pub const FIRST_REGISTERED_FORMAT: u32 = 0xC000;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BITMAPINFOHEADER_SIZE: usize = 40;
+const BITMAPV5HEADER_SIZE: usize = 124;
+/// 'sRGB' as a little-endian u32, the color space of a BITMAPV5HEADER.
+const LCS_SRGB: u32 = 0x7352_4742;
+const LCS_GM_IMAGES: u32 = 4;
+
+/**
+    A format an app registered with a name. Every app that registers the same name gets the same id, so this is how
+    two apps agree on a format.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/dataxchg/clipboard-formats#registered-clipboard-formats
+*/
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ClipboardFormat {
+    pub id: u32,
+    pub name: String,
+}
+
+/// An image with 8 bits per channel, row by row from the top.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ClipboardImage {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, not premultiplied. `width * height * 4` bytes.
+    pub pixels: Vec<u8>,
+}
+
+/// One format of the clipboard contents. Put more than one on the clipboard to let the app that pastes pick.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ClipboardItem {
+    Text(String),
+    Image(ClipboardImage),
+    /// A registered format with the bytes of the app.
+    Data(ClipboardFormat, Vec<u8>),
+}
+
+impl ClipboardImage {
+    /// [None] when `pixels` doesn't have `width * height * 4` bytes.
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Option<ClipboardImage> {
+        if pixels.len() as u64 != width as u64 * height as u64 * 4 {
+            return None;
+        }
+        return Some(ClipboardImage {
+            width,
+            height,
+            pixels,
+        });
+    }
+
+    /**
+        The [CF_DIBV5] bytes for this image: a BITMAPV5HEADER with masks for BGRA, then the rows from the top. The
+        header has an alpha mask, so apps that understand it keep the transparency.
+
+        For more info see: https://learn.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-bitmapv5header
+    */
+    pub fn to_dib_v5(&self) -> Vec<u8> {
+        let image_size: u32 = self.width * self.height * 4;
+        let mut bytes: Vec<u8> = Vec::with_capacity(BITMAPV5HEADER_SIZE + image_size as usize);
+        bytes.extend_from_slice(&(BITMAPV5HEADER_SIZE as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.width as i32).to_le_bytes());
+        // A negative height means the rows go from the top down
+        bytes.extend_from_slice(&(-(self.height as i32)).to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&32u16.to_le_bytes());
+        bytes.extend_from_slice(&BI_BITFIELDS.to_le_bytes());
+        bytes.extend_from_slice(&image_size.to_le_bytes());
+        // 72 DPI in pixels per meter, the resolution doesn't matter for the clipboard
+        bytes.extend_from_slice(&2835i32.to_le_bytes());
+        bytes.extend_from_slice(&2835i32.to_le_bytes());
+        bytes.extend_from_slice(&[0; 8]);
+        for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+            bytes.extend_from_slice(&mask.to_le_bytes());
+        }
+        bytes.extend_from_slice(&LCS_SRGB.to_le_bytes());
+        // The end points and gamma are only used for a calibrated color space
+        bytes.extend_from_slice(&[0; 36 + 12]);
+        bytes.extend_from_slice(&LCS_GM_IMAGES.to_le_bytes());
+        bytes.extend_from_slice(&[0; 12]);
+
+        for pixel in self.pixels.chunks_exact(4) {
+            bytes.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+        }
+        return bytes;
+    }
+
+    /**
+        Read [CF_DIB] or [CF_DIBV5] bytes. Only uncompressed 24 and 32 bit images are supported, that is what
+        windows and the common apps put on the clipboard. A 32 bit image without an alpha mask, or with an alpha of 0
+        everywhere, is opaque: a lot of apps leave the alpha empty.
+
+        For more info see: https://learn.microsoft.com/en-us/windows/win32/gdi/device-independent-bitmaps
+    */
+    pub fn from_dib(bytes: &[u8]) -> Option<ClipboardImage> {
+        let header_size: usize = read_u32(bytes, 0)? as usize;
+        if header_size < BITMAPINFOHEADER_SIZE {
+            return None;
+        }
+        let width: i32 = read_u32(bytes, 4)? as i32;
+        let height: i32 = read_u32(bytes, 8)? as i32;
+        let bit_count: u16 = read_u16(bytes, 14)?;
+        let compression: u32 = read_u32(bytes, 16)?;
+        let colors_used: usize = read_u32(bytes, 32)? as usize;
+        if width <= 0 || height == 0 || !matches!(bit_count, 24 | 32) {
+            return None;
+        }
+
+        let mut masks: [u32; 4] = [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0];
+        let mut offset: usize = header_size;
+        match compression {
+            BI_RGB => {}
+            BI_BITFIELDS if bit_count == 32 => {
+                // An old header has the masks after it, the newer ones have them inside
+                let masks_at: usize = BITMAPINFOHEADER_SIZE;
+                for (index, mask) in masks.iter_mut().take(3).enumerate() {
+                    *mask = read_u32(bytes, masks_at + index * 4)?;
+                }
+                if header_size == BITMAPINFOHEADER_SIZE {
+                    offset += 12;
+                } else if header_size >= 56 {
+                    masks[3] = read_u32(bytes, masks_at + 12)?;
+                }
+            }
+            _ => return None,
+        }
+        offset += colors_used * 4;
+
+        let (width, rows): (usize, usize) = (width as usize, height.unsigned_abs() as usize);
+        let stride: usize = (width * bit_count as usize).div_ceil(32) * 4;
+        let data: &[u8] = bytes.get(offset..offset.checked_add(stride.checked_mul(rows)?)?)?;
+
+        let mut pixels: Vec<u8> = Vec::with_capacity(width * rows * 4);
+        for row in 0..rows {
+            // A positive height means the rows go from the bottom up
+            let source: usize = if height > 0 { rows - 1 - row } else { row };
+            let line: &[u8] = &data[source * stride..];
+            for x in 0..width {
+                if bit_count == 24 {
+                    let pixel: &[u8] = &line[x * 3..x * 3 + 3];
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], 255]);
+                    continue;
+                }
+                let value: u32 = u32::from_le_bytes([
+                    line[x * 4],
+                    line[x * 4 + 1],
+                    line[x * 4 + 2],
+                    line[x * 4 + 3],
+                ]);
+                for mask in masks {
+                    pixels.push(extract_channel(value, mask));
+                }
+            }
+        }
+
+        if masks[3] == 0 || pixels.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel[3] = 255;
+            }
+        }
+        return Some(ClipboardImage {
+            width: width as u32,
+            height: rows as u32,
+            pixels,
+        });
+    }
+}
+
+/**
+    A clipboard that only exists in memory, used by the [headless](super::backend::headless::HeadlessBackend)
+    backend. It behaves like the Win32 one: setting replaces everything, format names are case insensitive and
+    an image can be read from what another app put on the clipboard as [CF_DIB] bytes.
+*/
+#[derive(Debug, Default, Clone)]
+pub struct MemoryClipboard {
+    items: Vec<ClipboardItem>,
+    formats: Vec<ClipboardFormat>,
+    /// Goes up with every change, like GetClipboardSequenceNumber.
+    sequence_number: u32,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> MemoryClipboard {
+        return MemoryClipboard::default();
+    }
+
+    pub fn items(&self) -> &[ClipboardItem] {
+        return &self.items;
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        return self.sequence_number;
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.sequence_number += 1;
+    }
+
+    pub fn text(&self) -> Option<String> {
+        return self.items.iter().find_map(|item| match item {
+            ClipboardItem::Text(text) => Some(text.clone()),
+            _ => None,
+        });
+    }
+
+    pub fn image(&self) -> Option<ClipboardImage> {
+        return self.items.iter().find_map(|item| match item {
+            ClipboardItem::Image(image) => Some(image.clone()),
+            ClipboardItem::Data(format, bytes) if matches!(format.id, CF_DIB | CF_DIBV5) => {
+                ClipboardImage::from_dib(bytes)
+            }
+            _ => None,
+        });
+    }
+
+    pub fn data(&self, format: &ClipboardFormat) -> Option<Vec<u8>> {
+        return self.items.iter().find_map(|item| match item {
+            ClipboardItem::Data(item_format, bytes) if item_format.id == format.id => {
+                Some(bytes.clone())
+            }
+            _ => None,
+        });
+    }
+
+    /// Replace the contents with `items`.
+    pub fn set(&mut self, items: &[ClipboardItem]) {
+        self.items = items.to_vec();
+        self.sequence_number += 1;
+    }
+
+    /// The format for `name`, the same one every time. [None] when there are no ids left.
+    pub fn register_format(&mut self, name: &str) -> Option<ClipboardFormat> {
+        if let Some(format) = self
+            .formats
+            .iter()
+            .find(|format| format.name.eq_ignore_ascii_case(name))
+        {
+            return Some(format.clone());
+        }
+        let id: u32 = FIRST_REGISTERED_FORMAT + self.formats.len() as u32;
+        if id > 0xFFFF {
+            return None;
+        }
+        let format: ClipboardFormat = ClipboardFormat {
+            id,
+            name: name.to_string(),
+        };
+        self.formats.push(format.clone());
+        return Some(format);
+    }
+}
+
+/// Scale the bits of `mask` in `value` to 0-255.
+fn extract_channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift: u32 = mask.trailing_zeros();
+    let max: u32 = mask >> shift;
+    let channel: u32 = (value & mask) >> shift;
+    return ((channel * 255 + max / 2) / max) as u8;
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes: &[u8] = bytes.get(offset..offset + 2)?;
+    return Some(u16::from_le_bytes([bytes[0], bytes[1]]));
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes: &[u8] = bytes.get(offset..offset + 4)?;
+    return Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A BITMAPINFOHEADER without compression, followed by `pixels`.
+    fn dib(width: i32, height: i32, bit_count: u16, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![];
+        bytes.extend_from_slice(&(BITMAPINFOHEADER_SIZE as u32).to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&bit_count.to_le_bytes());
+        bytes.extend_from_slice(&BI_RGB.to_le_bytes());
+        bytes.extend_from_slice(&[0; 20]);
+        bytes.extend_from_slice(pixels);
+        return bytes;
+    }
+
+    #[test]
+    fn memory_clipboard_round_trips() {
+        let mut clipboard: MemoryClipboard = MemoryClipboard::new();
+        assert_eq!(clipboard.text(), None);
+        assert_eq!(clipboard.image(), None);
+
+        let image: ClipboardImage = ClipboardImage::new(1, 1, vec![10, 20, 30, 40]).unwrap();
+        let format: ClipboardFormat = clipboard.register_format("Test.Shapes").unwrap();
+        clipboard.set(&[
+            ClipboardItem::Text("Hello".to_string()),
+            ClipboardItem::Image(image.clone()),
+            ClipboardItem::Data(format.clone(), vec![1, 2, 3]),
+        ]);
+        assert_eq!(clipboard.text(), Some("Hello".to_string()));
+        assert_eq!(clipboard.image(), Some(image));
+        assert_eq!(clipboard.data(&format), Some(vec![1, 2, 3]));
+        assert_eq!(clipboard.sequence_number(), 1);
+
+        // Setting replaces everything
+        clipboard.set(&[ClipboardItem::Text("World".to_string())]);
+        assert_eq!(clipboard.text(), Some("World".to_string()));
+        assert_eq!(clipboard.image(), None);
+        assert_eq!(clipboard.data(&format), None);
+        clipboard.clear();
+        assert!(clipboard.items().is_empty());
+        assert_eq!(clipboard.sequence_number(), 3);
+    }
+
+    #[test]
+    fn registered_formats() {
+        let mut clipboard: MemoryClipboard = MemoryClipboard::new();
+        let shapes: ClipboardFormat = clipboard.register_format("Test.Shapes").unwrap();
+        assert_eq!(shapes.id, FIRST_REGISTERED_FORMAT);
+        // Names are case insensitive
+        assert_eq!(
+            clipboard.register_format("test.shapes"),
+            Some(shapes.clone())
+        );
+        let other: ClipboardFormat = clipboard.register_format("Test.Other").unwrap();
+        assert_eq!(other.id, FIRST_REGISTERED_FORMAT + 1);
+
+        clipboard.set(&[ClipboardItem::Data(other.clone(), vec![7])]);
+        assert_eq!(clipboard.data(&shapes), None);
+        assert_eq!(clipboard.data(&other), Some(vec![7]));
+    }
+
+    #[test]
+    fn dib_v5_round_trip() {
+        let image: ClipboardImage = ClipboardImage::new(
+            2,
+            2,
+            vec![
+                255, 0, 0, 255, 0, 255, 0, 128, //
+                0, 0, 255, 64, 10, 20, 30, 1,
+            ],
+        )
+        .unwrap();
+        let bytes: Vec<u8> = image.to_dib_v5();
+        assert_eq!(bytes.len(), BITMAPV5HEADER_SIZE + 16);
+        assert_eq!(ClipboardImage::from_dib(&bytes), Some(image));
+        assert_eq!(ClipboardImage::new(2, 2, vec![0; 15]), None);
+    }
+
+    #[test]
+    fn dib_rows_are_padded_to_four_bytes() {
+        // 3 pixels of 24 bits are 9 bytes, the rows are 12 bytes apart. The bottom row comes first.
+        let pixels: Vec<u8> = vec![
+            255, 255, 255, 0, 0, 0, 128, 128, 128, 0, 0, 0, //
+            0, 0, 255, 0, 255, 0, 255, 0, 0, 0, 0, 0,
+        ];
+        let image: ClipboardImage = ClipboardImage::from_dib(&dib(3, 2, 24, &pixels)).unwrap();
+        assert_eq!((image.width, image.height), (3, 2));
+        assert_eq!(
+            image.pixels,
+            vec![
+                255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, //
+                255, 255, 255, 255, 0, 0, 0, 255, 128, 128, 128, 255,
+            ]
+        );
+        // A row that is cut short is rejected
+        assert_eq!(
+            ClipboardImage::from_dib(&dib(3, 2, 24, &pixels[..20])),
+            None
+        );
+        // A single pixel still takes 4 bytes
+        let image: ClipboardImage =
+            ClipboardImage::from_dib(&dib(1, 1, 24, &[1, 2, 3, 0])).unwrap();
+        assert_eq!(image.pixels, vec![3, 2, 1, 255]);
+    }
+
+    #[test]
+    fn dib_without_alpha_is_opaque() {
+        // 32 bits without a mask, from the top down, with the alpha left at 0
+        let image: ClipboardImage =
+            ClipboardImage::from_dib(&dib(2, -1, 32, &[1, 2, 3, 0, 4, 5, 6, 0])).unwrap();
+        assert_eq!(image.pixels, vec![3, 2, 1, 255, 6, 5, 4, 255]);
+        // Other bit counts aren't supported
+        assert_eq!(
+            ClipboardImage::from_dib(&dib(1, 1, 16, &[0, 0, 0, 0])),
+            None
+        );
+    }
+
+    #[test]
+    fn dib_data_from_another_app_is_an_image() {
+        let mut clipboard: MemoryClipboard = MemoryClipboard::new();
+        let format: ClipboardFormat = ClipboardFormat {
+            id: CF_DIB,
+            name: "CF_DIB".to_string(),
+        };
+        clipboard.set(&[ClipboardItem::Data(format, dib(1, 1, 24, &[1, 2, 3, 0]))]);
+        assert_eq!(
+            clipboard.image(),
+            ClipboardImage::new(1, 1, vec![3, 2, 1, 255])
+        );
+    }
+}
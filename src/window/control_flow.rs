@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+/// The timeout value which makes MsgWaitForMultipleObjects wait forever.
+pub const INFINITE: u32 = u32::MAX;
+
+/**
+    What [Window::handle_messages()](super::Window::handle_messages()) does when there are no messages left.
+
+    A game wants [ControlFlow::Poll] so it can render as fast as possible. A tool or editor only has to redraw when
+    something happens, with [ControlFlow::Wait] the thread sleeps until the next message and uses no CPU at all.
+    Call [Window::request_redraw()](super::Window::request_redraw()) to wake the loop yourself.
+*/
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum ControlFlow {
+    /// Return right away, even when there were no messages.
+    #[default]
+    Poll,
+    /// Block until a message arrives.
+    Wait,
+    /// Block until a message arrives or the deadline has passed, whichever comes first.
+    WaitUntil(Instant),
+}
+
+impl ControlFlow {
+    /**
+        The timeout in milliseconds for MsgWaitForMultipleObjects at `now`. [ControlFlow::Wait] gives [INFINITE] and
+        a deadline in the past gives 0. The timeout is rounded up, rounding down would wake us just before the
+        deadline and make us wait a second time for less than a millisecond.
+    */
+    pub fn timeout_ms(&self, now: Instant) -> u32 {
+        return match self {
+            ControlFlow::Poll => 0,
+            ControlFlow::Wait => INFINITE,
+            ControlFlow::WaitUntil(deadline) => {
+                let remaining: Duration = deadline.saturating_duration_since(now);
+                let millis: u128 = remaining.as_nanos().div_ceil(1_000_000);
+                // INFINITE is reserved, a very long wait is still a wait with a deadline
+                millis.min((INFINITE - 1) as u128) as u32
+            }
+        };
+    }
+
+    /// Wake up at `deadline` at the latest, used for the timers. [ControlFlow::Poll] doesn't wait at all so it stays.
+    pub fn until(self, deadline: Instant) -> ControlFlow {
+        return match self {
+            ControlFlow::Poll => ControlFlow::Poll,
+            ControlFlow::Wait => ControlFlow::WaitUntil(deadline),
+            ControlFlow::WaitUntil(current) => ControlFlow::WaitUntil(current.min(deadline)),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_rounds_up() {
+        let now: Instant = Instant::now();
+        let after = |nanos: u64| ControlFlow::WaitUntil(now + Duration::from_nanos(nanos));
+
+        assert_eq!(ControlFlow::Poll.timeout_ms(now), 0);
+        assert_eq!(ControlFlow::Wait.timeout_ms(now), INFINITE);
+        assert_eq!(after(0).timeout_ms(now), 0);
+        assert_eq!(after(1).timeout_ms(now), 1);
+        assert_eq!(after(1_000_000).timeout_ms(now), 1);
+        assert_eq!(after(1_000_001).timeout_ms(now), 2);
+        // A deadline in the past doesn't wait
+        assert_eq!(
+            ControlFlow::WaitUntil(now).timeout_ms(now + Duration::from_secs(1)),
+            0
+        );
+        assert_eq!(after(u64::MAX).timeout_ms(now), INFINITE - 1);
+    }
+
+    #[test]
+    fn until_keeps_the_earliest_deadline() {
+        let now: Instant = Instant::now();
+        let soon: Instant = now + Duration::from_millis(5);
+        let later: Instant = now + Duration::from_millis(50);
+
+        assert_eq!(ControlFlow::Poll.until(soon), ControlFlow::Poll);
+        assert_eq!(ControlFlow::Wait.until(soon), ControlFlow::WaitUntil(soon));
+        assert_eq!(
+            ControlFlow::WaitUntil(later).until(soon),
+            ControlFlow::WaitUntil(soon)
+        );
+        assert_eq!(
+            ControlFlow::WaitUntil(soon).until(later),
+            ControlFlow::WaitUntil(soon)
+        );
+    }
+}
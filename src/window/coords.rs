@@ -0,0 +1,365 @@
+use windows::Win32::Foundation::{POINT, POINTS};
+
+/// The DPI windows uses for a scale factor of 1.0 (100%).
+pub const BASE_DPI: u32 = 96;
+
+/// A point in physical pixels relative to the top-left corner of the client area.<br>
+/// This is what [WM_MOUSEMOVE][mm] gives you.
+/// [mm]: windows::Win32::UI::WindowsAndMessaging::WM_MOUSEMOVE
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct ClientPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A point in physical pixels relative to the top-left corner of the primary monitor.<br>
+/// This is what [WM_MOUSEWHEEL][mw] and the pointer messages give you.
+/// [mw]: windows::Win32::UI::WindowsAndMessaging::WM_MOUSEWHEEL
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct ScreenPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A point in physical (device) pixels.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct PhysicalPoint {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// A point in logical (device independent) pixels. One logical pixel is one physical pixel at 96 DPI.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct LogicalPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A size in physical (device) pixels. This is what the Win32 API uses.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct PhysicalSize {
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A size in logical (device independent) pixels. The same logical size looks the same on every monitor.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct LogicalSize {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A size in logical or physical pixels, see [WindowBuilder::inner_size()](super::builder::WindowBuilder::inner_size()).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Size {
+    Logical(LogicalSize),
+    Physical(PhysicalSize),
+}
+
+/// A position in logical or physical pixels, see [WindowBuilder::position()](super::builder::WindowBuilder::position()).
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Position {
+    Logical(LogicalPoint),
+    Physical(PhysicalPoint),
+}
+
+/// Turn a DPI value (from [GetDpiForWindow][d]) into a scale factor. 96 DPI is 1.0, 144 DPI is 1.5.
+/// [d]: windows::Win32::UI::HiDpi::GetDpiForWindow
+pub fn scale_factor_from_dpi(dpi: u32) -> f64 {
+    if dpi == 0 {
+        // GetDpiForWindow returns 0 for an invalid window
+        return 1.0;
+    }
+    return dpi as f64 / BASE_DPI as f64;
+}
+
+/// The DPI for a scale factor, rounded to the nearest DPI. The inverse of [scale_factor_from_dpi()].
+pub fn dpi_from_scale_factor(scale_factor: f64) -> u32 {
+    return (scale_factor * BASE_DPI as f64).round().max(1.0) as u32;
+}
+
+/**
+    Scale a physical length from one DPI to another, like [MulDiv][m]`(value, to_dpi, from_dpi)`. The result is
+    rounded to the nearest pixel (halves away from zero), so scaling back and forth doesn't drift.
+    [m]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-muldiv
+*/
+pub fn scale_for_dpi(value: i32, from_dpi: u32, to_dpi: u32) -> i32 {
+    if from_dpi == 0 || from_dpi == to_dpi {
+        return value;
+    }
+    let scaled: f64 = value as f64 * to_dpi as f64 / from_dpi as f64;
+    return scaled.round() as i32;
+}
+
+impl ScreenPoint {
+    /// Convert to client coordinates. `client_origin` is the top-left corner of the client area in
+    /// screen coordinates (what [ClientToScreen][c] gives you for (0, 0)).
+    /// [c]: windows::Win32::Graphics::Gdi::ClientToScreen
+    pub fn to_client(self, client_origin: ScreenPoint) -> ClientPoint {
+        return ClientPoint {
+            x: self.x - client_origin.x,
+            y: self.y - client_origin.y,
+        };
+    }
+}
+
+impl ClientPoint {
+    /// Convert to screen coordinates. See [ScreenPoint::to_client()].
+    pub fn to_screen(self, client_origin: ScreenPoint) -> ScreenPoint {
+        return ScreenPoint {
+            x: self.x + client_origin.x,
+            y: self.y + client_origin.y,
+        };
+    }
+
+    /// Convert to logical pixels with the scale factor of the window.
+    pub fn to_logical(self, scale_factor: f64) -> LogicalPoint {
+        return PhysicalPoint::from(self).to_logical(scale_factor);
+    }
+}
+
+impl PhysicalPoint {
+    pub fn new(x: i32, y: i32) -> PhysicalPoint {
+        return PhysicalPoint { x, y };
+    }
+
+    pub fn to_logical(self, scale_factor: f64) -> LogicalPoint {
+        return LogicalPoint {
+            x: self.x as f64 / scale_factor,
+            y: self.y as f64 / scale_factor,
+        };
+    }
+}
+
+impl LogicalPoint {
+    pub fn new(x: f64, y: f64) -> LogicalPoint {
+        return LogicalPoint { x, y };
+    }
+
+    /// Convert to physical pixels, rounded to the nearest pixel.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalPoint {
+        return PhysicalPoint {
+            x: (self.x * scale_factor).round() as i32,
+            y: (self.y * scale_factor).round() as i32,
+        };
+    }
+}
+
+impl PhysicalSize {
+    pub fn new(width: i32, height: i32) -> PhysicalSize {
+        return PhysicalSize { width, height };
+    }
+
+    pub fn to_logical(self, scale_factor: f64) -> LogicalSize {
+        return LogicalSize {
+            width: self.width as f64 / scale_factor,
+            height: self.height as f64 / scale_factor,
+        };
+    }
+
+    /// The same size on a monitor with another DPI, see [scale_for_dpi()].
+    pub fn scale_for_dpi(self, from_dpi: u32, to_dpi: u32) -> PhysicalSize {
+        return PhysicalSize {
+            width: scale_for_dpi(self.width, from_dpi, to_dpi),
+            height: scale_for_dpi(self.height, from_dpi, to_dpi),
+        };
+    }
+}
+
+impl LogicalSize {
+    pub fn new(width: f64, height: f64) -> LogicalSize {
+        return LogicalSize { width, height };
+    }
+
+    /// Convert to physical pixels, rounded to the nearest pixel.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize {
+        return PhysicalSize {
+            width: (self.width * scale_factor).round() as i32,
+            height: (self.height * scale_factor).round() as i32,
+        };
+    }
+}
+
+impl Size {
+    /// The size in physical pixels at `scale_factor`. A physical size stays the same.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalSize {
+        return match self {
+            Size::Logical(size) => size.to_physical(scale_factor),
+            Size::Physical(size) => size,
+        };
+    }
+}
+
+impl Position {
+    /// The position in physical pixels at `scale_factor`. A physical position stays the same.
+    pub fn to_physical(self, scale_factor: f64) -> PhysicalPoint {
+        return match self {
+            Position::Logical(point) => point.to_physical(scale_factor),
+            Position::Physical(point) => point,
+        };
+    }
+}
+
+impl From<LogicalSize> for Size {
+    fn from(size: LogicalSize) -> Self {
+        return Size::Logical(size);
+    }
+}
+
+impl From<PhysicalSize> for Size {
+    fn from(size: PhysicalSize) -> Self {
+        return Size::Physical(size);
+    }
+}
+
+impl From<LogicalPoint> for Position {
+    fn from(point: LogicalPoint) -> Self {
+        return Position::Logical(point);
+    }
+}
+
+impl From<PhysicalPoint> for Position {
+    fn from(point: PhysicalPoint) -> Self {
+        return Position::Physical(point);
+    }
+}
+
+impl From<ClientPoint> for PhysicalPoint {
+    fn from(point: ClientPoint) -> Self {
+        return PhysicalPoint {
+            x: point.x,
+            y: point.y,
+        };
+    }
+}
+
+/// The lParam of a mouse message is a [POINTS], the coordinate space depends on the message.
+impl From<POINTS> for ClientPoint {
+    fn from(points: POINTS) -> Self {
+        return ClientPoint {
+            x: points.x.into(),
+            y: points.y.into(),
+        };
+    }
+}
+
+impl From<POINTS> for ScreenPoint {
+    fn from(points: POINTS) -> Self {
+        return ScreenPoint {
+            x: points.x.into(),
+            y: points.y.into(),
+        };
+    }
+}
+
+impl From<POINT> for ScreenPoint {
+    fn from(point: POINT) -> Self {
+        return ScreenPoint {
+            x: point.x,
+            y: point.y,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The DPI values of 100%, 125%, 150% and 200% scaling.
+    const DPIS: [u32; 4] = [96, 120, 144, 192];
+
+    #[test]
+    fn scale_factors() {
+        let factors: Vec<f64> = DPIS.iter().map(|dpi| scale_factor_from_dpi(*dpi)).collect();
+        assert_eq!(factors, vec![1.0, 1.25, 1.5, 2.0]);
+        for dpi in DPIS {
+            assert_eq!(dpi_from_scale_factor(scale_factor_from_dpi(dpi)), dpi);
+        }
+        assert_eq!(scale_factor_from_dpi(0), 1.0);
+        assert_eq!(dpi_from_scale_factor(1.2499), 120);
+        assert_eq!(dpi_from_scale_factor(0.0), 1);
+    }
+
+    #[test]
+    fn scale_for_dpi_rounds_halves_away_from_zero() {
+        assert_eq!(scale_for_dpi(1, 96, 120), 1);
+        assert_eq!(scale_for_dpi(2, 96, 120), 3);
+        assert_eq!(scale_for_dpi(-2, 96, 120), -3);
+        assert_eq!(scale_for_dpi(3, 96, 144), 5);
+        assert_eq!(scale_for_dpi(-3, 96, 144), -5);
+        assert_eq!(scale_for_dpi(3, 96, 192), 6);
+        assert_eq!(scale_for_dpi(3, 120, 96), 2);
+        assert_eq!(scale_for_dpi(5, 120, 96), 4);
+        assert_eq!(scale_for_dpi(5, 192, 96), 3);
+        assert_eq!(scale_for_dpi(7, 0, 144), 7);
+        assert_eq!(
+            PhysicalSize::new(800, 600).scale_for_dpi(96, 144),
+            PhysicalSize::new(1200, 900)
+        );
+    }
+
+    #[test]
+    fn scale_for_dpi_round_trip() {
+        // Scaling up and back down never drifts, scaling down first loses pixels
+        for (index, from_dpi) in DPIS.iter().enumerate() {
+            for to_dpi in DPIS[index..].iter() {
+                for value in -1000..1000 {
+                    let scaled: i32 = scale_for_dpi(value, *from_dpi, *to_dpi);
+                    assert_eq!(scale_for_dpi(scaled, *to_dpi, *from_dpi), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn to_physical_rounds_to_the_nearest_pixel() {
+        let point: LogicalPoint = LogicalPoint { x: 10.5, y: -10.5 };
+        assert_eq!(point.to_physical(1.0), PhysicalPoint { x: 11, y: -11 });
+        assert_eq!(point.to_physical(1.25), PhysicalPoint { x: 13, y: -13 });
+
+        let size: LogicalSize = LogicalSize::new(100.0, 33.0);
+        let sizes: Vec<PhysicalSize> = DPIS
+            .iter()
+            .map(|dpi| size.to_physical(scale_factor_from_dpi(*dpi)))
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                PhysicalSize::new(100, 33),
+                PhysicalSize::new(125, 41),
+                PhysicalSize::new(150, 50),
+                PhysicalSize::new(200, 66),
+            ]
+        );
+    }
+
+    #[test]
+    fn physical_round_trip() {
+        for dpi in DPIS {
+            let scale_factor: f64 = scale_factor_from_dpi(dpi);
+            for value in -500..500 {
+                let point: PhysicalPoint = PhysicalPoint {
+                    x: value,
+                    y: -value,
+                };
+                assert_eq!(
+                    point.to_logical(scale_factor).to_physical(scale_factor),
+                    point
+                );
+                let size: PhysicalSize = PhysicalSize::new(value, value + 1);
+                assert_eq!(
+                    size.to_logical(scale_factor).to_physical(scale_factor),
+                    size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn client_and_screen() {
+        let origin: ScreenPoint = ScreenPoint { x: 100, y: -50 };
+        let client: ClientPoint = ScreenPoint { x: 130, y: 20 }.to_client(origin);
+        assert_eq!(client, ClientPoint { x: 30, y: 70 });
+        assert_eq!(client.to_screen(origin), ScreenPoint { x: 130, y: 20 });
+    }
+}
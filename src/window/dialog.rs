@@ -0,0 +1,534 @@
+use std::path::{Path, PathBuf};
+
+use super::{backend, manager::WindowId};
+
+/// The icon on the left of a [MessageBox]. It also picks the sound windows plays when the box opens.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum MessageIcon {
+    #[default]
+    None,
+    Information,
+    Warning,
+    Error,
+    /// Microsoft recommends against it, a question is clear from the buttons already.
+    Question,
+}
+
+/// The buttons of a [MessageBox], in the order they are shown.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum MessageButtons {
+    #[default]
+    Ok,
+    OkCancel,
+    YesNo,
+    YesNoCancel,
+    RetryCancel,
+    AbortRetryIgnore,
+    CancelTryContinue,
+}
+
+/// The button the user pressed to close a dialog.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum DialogResult {
+    Ok,
+    Cancel,
+    Yes,
+    No,
+    Retry,
+    Abort,
+    Ignore,
+    TryAgain,
+    Continue,
+}
+
+impl MessageButtons {
+    /// The result of every button, from left to right.
+    pub fn results(&self) -> &'static [DialogResult] {
+        return match self {
+            MessageButtons::Ok => &[DialogResult::Ok],
+            MessageButtons::OkCancel => &[DialogResult::Ok, DialogResult::Cancel],
+            MessageButtons::YesNo => &[DialogResult::Yes, DialogResult::No],
+            MessageButtons::YesNoCancel => {
+                &[DialogResult::Yes, DialogResult::No, DialogResult::Cancel]
+            }
+            MessageButtons::RetryCancel => &[DialogResult::Retry, DialogResult::Cancel],
+            MessageButtons::AbortRetryIgnore => &[
+                DialogResult::Abort,
+                DialogResult::Retry,
+                DialogResult::Ignore,
+            ],
+            MessageButtons::CancelTryContinue => &[
+                DialogResult::Cancel,
+                DialogResult::TryAgain,
+                DialogResult::Continue,
+            ],
+        };
+    }
+
+    /**
+        What closing the box with Escape or the close button gives: Cancel, or Ok for a box with only an Ok button.
+        The boxes without either can't be closed that way, for those it is the answer that does nothing (No or
+        Abort). A box that couldn't be shown at all gives this as well, so a failure never counts as a "yes".
+    */
+    pub fn close_result(&self) -> DialogResult {
+        return match self {
+            MessageButtons::Ok => DialogResult::Ok,
+            MessageButtons::YesNo => DialogResult::No,
+            MessageButtons::AbortRetryIgnore => DialogResult::Abort,
+            _ => DialogResult::Cancel,
+        };
+    }
+}
+
+/**
+    A modal message box with a text, an icon and a set of buttons:
+    ```ignore
+    let answer: DialogResult = window.show_message_box(
+        MessageBox::new("Delete 3 files?")
+            .title("Confirm delete")
+            .icon(MessageIcon::Warning)
+            .buttons(MessageButtons::YesNo)
+            .default_button(DialogResult::No),
+    );
+    if answer == DialogResult::Yes {
+        self.delete_selection();
+    }
+    ```
+    The [headless](super::backend::headless::HeadlessBackend) backend answers from a script, see
+    [HeadlessBackend::answer_message_box()](super::backend::headless::HeadlessBackend::answer_message_box()).
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxexw
+*/
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct MessageBox {
+    pub title: String,
+    pub text: String,
+    pub icon: MessageIcon,
+    pub buttons: MessageButtons,
+    /// The button that has the focus when the box opens, the first one when [None] or not one of the buttons.
+    pub default_button: Option<DialogResult>,
+    /// The window that is disabled while the box is open, the box is shown on top of it.
+    pub owner: Option<WindowId>,
+}
+
+impl MessageBox {
+    pub fn new(text: &str) -> MessageBox {
+        return MessageBox {
+            text: text.to_string(),
+            ..Default::default()
+        };
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        return self;
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        return self;
+    }
+
+    pub fn icon(mut self, icon: MessageIcon) -> Self {
+        self.icon = icon;
+        return self;
+    }
+
+    pub fn buttons(mut self, buttons: MessageButtons) -> Self {
+        self.buttons = buttons;
+        return self;
+    }
+
+    pub fn default_button(mut self, default_button: DialogResult) -> Self {
+        self.default_button = Some(default_button);
+        return self;
+    }
+
+    /// See [Window::show_message_box()](super::Window::show_message_box()), which sets the owner for you.
+    pub fn owner(mut self, owner: WindowId) -> Self {
+        self.owner = Some(owner);
+        return self;
+    }
+
+    /// The position of the default button, 0 for the first one.
+    pub fn default_index(&self) -> usize {
+        let results: &[DialogResult] = self.buttons.results();
+        return self
+            .default_button
+            .and_then(|default| results.iter().position(|result| *result == default))
+            .unwrap_or(0);
+    }
+
+    /// Show the box with the [current](backend::current()) backend and wait until it is closed.
+    pub fn show(&self) -> DialogResult {
+        return backend::current().show_message_box(self);
+    }
+}
+
+/// A file type in the type list of a [FileDialog].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FileFilter {
+    /// The name the user sees, like "Images".
+    pub name: String,
+    /// Patterns like `*.png`, or `*` for all files.
+    pub patterns: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(name: &str, patterns: &[&str]) -> FileFilter {
+        return FileFilter {
+            name: name.to_string(),
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+        };
+    }
+
+    /// The extension of the first pattern, `png` for `*.png`. [None] for a pattern like `*`.
+    pub fn extension(&self) -> Option<&str> {
+        return self
+            .patterns
+            .first()?
+            .strip_prefix("*.")
+            .filter(|extension| !extension.contains(['*', '?']));
+    }
+
+    /// The patterns the way windows wants them, separated by `;`.
+    pub fn spec(&self) -> String {
+        return self.patterns.join(";");
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum FileDialogKind {
+    /// Pick one or more existing files.
+    #[default]
+    Open,
+    /// Pick a file name to save to, the file doesn't have to exist.
+    Save,
+    /// Pick an existing folder.
+    Folder,
+}
+
+/**
+    The common "Open", "Save As" and "Choose folder" dialogs:
+    ```ignore
+    let path: Option<PathBuf> = window.show_file_dialog(
+        FileDialog::save()
+            .title("Export image")
+            .filter("PNG image", &["*.png"])
+            .filter("All files", &["*"])
+            .default_path(&self.last_export),
+    );
+    ```
+    [None] or an empty list means the user cancelled. The [headless](super::backend::headless::HeadlessBackend)
+    backend picks the paths from a script, see
+    [HeadlessBackend::answer_file_dialog()](super::backend::headless::HeadlessBackend::answer_file_dialog()).
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/shell/common-file-dialog
+*/
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct FileDialog {
+    pub kind: FileDialogKind,
+    /// The text in the title bar, windows picks one that fits the kind when it is empty.
+    pub title: String,
+    /// The first filter is selected when the dialog opens. No filters shows all the files.
+    pub filters: Vec<FileFilter>,
+    /// A folder to open the dialog in, or a file to also fill in the name of.
+    pub default_path: Option<PathBuf>,
+    /// Let the user pick more than one file. Only for [FileDialogKind::Open].
+    pub multi_select: bool,
+    /// Ask before the user saves over a file that exists. Only for [FileDialogKind::Save], on by default there.
+    pub confirm_overwrite: bool,
+    /// The window that is disabled while the dialog is open.
+    pub owner: Option<WindowId>,
+}
+
+impl FileDialog {
+    pub fn open() -> FileDialog {
+        return FileDialog::default();
+    }
+
+    pub fn save() -> FileDialog {
+        return FileDialog {
+            kind: FileDialogKind::Save,
+            confirm_overwrite: true,
+            ..Default::default()
+        };
+    }
+
+    pub fn folder() -> FileDialog {
+        return FileDialog {
+            kind: FileDialogKind::Folder,
+            ..Default::default()
+        };
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        return self;
+    }
+
+    /// Add a file type, like `.filter("Images", &["*.png", "*.jpg"])`.
+    pub fn filter(mut self, name: &str, patterns: &[&str]) -> Self {
+        self.filters.push(FileFilter::new(name, patterns));
+        return self;
+    }
+
+    pub fn default_path(mut self, path: &Path) -> Self {
+        self.default_path = Some(path.to_path_buf());
+        return self;
+    }
+
+    pub fn multi_select(mut self, multi_select: bool) -> Self {
+        self.multi_select = multi_select;
+        return self;
+    }
+
+    pub fn confirm_overwrite(mut self, confirm_overwrite: bool) -> Self {
+        self.confirm_overwrite = confirm_overwrite;
+        return self;
+    }
+
+    /// See [Window::show_file_dialog()](super::Window::show_file_dialog()), which sets the owner for you.
+    pub fn owner(mut self, owner: WindowId) -> Self {
+        self.owner = Some(owner);
+        return self;
+    }
+
+    /**
+        The folder to open in and the file name to fill in, from [FileDialog::default_path]. A path that is an
+        existing folder is only the folder, anything else is split in the folder and the name.
+    */
+    pub fn default_folder_and_name(&self) -> (Option<PathBuf>, Option<String>) {
+        let path: &Path = match &self.default_path {
+            Some(path) => path,
+            None => return (None, None),
+        };
+        if path.is_dir() || self.kind == FileDialogKind::Folder {
+            return (Some(path.to_path_buf()), None);
+        }
+        let folder: Option<PathBuf> = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .map(Path::to_path_buf);
+        let name: Option<String> = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned());
+        return (folder, name);
+    }
+
+    /**
+        The extension a save dialog adds to a name without one: the one of the first filter. Windows changes it
+        along when the user picks another type.
+    */
+    pub fn default_extension(&self) -> Option<&str> {
+        if self.kind != FileDialogKind::Save {
+            return None;
+        }
+        return self.filters.first()?.extension();
+    }
+
+    /// Show the dialog with the [current](backend::current()) backend. The first picked path, [None] when cancelled.
+    pub fn show(&self) -> Option<PathBuf> {
+        return backend::current().show_file_dialog(self).into_iter().next();
+    }
+
+    /// Show an open dialog where the user can pick more than one file. Empty when cancelled.
+    pub fn show_multi(&self) -> Vec<PathBuf> {
+        let dialog: FileDialog = self.clone().multi_select(true);
+        return backend::current().show_file_dialog(&dialog);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{
+        backend::{headless::HeadlessBackend, Backend},
+        builder::WindowBuilder,
+        Window,
+    };
+    use std::rc::Rc;
+
+    #[test]
+    fn default_and_close_results() {
+        let save: MessageBox = MessageBox::new("Save changes?")
+            .buttons(MessageButtons::YesNoCancel)
+            .default_button(DialogResult::Cancel);
+        assert_eq!(save.default_index(), 2);
+        // Not one of the buttons, the first one gets the focus
+        assert_eq!(
+            save.clone()
+                .default_button(DialogResult::Retry)
+                .default_index(),
+            0
+        );
+        assert_eq!(MessageBox::new("Done").default_index(), 0);
+
+        assert_eq!(MessageButtons::Ok.close_result(), DialogResult::Ok);
+        assert_eq!(MessageButtons::YesNo.close_result(), DialogResult::No);
+        assert_eq!(
+            MessageButtons::YesNoCancel.close_result(),
+            DialogResult::Cancel
+        );
+        assert_eq!(
+            MessageButtons::AbortRetryIgnore.close_result(),
+            DialogResult::Abort
+        );
+        assert_eq!(
+            MessageButtons::CancelTryContinue.close_result(),
+            DialogResult::Cancel
+        );
+    }
+
+    #[test]
+    fn scripted_message_boxes() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let window: Window = WindowBuilder::new("Test")
+            .build_with(backend.clone())
+            .unwrap();
+        let delete: MessageBox = MessageBox::new("Delete 3 files?")
+            .title("Confirm delete")
+            .icon(MessageIcon::Warning)
+            .buttons(MessageButtons::YesNo)
+            .default_button(DialogResult::No);
+
+        backend.answer_message_box(DialogResult::Yes);
+        backend.answer_message_box(DialogResult::No);
+        assert_eq!(window.show_message_box(delete.clone()), DialogResult::Yes);
+        assert_eq!(window.show_message_box(delete.clone()), DialogResult::No);
+        // The script ran out, the user pressed Enter on the default button
+        assert_eq!(window.show_message_box(delete.clone()), DialogResult::No);
+        assert_eq!(
+            backend.show_message_box(&MessageBox::new("Saved")),
+            DialogResult::Ok
+        );
+
+        let shown: Vec<MessageBox> = backend.message_boxes();
+        assert_eq!(shown.len(), 4);
+        assert_eq!(shown[0], delete.clone().owner(window.id));
+        assert_eq!(shown[3].owner, None);
+    }
+
+    #[test]
+    fn filters_and_default_paths() {
+        assert_eq!(
+            FileFilter::new("Images", &["*.png", "*.jpg"]).extension(),
+            Some("png")
+        );
+        assert_eq!(
+            FileFilter::new("Images", &["*.png", "*.jpg"]).spec(),
+            "*.png;*.jpg"
+        );
+        assert_eq!(FileFilter::new("All files", &["*"]).extension(), None);
+        assert_eq!(FileFilter::new("Backups", &["*.b?k"]).extension(), None);
+
+        let save: FileDialog = FileDialog::save()
+            .filter("PNG image", &["*.png"])
+            .default_path(Path::new("exports/image.png"));
+        assert_eq!(save.default_extension(), Some("png"));
+        assert_eq!(
+            save.default_folder_and_name(),
+            (
+                Some(PathBuf::from("exports")),
+                Some("image.png".to_string())
+            )
+        );
+        let open: FileDialog = FileDialog::open()
+            .filter("PNG image", &["*.png"])
+            .default_path(Path::new("image.png"));
+        assert_eq!(open.default_extension(), None);
+        assert_eq!(
+            open.default_folder_and_name(),
+            (None, Some("image.png".to_string()))
+        );
+        let folder: FileDialog = FileDialog::folder().default_path(Path::new("projects/new"));
+        assert_eq!(
+            folder.default_folder_and_name(),
+            (Some(PathBuf::from("projects/new")), None)
+        );
+    }
+
+    #[test]
+    fn scripted_file_dialogs() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let window: Window = WindowBuilder::new("Test")
+            .build_with(backend.clone())
+            .unwrap();
+        let first: PathBuf = PathBuf::from("a.txt");
+        let second: PathBuf = PathBuf::from("b.txt");
+
+        backend.answer_file_dialog(std::slice::from_ref(&first));
+        backend.answer_file_dialog(&[]);
+        backend.answer_file_dialog(&[first.clone(), second.clone()]);
+        assert_eq!(
+            window.show_file_dialog(FileDialog::open()),
+            Some(first.clone())
+        );
+        assert_eq!(window.show_file_dialog(FileDialog::open()), None);
+        assert_eq!(
+            window.show_file_dialog_multi(FileDialog::open()),
+            vec![first, second]
+        );
+        // The script ran out, the user cancelled
+        assert_eq!(window.show_file_dialog(FileDialog::folder()), None);
+
+        let shown: Vec<FileDialog> = backend.file_dialogs();
+        assert_eq!(shown.len(), 4);
+        assert!(shown[2].multi_select);
+        assert!(shown.iter().all(|dialog| dialog.owner == Some(window.id)));
+    }
+
+    #[test]
+    fn a_save_dialog_asks_before_overwriting() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        let dir: PathBuf = std::env::temp_dir().join(format!("dialog-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let existing: PathBuf = dir.join("existing.png");
+        std::fs::write(&existing, b"png").unwrap();
+        let dialog: FileDialog = FileDialog::save().filter("PNG image", &["*.png"]);
+
+        // A name without an extension gets the one of the filter
+        backend.answer_file_dialog(&[dir.join("new")]);
+        assert_eq!(backend.show_file_dialog(&dialog), vec![dir.join("new.png")]);
+
+        // "No" to the overwrite prompt, the user picks another name
+        backend.answer_file_dialog(&[dir.join("existing")]);
+        backend.answer_message_box(DialogResult::No);
+        backend.answer_file_dialog(&[dir.join("other.png")]);
+        assert_eq!(
+            backend.show_file_dialog(&dialog),
+            vec![dir.join("other.png")]
+        );
+
+        backend.answer_file_dialog(std::slice::from_ref(&existing));
+        backend.answer_message_box(DialogResult::Yes);
+        assert_eq!(backend.show_file_dialog(&dialog), vec![existing.clone()]);
+
+        // Without a prompt the file is replaced right away
+        backend.answer_file_dialog(std::slice::from_ref(&existing));
+        let no_prompt: FileDialog = dialog.clone().confirm_overwrite(false);
+        assert_eq!(backend.show_file_dialog(&no_prompt), vec![existing.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let prompts: Vec<MessageBox> = backend.message_boxes();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0].buttons, MessageButtons::YesNo);
+        assert_eq!(prompts[0].default_button, Some(DialogResult::No));
+    }
+
+    #[test]
+    #[should_panic(expected = "without multi select")]
+    fn a_script_with_too_many_paths_panics() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        backend.answer_file_dialog(&[PathBuf::from("a.txt"), PathBuf::from("b.txt")]);
+        backend.show_file_dialog(&FileDialog::open());
+    }
+
+    #[test]
+    #[should_panic(expected = "which has the buttons")]
+    fn a_script_mismatch_panics() {
+        let backend: HeadlessBackend = HeadlessBackend::new();
+        backend.answer_message_box(DialogResult::Retry);
+        backend.show_message_box(&MessageBox::new("Delete?").buttons(MessageButtons::YesNo));
+    }
+}
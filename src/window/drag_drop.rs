@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use super::coords::ClientPoint;
+
+/**
+    Files dragged onto a window that accepts them, see
+    [WindowBuilder::accept_files()](super::builder::WindowBuilder::accept_files()). A drag gives one
+    [DragDropEvent::HoveredFile] for every file when it enters the window, followed by either
+    [DragDropEvent::HoveredFileCancelled] or [DragDropEvent::DroppedFiles]:
+    ```ignore
+    match event {
+        Event::DragDrop(DragDropEvent::HoveredFile(_)) => self.highlight_drop_zone = true,
+        Event::DragDrop(DragDropEvent::HoveredFileCancelled) => self.highlight_drop_zone = false,
+        Event::DragDrop(DragDropEvent::DroppedFiles { paths, position }) => self.open_at(paths, position),
+        _ => {}
+    }
+    ```
+    Without OLE (when the thread was initialized for another COM apartment) windows only tells us about the drop,
+    so then there are no hover events.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/com/drag-and-drop
+*/
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DragDropEvent {
+    /// A file is dragged into the window.
+    HoveredFile(PathBuf),
+    /// The files left the window again or the user pressed Escape.
+    HoveredFileCancelled,
+    /// The files were dropped at `position`, in the order the app they came from listed them.
+    DroppedFiles {
+        paths: Vec<PathBuf>,
+        position: ClientPoint,
+    },
+}
+
+/// The events for files that are dragged into a window.
+pub fn hover_events(paths: &[PathBuf]) -> Vec<DragDropEvent> {
+    return paths
+        .iter()
+        .map(|path| DragDropEvent::HoveredFile(path.clone()))
+        .collect();
+}
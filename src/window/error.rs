@@ -1,6 +1,6 @@
-use windows::{
-    core::PCSTR,
-    Win32::UI::WindowsAndMessaging::{MB_ICONERROR, MB_OK},
+use super::{
+    coords::Size,
+    dialog::{MessageBox, MessageIcon},
 };
 
 #[derive(Debug)]
@@ -19,7 +19,7 @@ pub struct CallLocation {
 #[macro_export]
 macro_rules! loc {
     () => {
-        crate::window::error::CallLocation {
+        $crate::window::error::CallLocation {
             file: file!().to_string(),
             line: line!(),
             column: column!(),
@@ -28,14 +28,20 @@ macro_rules! loc {
 }
 
 impl WindowError {
+    // It never returns, the process exits
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(error_details: &str, error_code: Option<i32>, origin: CallLocation) -> ! {
-        let base_details: String = format!(
-            "Error in {}:{}\n{}{}",
-            origin.file, origin.line, error_details, '\0'
+        let formatted_details: String = format!(
+            "Error in {}:{}\n{}",
+            origin.file, origin.line, error_details
         );
-        let formatted_details: PCSTR = PCSTR::from_raw(base_details.as_ptr());
 
-        crate::window::create_message_box(formatted_details, MB_ICONERROR | MB_OK, 0);
+        // Without a desktop (or with the headless backend) the box isn't seen, so the text goes to stderr as well
+        eprintln!("Fatal error: {}", formatted_details);
+        MessageBox::new(&formatted_details)
+            .title("Fatal error")
+            .icon(MessageIcon::Error)
+            .show();
 
         std::process::exit(error_code.unwrap_or(1));
     }
@@ -52,3 +58,167 @@ impl std::error::Error for WindowError {
         &self.details
     }
 }
+
+/// The reasons a [WindowBuilder](super::builder::WindowBuilder) can refuse to build a window.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BuildError {
+    /// The title or class name contains a NUL character, which Win32 would cut the string at.
+    InteriorNul(&'static str),
+    EmptyClassName,
+    /// The width or height is zero or negative, or a logical one isn't a finite number.
+    InvalidSize(Size),
+    /// The minimum size is larger than the maximum size.
+    MinLargerThanMax,
+    /// The size doesn't fit between the minimum and maximum size.
+    SizeOutsideLimits,
+    MaximizedAndFullscreen,
+    /**
+        Child windows live inside their parent so they can't be fullscreen, maximized, always on top or have a menu
+        bar or custom chrome.
+    */
+    InvalidChildOption(&'static str),
+    /// The owner or parent window doesn't exist (anymore).
+    UnknownRelatedWindow,
+    /**
+        The class name was already registered with another class style or window procedure. A class is only
+        registered once, so give windows that need another class style their own class name.
+    */
+    ClassMismatch,
+    /// RegisterClassEx or one of the calls for the class failed. Holds the GetLastError code.
+    RegisterClassFailed(u32),
+    /// CreateWindowEx failed. Holds the GetLastError code.
+    CreateWindowFailed(u32),
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InteriorNul(field) => {
+                write!(f, "The {} can't contain a NUL character", field)
+            }
+            BuildError::EmptyClassName => write!(f, "The class name can't be empty"),
+            BuildError::InvalidSize(Size::Logical(size)) => {
+                write!(f, "Invalid window size {}x{}", size.width, size.height)
+            }
+            BuildError::InvalidSize(Size::Physical(size)) => {
+                write!(
+                    f,
+                    "Invalid window size {}x{} physical pixels",
+                    size.width, size.height
+                )
+            }
+            BuildError::MinLargerThanMax => {
+                write!(f, "The minimum size is larger than the maximum size")
+            }
+            BuildError::SizeOutsideLimits => {
+                write!(f, "The size is outside of the minimum and maximum size")
+            }
+            BuildError::MaximizedAndFullscreen => {
+                write!(
+                    f,
+                    "A window can't be maximized and fullscreen at the same time"
+                )
+            }
+            BuildError::InvalidChildOption(option) => {
+                write!(f, "A child window can't be {}", option)
+            }
+            BuildError::UnknownRelatedWindow => {
+                write!(f, "The owner or parent window doesn't exist")
+            }
+            BuildError::ClassMismatch => {
+                write!(
+                    f,
+                    "The class name is already registered with another class style"
+                )
+            }
+            BuildError::RegisterClassFailed(code) => {
+                write!(f, "RegisterClassEx failed with code {}", code)
+            }
+            BuildError::CreateWindowFailed(code) => {
+                write!(f, "CreateWindowEx failed with code {}", code)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// The reasons [Window::set_window_mode()](super::Window::set_window_mode()) can fail. The window keeps its mode.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ModeError {
+    /// The monitor was disconnected or never existed.
+    UnknownMonitor,
+    /// The monitor doesn't support the video mode.
+    VideoModeRejected,
+    /// Child windows can't go fullscreen.
+    ChildWindow,
+}
+
+impl std::fmt::Display for ModeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModeError::UnknownMonitor => write!(f, "The monitor doesn't exist"),
+            ModeError::VideoModeRejected => {
+                write!(f, "The monitor can't switch to this video mode")
+            }
+            ModeError::ChildWindow => write!(f, "A child window can't go fullscreen"),
+        }
+    }
+}
+
+impl std::error::Error for ModeError {}
+
+/// The reasons a [Recording](super::recording::Recording) can't be read or written.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RecordingError {
+    /// The data doesn't start like a recording in either format.
+    BadMagic,
+    /// The recording was made by a newer version of the format.
+    UnsupportedVersion(u16),
+    /// The binary data ends in the middle of a frame.
+    UnexpectedEnd,
+    /// A value in the binary data is out of range. Holds what was being read.
+    InvalidValue(&'static str),
+    /// A line of the JSON-lines format can't be read. `line` counts from 1.
+    InvalidLine { line: usize, reason: String },
+    /// Reading or writing the file failed.
+    Io(String),
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(error: std::io::Error) -> Self {
+        return RecordingError::Io(error.to_string());
+    }
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::BadMagic => write!(f, "This is not an input recording"),
+            RecordingError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported recording version {}", version)
+            }
+            RecordingError::UnexpectedEnd => write!(f, "The recording ends unexpectedly"),
+            RecordingError::InvalidValue(what) => write!(f, "Invalid {} in the recording", what),
+            RecordingError::InvalidLine { line, reason } => {
+                write!(f, "Line {} of the recording: {}", line, reason)
+            }
+            RecordingError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// Returned by [EventLoopProxy::send_event()](super::proxy::EventLoopProxy::send_event()) when the window is closed.
+/// Holds the payload that couldn't be sent.
+#[derive(Debug, PartialEq, Clone)]
+pub struct EventLoopClosed<T>(pub T);
+
+impl<T> std::fmt::Display for EventLoopClosed<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "The window is closed")
+    }
+}
+
+impl<T: std::fmt::Debug> std::error::Error for EventLoopClosed<T> {}
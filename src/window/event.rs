@@ -0,0 +1,107 @@
+use super::{
+    chrome::CaptionButton, coords::ClientPoint, drag_drop::DragDropEvent, gesture::GestureEvent,
+    keyboard::KeyEvent, menu::MenuId, mouse::MouseEvent, timer::TimerId,
+};
+
+/**
+    Every kind of event a window can produce, in one type. [Window::poll_events()](super::Window::poll_events())
+    yields these in the order the messages arrived.<br>
+    `T` is the payload of [Event::User].
+*/
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event<T = ()> {
+    /// WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN and WM_SYSKEYUP
+    Key(KeyEvent),
+    /// WM_CHAR. Surrogate pairs are already combined into one char.
+    Char(char),
+    /// Mouse movement, buttons and enter/leave
+    Mouse(MouseEvent),
+    /// WM_MOUSEWHEEL without Ctrl. `delta` is the raw wheel delta, 120 per notch.
+    Wheel {
+        pos: ClientPoint,
+        delta: i16,
+    },
+    /// Touch gestures and Ctrl + mouse wheel zoom
+    Gesture(GestureEvent),
+    Window(WindowEvent),
+    /// Files dragged onto the window, only for windows that accept them
+    DragDrop(DragDropEvent),
+    /// A menu item was picked or its accelerator was pressed. WM_COMMAND and WM_CONTEXTMENU
+    MenuActivated(MenuId),
+    /// A timer of [Window::timers()](super::Window::timers()) without a callback fired.
+    Timer(TimerId),
+    /// An event defined by the app.
+    User(T),
+}
+
+impl Event {
+    /// The same event for a window with user events of type `U`. [None] for [Event::User].
+    pub fn with_user_type<U>(self) -> Option<Event<U>> {
+        return match self {
+            Event::Key(key) => Some(Event::Key(key)),
+            Event::Char(char) => Some(Event::Char(char)),
+            Event::Mouse(mouse) => Some(Event::Mouse(mouse)),
+            Event::Wheel { pos, delta } => Some(Event::Wheel { pos, delta }),
+            Event::Gesture(gesture) => Some(Event::Gesture(gesture)),
+            Event::Window(window_event) => Some(Event::Window(window_event)),
+            Event::DragDrop(drag_drop) => Some(Event::DragDrop(drag_drop)),
+            Event::MenuActivated(id) => Some(Event::MenuActivated(id)),
+            Event::Timer(id) => Some(Event::Timer(id)),
+            Event::User(()) => None,
+        };
+    }
+}
+
+/// Events about the window itself, as opposed to keyboard and mouse input.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum WindowEvent {
+    /// The user clicked the close button or pressed Alt+F4. The window stays open until the app calls
+    /// [Window::close()](super::Window::close()), so ignoring this event vetoes the close.
+    CloseRequested,
+    /// The window is destroyed. It won't receive any events after this one.
+    Destroyed,
+    /// The client area moved. The position is the top-left corner of the client area in screen coordinates, for a
+    /// child window it is relative to the client area of the parent.
+    Moved {
+        x: i32,
+        y: i32,
+    },
+    /// The client area changed size. The size is in physical pixels.
+    Resized {
+        width: i32,
+        height: i32,
+    },
+    Minimized,
+    Maximized,
+    /// The window went back to its normal size after being minimized or maximized.
+    Restored,
+    FocusGained,
+    FocusLost,
+    Shown,
+    Hidden,
+    /**
+        The window moved to a monitor with another DPI or the user changed the scale. The size is the new size of
+        the client area in physical pixels, a [WindowEvent::Resized] follows when it differs from the old size.
+    */
+    ScaleFactorChanged {
+        scale_factor: f64,
+        width: i32,
+        height: i32,
+    },
+    /// The window has to be drawn again. Sent on WM_PAINT, see [Window::request_redraw()](super::Window::request_redraw()).
+    RedrawRequested,
+    /**
+        Another app or this one changed the clipboard. Only sent on WM_CLIPBOARDUPDATE while the window listens, see
+        [Window::set_clipboard_listener()](super::Window::set_clipboard_listener()).
+    */
+    ClipboardChanged,
+    /**
+        The cursor moved over or off a caption button of a [custom chrome](super::chrome::ChromeLayout), or a
+        button was pressed or released. Draw the buttons again, see
+        [CaptionButtonState](super::chrome::CaptionButtonState).
+    */
+    CaptionButtonsChanged {
+        hovered: Option<CaptionButton>,
+        pressed: Option<CaptionButton>,
+    },
+}
@@ -0,0 +1,434 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use super::{control_flow::ControlFlow, Window};
+
+/// How many frame times [FrameStats] keeps for the min, max and p99 values.
+const STATS_WINDOW: usize = 240;
+/// The lowest [GameLoopConfig::target_fps], a frame every 1000 seconds. Lower rates are raised to this.
+pub const MIN_TARGET_FPS: f64 = 0.001;
+
+/// The app side of a [GameLoop].
+pub trait Game {
+    /// The window the loop pumps the messages of.
+    fn window(&mut self) -> &mut Window;
+
+    /// Advance the simulation by exactly `dt`. Called zero or more times per frame.
+    fn update(&mut self, dt: Duration);
+
+    /**
+        Draw the current state. `alpha` is how far we are between the last update and the next one, from 0.0 up to
+        (but not including) 1.0. Interpolate between the previous and the current state with it to get smooth
+        movement when the render rate isn't the update rate.
+    */
+    fn render(&mut self, alpha: f64);
+}
+
+/// The settings of a [GameLoop].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct GameLoopConfig {
+    /// The fixed time step of [Game::update()].
+    pub update_interval: Duration,
+    /// The most updates we run in one frame. When the app falls behind further than this the remaining time is
+    /// dropped, otherwise a slow frame makes the next frame slower as well (the "spiral of death").
+    pub max_frame_skip: u32,
+    /// Limit the frame rate. [None] renders as fast as possible, see [GameLoopConfig::frame_interval()].
+    pub target_fps: Option<f64>,
+    /// How long before the frame deadline we stop sleeping and start spinning. Sleep on windows wakes up to a
+    /// timer tick (up to ~15.6ms) late, spinning for the last part makes the frame times accurate.
+    pub spin_margin: Duration,
+}
+
+impl GameLoopConfig {
+    pub const DEFAULT: GameLoopConfig = GameLoopConfig {
+        // 60 updates per second
+        update_interval: Duration::from_nanos(16_666_667),
+        max_frame_skip: 5,
+        target_fps: Some(60.0),
+        spin_margin: Duration::from_millis(2),
+    };
+
+    /**
+        The time a frame gets with [GameLoopConfig::target_fps]. A rate that is zero, negative or NaN renders as fast
+        as possible, just like [None]. A rate below [MIN_TARGET_FPS] is raised to it.
+    */
+    pub fn frame_interval(&self) -> Option<Duration> {
+        let fps: f64 = self.target_fps.filter(|fps| *fps > 0.0)?;
+        return Some(Duration::from_secs_f64(1.0 / fps.max(MIN_TARGET_FPS)));
+    }
+}
+
+impl Default for GameLoopConfig {
+    fn default() -> Self {
+        return GameLoopConfig::DEFAULT;
+    }
+}
+
+/// The fixed time step accumulator. It only does the math so it doesn't depend on a clock.
+#[derive(Debug, Clone)]
+pub struct FixedStep {
+    pub interval: Duration,
+    pub max_frame_skip: u32,
+    accumulator: Duration,
+}
+
+impl FixedStep {
+    pub fn new(interval: Duration, max_frame_skip: u32) -> FixedStep {
+        return FixedStep {
+            interval,
+            max_frame_skip,
+            accumulator: Duration::ZERO,
+        };
+    }
+
+    /**
+        Add the time of the last frame. Returns how many updates to run now and how many updates were dropped
+        because of [FixedStep::max_frame_skip].
+    */
+    pub fn advance(&mut self, frame_time: Duration) -> (u32, u32) {
+        if self.interval.is_zero() {
+            return (0, 0);
+        }
+        self.accumulator += frame_time;
+
+        let due: u128 = self.accumulator.as_nanos() / self.interval.as_nanos();
+        let updates: u32 = due.min(self.max_frame_skip as u128) as u32;
+        let dropped: u32 = (due - updates as u128).min(u32::MAX as u128) as u32;
+
+        self.accumulator -= self.interval * updates;
+        if dropped > 0 {
+            // Keep the fraction of an update, drop the whole updates we can't catch up with
+            self.accumulator = Duration::from_nanos(
+                (self.accumulator.as_nanos() % self.interval.as_nanos()) as u64,
+            );
+        }
+        return (updates, dropped);
+    }
+
+    /// The interpolation factor for [Game::render()].
+    pub fn alpha(&self) -> f64 {
+        if self.interval.is_zero() {
+            return 0.0;
+        }
+        return self.accumulator.as_secs_f64() / self.interval.as_secs_f64();
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
+
+/// Statistics about the last frames of a [GameLoop].
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    /// The number of frames since the loop started.
+    pub frame_count: u64,
+    /// The number of updates that were dropped since the loop started, see [GameLoopConfig::max_frame_skip].
+    pub dropped_updates: u64,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        return FrameStats {
+            frame_times: VecDeque::with_capacity(STATS_WINDOW),
+            frame_count: 0,
+            dropped_updates: 0,
+        };
+    }
+
+    pub fn record_frame(&mut self, frame_time: Duration, dropped_updates: u32) {
+        self.frame_times.push_back(frame_time);
+        while self.frame_times.len() > STATS_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_count += 1;
+        self.dropped_updates += dropped_updates as u64;
+    }
+
+    /// The frames per second over the last frames.
+    pub fn fps(&self) -> f64 {
+        let average: Duration = self.avg_frame_time();
+        if average.is_zero() {
+            return 0.0;
+        }
+        return 1.0 / average.as_secs_f64();
+    }
+
+    pub fn min_frame_time(&self) -> Duration {
+        return self.frame_times.iter().min().copied().unwrap_or_default();
+    }
+
+    pub fn avg_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        return self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        return self.frame_times.iter().max().copied().unwrap_or_default();
+    }
+
+    /// The frame time 99% of the last frames were faster than. Shows stutter that the average hides.
+    pub fn p99_frame_time(&self) -> Duration {
+        return self.percentile(0.99);
+    }
+
+    /// The frame time at `percentile` (0.0 to 1.0) using the nearest rank.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted: Vec<Duration> = self.frame_times.iter().copied().collect();
+        sorted.sort();
+        let rank: usize = (percentile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+        return sorted[rank.saturating_sub(1)];
+    }
+
+    pub fn reset(&mut self) {
+        *self = FrameStats::new();
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        return FrameStats::new();
+    }
+}
+
+/**
+    Wait until `deadline`. Sleeps until `spin_margin` before the deadline and spins for the rest, because a sleep can
+    wake up a whole timer tick late.
+*/
+pub fn wait_until(deadline: Instant, spin_margin: Duration) {
+    let now: Instant = Instant::now();
+    if deadline <= now {
+        return;
+    }
+    let remaining: Duration = deadline - now;
+    if remaining > spin_margin {
+        std::thread::sleep(remaining - spin_margin);
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+}
+
+/**
+    Runs a [Game] with a fixed update rate and a variable render rate:
+    ```ignore
+    let mut game_loop: GameLoop = GameLoop::new(GameLoopConfig::DEFAULT);
+    let exit_code: usize = game_loop.run(&mut app);
+    println!("{:.1} fps, p99 {:?}", game_loop.stats().fps(), game_loop.stats().p99_frame_time());
+    ```
+    Every frame pumps the messages, runs the updates that are due, renders once and then waits for the next frame
+    when there is a target frame rate.
+*/
+pub struct GameLoop {
+    pub config: GameLoopConfig,
+    step: FixedStep,
+    stats: FrameStats,
+}
+
+impl GameLoop {
+    pub fn new(config: GameLoopConfig) -> GameLoop {
+        return GameLoop {
+            config,
+            step: FixedStep::new(config.update_interval, config.max_frame_skip),
+            stats: FrameStats::new(),
+        };
+    }
+
+    pub fn stats(&self) -> &FrameStats {
+        return &self.stats;
+    }
+
+    /// Run until WM_QUIT and return its exit code. The window gets its [ControlFlow] back afterwards.
+    pub fn run<G: Game>(&mut self, game: &mut G) -> usize {
+        // The loop paces itself, it must never block on the message queue
+        let control_flow: ControlFlow = game.window().control_flow();
+        game.window().set_control_flow(ControlFlow::Poll);
+        self.step = FixedStep::new(self.config.update_interval, self.config.max_frame_skip);
+
+        let mut last_frame: Instant = Instant::now();
+        loop {
+            if let Some(exit_code) = game.window().handle_messages() {
+                game.window().set_control_flow(control_flow);
+                return exit_code;
+            }
+
+            let frame_start: Instant = Instant::now();
+            let frame_time: Duration = frame_start - last_frame;
+            last_frame = frame_start;
+
+            let (updates, dropped): (u32, u32) = self.step.advance(frame_time);
+            for _ in 0..updates {
+                game.update(self.step.interval);
+            }
+            game.render(self.step.alpha());
+            self.stats.record_frame(frame_time, dropped);
+
+            if let Some(interval) = self.config.frame_interval() {
+                wait_until(frame_start + interval, self.config.spin_margin);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{backend::headless::HeadlessBackend, builder::WindowBuilder};
+    use std::rc::Rc;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn fixed_step_runs_the_due_updates() {
+        let mut step: FixedStep = FixedStep::new(10 * MS, 5);
+        assert_eq!(step.advance(25 * MS), (2, 0));
+        assert!((step.alpha() - 0.5).abs() < 1e-9);
+        assert_eq!(step.advance(5 * MS), (1, 0));
+        assert_eq!(step.alpha(), 0.0);
+        assert_eq!(step.advance(9 * MS), (0, 0));
+        assert_eq!(step.advance(MS), (1, 0));
+    }
+
+    #[test]
+    fn fixed_step_clamps_to_max_frame_skip() {
+        let mut step: FixedStep = FixedStep::new(10 * MS, 5);
+        // 10 updates are due, 5 run and the other 5 are dropped with only the fraction kept
+        assert_eq!(step.advance(103 * MS), (5, 5));
+        assert!((step.alpha() - 0.3).abs() < 1e-9);
+        // Exactly at the limit nothing is dropped
+        assert_eq!(step.advance(47 * MS), (5, 0));
+        assert_eq!(step.alpha(), 0.0);
+
+        let mut paused: FixedStep = FixedStep::new(10 * MS, 0);
+        assert_eq!(paused.advance(35 * MS), (0, 3));
+        assert!((paused.alpha() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fixed_step_with_a_zero_interval() {
+        let mut step: FixedStep = FixedStep::new(Duration::ZERO, 5);
+        assert_eq!(step.advance(10 * MS), (0, 0));
+        assert_eq!(step.alpha(), 0.0);
+    }
+
+    #[test]
+    fn frame_interval() {
+        let config = |target_fps: Option<f64>| GameLoopConfig {
+            target_fps,
+            ..GameLoopConfig::DEFAULT
+        };
+        assert_eq!(config(Some(50.0)).frame_interval(), Some(20 * MS));
+        assert_eq!(config(None).frame_interval(), None);
+        for fps in [0.0, -60.0, f64::NAN, f64::NEG_INFINITY] {
+            assert_eq!(config(Some(fps)).frame_interval(), None);
+        }
+        assert_eq!(
+            config(Some(f64::INFINITY)).frame_interval(),
+            Some(Duration::ZERO)
+        );
+        // Too slow for a Duration, or for any app to wait on
+        let slowest: Option<Duration> = Some(Duration::from_secs(1000));
+        assert_eq!(config(Some(1e-20)).frame_interval(), slowest);
+        assert_eq!(config(Some(f64::MIN_POSITIVE)).frame_interval(), slowest);
+    }
+
+    #[test]
+    fn percentile_boundaries() {
+        let mut stats: FrameStats = FrameStats::new();
+        assert_eq!(stats.percentile(0.5), Duration::ZERO);
+        assert_eq!(stats.fps(), 0.0);
+
+        stats.record_frame(7 * MS, 0);
+        for percentile in [0.0, 0.5, 1.0] {
+            assert_eq!(stats.percentile(percentile), 7 * MS);
+        }
+
+        stats.reset();
+        // Recorded out of order, 1ms up to 100ms
+        for ms in (1..=100).rev() {
+            stats.record_frame(ms * MS, 0);
+        }
+        assert_eq!(stats.percentile(0.0), MS);
+        assert_eq!(stats.percentile(0.01), MS);
+        assert_eq!(stats.percentile(0.5), 50 * MS);
+        assert_eq!(stats.percentile(0.505), 51 * MS);
+        assert_eq!(stats.p99_frame_time(), 99 * MS);
+        assert_eq!(stats.percentile(1.0), 100 * MS);
+        // Out of range is clamped
+        assert_eq!(stats.percentile(-1.0), MS);
+        assert_eq!(stats.percentile(2.0), 100 * MS);
+    }
+
+    #[test]
+    fn stats_keep_the_last_frames() {
+        let mut stats: FrameStats = FrameStats::new();
+        for ms in 1..=(STATS_WINDOW as u32 + 60) {
+            stats.record_frame(ms * MS, 1);
+        }
+        assert_eq!(stats.frame_count, STATS_WINDOW as u64 + 60);
+        assert_eq!(stats.dropped_updates, STATS_WINDOW as u64 + 60);
+        assert_eq!(stats.min_frame_time(), 61 * MS);
+        assert_eq!(stats.max_frame_time(), (STATS_WINDOW as u32 + 60) * MS);
+    }
+
+    struct CountingGame {
+        window: Window,
+        backend: Rc<HeadlessBackend>,
+        updates: u32,
+        renders: u32,
+    }
+
+    impl Game for CountingGame {
+        fn window(&mut self) -> &mut Window {
+            return &mut self.window;
+        }
+
+        fn update(&mut self, dt: Duration) {
+            assert_eq!(dt, MS);
+            self.updates += 1;
+        }
+
+        fn render(&mut self, alpha: f64) {
+            assert!((0.0..1.0).contains(&alpha));
+            self.renders += 1;
+            if self.renders == 3 {
+                self.backend.post_quit(7);
+            }
+        }
+    }
+
+    #[test]
+    fn run_until_quit() {
+        let backend: Rc<HeadlessBackend> = Rc::new(HeadlessBackend::new());
+        let mut game: CountingGame = CountingGame {
+            window: WindowBuilder::new("Test")
+                .build_with(backend.clone())
+                .unwrap(),
+            backend,
+            updates: 0,
+            renders: 0,
+        };
+        let mut game_loop: GameLoop = GameLoop::new(GameLoopConfig {
+            update_interval: MS,
+            target_fps: Some(500.0),
+            ..GameLoopConfig::DEFAULT
+        });
+        game.window.set_control_flow(ControlFlow::Wait);
+
+        assert_eq!(game_loop.run(&mut game), 7);
+        assert_eq!(game.renders, 3);
+        assert_eq!(game_loop.stats().frame_count, 3);
+        // Every frame after the first one takes 2ms at 500 fps
+        assert!(game.updates >= 2);
+        // The loop polled, the window waits again afterwards
+        assert_eq!(game.window.control_flow(), ControlFlow::Wait);
+    }
+}
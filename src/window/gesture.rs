@@ -0,0 +1,766 @@
+const MAX_BUFFER_SIZE: usize = 16;
+const WHEEL_DELTA: f32 = 120.0;
+
+/// A single pointer contact (one finger on the touch screen).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contact {
+    /// The pointer id. Stays the same from [ContactPhase::Down] until [ContactPhase::Up].
+    pub id: u32,
+    pub phase: ContactPhase,
+    pub x: f32,
+    pub y: f32,
+    /// Time of the contact in milliseconds. Win32 uses [GetMessageTime][t] for this.
+    /// [t]: windows::Win32::UI::WindowsAndMessaging::GetMessageTime
+    pub time: u32,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum ContactPhase {
+    Down,
+    Move,
+    Up,
+}
+
+/// The thresholds the recognizers use before they start a gesture.
+#[derive(Debug, Copy, Clone)]
+pub struct GestureConfig {
+    /// Minimal relative change of the finger distance before a pinch starts (0.05 = 5%).
+    pub pinch_threshold: f32,
+    /// Minimal movement of the finger centroid in pixels before a pan starts.
+    pub pan_threshold: f32,
+    /// Minimal change of the finger angle in radians before a rotate starts.
+    pub rotate_threshold: f32,
+    /// Amount of fingers needed for a swipe.
+    pub swipe_fingers: usize,
+    /// Minimal distance in pixels the fingers have to travel for a swipe.
+    pub swipe_min_distance: f32,
+    /// Maximum time in milliseconds a swipe may take.
+    pub swipe_max_duration: u32,
+    /// Time in milliseconds a single finger has to stay down for a long-press.
+    pub long_press_duration: u32,
+    /// Distance in pixels a finger may move and still count as a long-press.
+    pub long_press_slop: f32,
+    /// Scale factor of one wheel notch when zooming with Ctrl + mouse wheel.
+    pub wheel_zoom_step: f32,
+}
+
+impl GestureConfig {
+    pub const DEFAULT: GestureConfig = GestureConfig {
+        pinch_threshold: 0.05,
+        pan_threshold: 10.0,
+        rotate_threshold: 0.1,
+        swipe_fingers: 3,
+        swipe_min_distance: 100.0,
+        swipe_max_duration: 500,
+        long_press_duration: 500,
+        long_press_slop: 10.0,
+        wheel_zoom_step: 1.1,
+    };
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        return GestureConfig::DEFAULT;
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GestureKind {
+    Pinch,
+    Pan,
+    Rotate,
+    Swipe(SwipeDirection),
+    LongPress,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GesturePhase {
+    Begin,
+    Update,
+    End,
+}
+
+/// A recognized gesture. All the deltas are relative to the previous event of the same gesture.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct GestureEvent {
+    pub kind: GestureKind,
+    pub phase: GesturePhase,
+    /// The focal point of the gesture (the centroid of the fingers or the cursor position).
+    pub x: f32,
+    pub y: f32,
+    /// Scale delta. 1.0 means no change.
+    pub scale: f32,
+    /// Rotation delta in radians.
+    pub rotation: f32,
+    pub translation_x: f32,
+    pub translation_y: f32,
+    pub fingers: usize,
+}
+
+/// Snapshot of the two-finger geometry used by pinch, pan and rotate.
+#[derive(Debug, Copy, Clone)]
+struct TwoFingerFrame {
+    centroid_x: f32,
+    centroid_y: f32,
+    span: f32,
+    angle: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct TrackedContact {
+    id: u32,
+    x: f32,
+    y: f32,
+    start_x: f32,
+    start_y: f32,
+    start_time: u32,
+}
+
+/// Turns a timeline of [Contact]'s into [GestureEvent]'s.<br>
+/// Feed it with [GestureRecognizer::on_contact()] and call [GestureRecognizer::update()] every frame so
+/// long-presses can fire without any finger movement.
+pub struct GestureRecognizer {
+    pub config: GestureConfig,
+    pub event_queue: Vec<GestureEvent>,
+    /// The events since the last [GestureRecognizer::take_new_events()], for the event stream of the window.
+    new_events: Vec<GestureEvent>,
+    contacts: Vec<TrackedContact>,
+    /// The frame where the two-finger gesture started.
+    start_frame: Option<TwoFingerFrame>,
+    /// The frame of the previous two-finger event.
+    last_frame: Option<TwoFingerFrame>,
+    pinch_active: bool,
+    pan_active: bool,
+    rotate_active: bool,
+    long_press_active: bool,
+    /// Set once a finger moved too much or another finger was added.
+    long_press_cancelled: bool,
+    /// Start time and start centroid of a possible swipe.
+    swipe_start: Option<(u32, f32, f32)>,
+    swipe_max_fingers: usize,
+}
+
+impl GestureRecognizer {
+    pub const fn new(config: GestureConfig) -> GestureRecognizer {
+        return GestureRecognizer {
+            config,
+            event_queue: Vec::new(),
+            new_events: Vec::new(),
+            contacts: Vec::new(),
+            start_frame: None,
+            last_frame: None,
+            pinch_active: false,
+            pan_active: false,
+            rotate_active: false,
+            long_press_active: false,
+            long_press_cancelled: false,
+            swipe_start: None,
+            swipe_max_fingers: 0,
+        };
+    }
+
+    /// Forget all the contacts and running gestures. Used when the window loses focus.
+    pub fn reset(&mut self) {
+        self.event_queue = vec![];
+        self.new_events = vec![];
+        self.contacts = vec![];
+        self.start_frame = None;
+        self.last_frame = None;
+        self.pinch_active = false;
+        self.pan_active = false;
+        self.rotate_active = false;
+        self.long_press_active = false;
+        self.long_press_cancelled = false;
+        self.swipe_start = None;
+        self.swipe_max_fingers = 0;
+    }
+
+    /// Read the oldest [GestureEvent] and remove it from the queue.
+    pub fn read(&mut self) -> Option<GestureEvent> {
+        if !self.event_queue.is_empty() {
+            return Some(self.event_queue.remove(0));
+        }
+        return None;
+    }
+
+    /// Take the events that were recognized since the last call. Unlike [GestureRecognizer::read()] this
+    /// doesn't touch the event queue.
+    pub fn take_new_events(&mut self) -> Vec<GestureEvent> {
+        return std::mem::take(&mut self.new_events);
+    }
+
+    pub fn on_contact(&mut self, contact: Contact) {
+        match contact.phase {
+            ContactPhase::Down => self.on_contact_down(contact),
+            ContactPhase::Move => self.on_contact_move(contact),
+            ContactPhase::Up => self.on_contact_up(contact),
+        }
+    }
+
+    /// Check the time based gestures. `time` uses the same clock as [Contact::time].
+    pub fn update(&mut self, time: u32) {
+        if self.contacts.len() != 1 || self.long_press_active || self.long_press_cancelled {
+            return;
+        }
+
+        let contact: TrackedContact = self.contacts[0];
+        if time.wrapping_sub(contact.start_time) >= self.config.long_press_duration {
+            self.long_press_active = true;
+            self.push(
+                GestureKind::LongPress,
+                GesturePhase::Begin,
+                contact.x,
+                contact.y,
+            );
+        }
+    }
+
+    /// Ctrl + mouse wheel. Produces the same [GestureKind::Pinch] as a two finger pinch so
+    /// zooming only has to be handled once. `delta` is the raw wheel delta (120 per notch).
+    pub fn on_wheel_zoom(&mut self, x: f32, y: f32, delta: i16) {
+        let scale: f32 = self.config.wheel_zoom_step.powf(delta as f32 / WHEEL_DELTA);
+
+        self.emit(GestureEvent {
+            kind: GestureKind::Pinch,
+            phase: GesturePhase::Update,
+            x,
+            y,
+            scale,
+            rotation: 0.0,
+            translation_x: 0.0,
+            translation_y: 0.0,
+            fingers: 0,
+        });
+    }
+
+    fn on_contact_down(&mut self, contact: Contact) {
+        // A pointer id can't go down twice, drop the stale one.
+        self.contacts.retain(|c| c.id != contact.id);
+        self.contacts.push(TrackedContact {
+            id: contact.id,
+            x: contact.x,
+            y: contact.y,
+            start_x: contact.x,
+            start_y: contact.y,
+            start_time: contact.time,
+        });
+
+        if self.contacts.len() > 1 {
+            self.cancel_long_press();
+        }
+
+        if self.swipe_start.is_none() {
+            self.swipe_start = Some((contact.time, contact.x, contact.y));
+        }
+        if self.contacts.len() > self.swipe_max_fingers {
+            self.swipe_max_fingers = self.contacts.len();
+            // Measure the swipe from where all fingers are down.
+            let (cx, cy): (f32, f32) = self.centroid();
+            if let Some((time, _, _)) = self.swipe_start {
+                self.swipe_start = Some((time, cx, cy));
+            }
+        }
+
+        // Adding or removing fingers restarts the two-finger gestures.
+        self.end_two_finger_gestures();
+        if self.contacts.len() == 2 {
+            self.start_frame = self.two_finger_frame();
+            self.last_frame = self.start_frame;
+        }
+    }
+
+    fn on_contact_move(&mut self, contact: Contact) {
+        let tracked: &mut TrackedContact =
+            match self.contacts.iter_mut().find(|c| c.id == contact.id) {
+                Some(tracked) => tracked,
+                None => return,
+            };
+        tracked.x = contact.x;
+        tracked.y = contact.y;
+
+        let moved: f32 = distance(tracked.start_x, tracked.start_y, tracked.x, tracked.y);
+        if self.contacts.len() == 1 && moved > self.config.long_press_slop {
+            self.cancel_long_press();
+        }
+
+        if self.contacts.len() == 2 {
+            self.update_two_finger_gestures();
+        }
+    }
+
+    fn on_contact_up(&mut self, contact: Contact) {
+        // The up message can carry movement that had no move message of its own
+        match self.contacts.iter_mut().find(|c| c.id == contact.id) {
+            Some(tracked) => {
+                tracked.x = contact.x;
+                tracked.y = contact.y;
+            }
+            None => return,
+        }
+
+        if self.long_press_active {
+            let c: TrackedContact = self.contacts[0];
+            self.push(GestureKind::LongPress, GesturePhase::End, c.x, c.y);
+            self.long_press_active = false;
+        }
+
+        // The swipe is decided when the first finger lifts.
+        if self.swipe_max_fingers >= self.config.swipe_fingers
+            && self.contacts.len() == self.swipe_max_fingers
+        {
+            self.check_swipe(contact.time);
+        }
+
+        self.end_two_finger_gestures();
+        self.contacts.retain(|c| c.id != contact.id);
+
+        if self.contacts.len() == 2 {
+            self.start_frame = self.two_finger_frame();
+            self.last_frame = self.start_frame;
+        }
+        if self.contacts.is_empty() {
+            self.long_press_cancelled = false;
+            self.swipe_start = None;
+            self.swipe_max_fingers = 0;
+        }
+    }
+
+    fn check_swipe(&mut self, time: u32) {
+        let (start_time, start_x, start_y): (u32, f32, f32) = match self.swipe_start {
+            Some(start) => start,
+            None => return,
+        };
+        // Only one swipe per touch sequence.
+        self.swipe_start = None;
+
+        if time.wrapping_sub(start_time) > self.config.swipe_max_duration {
+            return;
+        }
+
+        let (cx, cy): (f32, f32) = self.centroid();
+        let dx: f32 = cx - start_x;
+        let dy: f32 = cy - start_y;
+        if distance(0.0, 0.0, dx, dy) < self.config.swipe_min_distance {
+            return;
+        }
+
+        let direction: SwipeDirection = if dx.abs() >= dy.abs() {
+            if dx > 0.0 {
+                SwipeDirection::Right
+            } else {
+                SwipeDirection::Left
+            }
+        } else if dy > 0.0 {
+            SwipeDirection::Down
+        } else {
+            SwipeDirection::Up
+        };
+
+        self.emit(GestureEvent {
+            kind: GestureKind::Swipe(direction),
+            phase: GesturePhase::End,
+            x: cx,
+            y: cy,
+            scale: 1.0,
+            rotation: 0.0,
+            translation_x: dx,
+            translation_y: dy,
+            fingers: self.contacts.len(),
+        });
+    }
+
+    fn update_two_finger_gestures(&mut self) {
+        let (start, last, current): (TwoFingerFrame, TwoFingerFrame, TwoFingerFrame) =
+            match (self.start_frame, self.last_frame, self.two_finger_frame()) {
+                (Some(start), Some(last), Some(current)) => (start, last, current),
+                _ => return,
+            };
+
+        if !self.pinch_active
+            && start.span > 0.0
+            && (current.span / start.span - 1.0).abs() > self.config.pinch_threshold
+        {
+            self.pinch_active = true;
+            self.push(
+                GestureKind::Pinch,
+                GesturePhase::Begin,
+                current.centroid_x,
+                current.centroid_y,
+            );
+        }
+        if !self.pan_active
+            && distance(
+                start.centroid_x,
+                start.centroid_y,
+                current.centroid_x,
+                current.centroid_y,
+            ) > self.config.pan_threshold
+        {
+            self.pan_active = true;
+            self.push(
+                GestureKind::Pan,
+                GesturePhase::Begin,
+                current.centroid_x,
+                current.centroid_y,
+            );
+        }
+        if !self.rotate_active
+            && angle_delta(start.angle, current.angle).abs() > self.config.rotate_threshold
+        {
+            self.rotate_active = true;
+            self.push(
+                GestureKind::Rotate,
+                GesturePhase::Begin,
+                current.centroid_x,
+                current.centroid_y,
+            );
+        }
+
+        let base: GestureEvent = GestureEvent {
+            kind: GestureKind::Pinch,
+            phase: GesturePhase::Update,
+            x: current.centroid_x,
+            y: current.centroid_y,
+            scale: 1.0,
+            rotation: 0.0,
+            translation_x: 0.0,
+            translation_y: 0.0,
+            fingers: 2,
+        };
+
+        if self.pinch_active && last.span > 0.0 {
+            self.emit(GestureEvent {
+                scale: current.span / last.span,
+                ..base
+            });
+        }
+        if self.pan_active {
+            self.emit(GestureEvent {
+                kind: GestureKind::Pan,
+                translation_x: current.centroid_x - last.centroid_x,
+                translation_y: current.centroid_y - last.centroid_y,
+                ..base
+            });
+        }
+        if self.rotate_active {
+            self.emit(GestureEvent {
+                kind: GestureKind::Rotate,
+                rotation: angle_delta(last.angle, current.angle),
+                ..base
+            });
+        }
+
+        self.last_frame = Some(current);
+    }
+
+    fn end_two_finger_gestures(&mut self) {
+        let (x, y): (f32, f32) = self.centroid();
+        if self.pinch_active {
+            self.push(GestureKind::Pinch, GesturePhase::End, x, y);
+        }
+        if self.pan_active {
+            self.push(GestureKind::Pan, GesturePhase::End, x, y);
+        }
+        if self.rotate_active {
+            self.push(GestureKind::Rotate, GesturePhase::End, x, y);
+        }
+        self.pinch_active = false;
+        self.pan_active = false;
+        self.rotate_active = false;
+        self.start_frame = None;
+        self.last_frame = None;
+    }
+
+    fn cancel_long_press(&mut self) {
+        self.long_press_cancelled = true;
+        if self.long_press_active {
+            let (x, y): (f32, f32) = self.centroid();
+            self.push(GestureKind::LongPress, GesturePhase::End, x, y);
+            self.long_press_active = false;
+        }
+    }
+
+    fn two_finger_frame(&self) -> Option<TwoFingerFrame> {
+        if self.contacts.len() != 2 {
+            return None;
+        }
+        let a: TrackedContact = self.contacts[0];
+        let b: TrackedContact = self.contacts[1];
+
+        return Some(TwoFingerFrame {
+            centroid_x: (a.x + b.x) / 2.0,
+            centroid_y: (a.y + b.y) / 2.0,
+            span: distance(a.x, a.y, b.x, b.y),
+            angle: (b.y - a.y).atan2(b.x - a.x),
+        });
+    }
+
+    fn centroid(&self) -> (f32, f32) {
+        if self.contacts.is_empty() {
+            return (0.0, 0.0);
+        }
+        let count: f32 = self.contacts.len() as f32;
+        let x: f32 = self.contacts.iter().map(|c| c.x).sum::<f32>() / count;
+        let y: f32 = self.contacts.iter().map(|c| c.y).sum::<f32>() / count;
+        return (x, y);
+    }
+
+    fn emit(&mut self, event: GestureEvent) {
+        self.event_queue.push(event);
+        self.new_events.push(event);
+        trim_buffer(&mut self.event_queue);
+    }
+
+    /// Push an event without any deltas. Used for the begin and end events.
+    fn push(&mut self, kind: GestureKind, phase: GesturePhase, x: f32, y: f32) {
+        self.emit(GestureEvent {
+            kind,
+            phase,
+            x,
+            y,
+            scale: 1.0,
+            rotation: 0.0,
+            translation_x: 0.0,
+            translation_y: 0.0,
+            fingers: self.contacts.len(),
+        });
+    }
+}
+
+fn distance(x1: f32, y1: f32, x2: f32, y2: f32) -> f32 {
+    return ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+}
+
+/// The shortest signed angle from `from` to `to`, so crossing -PI..PI doesn't jump a full turn.
+fn angle_delta(from: f32, to: f32) -> f32 {
+    let mut delta: f32 = to - from;
+    while delta > std::f32::consts::PI {
+        delta -= 2.0 * std::f32::consts::PI;
+    }
+    while delta < -std::f32::consts::PI {
+        delta += 2.0 * std::f32::consts::PI;
+    }
+    return delta;
+}
+
+fn trim_buffer<T>(buffer: &mut Vec<T>) {
+    while buffer.len() > MAX_BUFFER_SIZE {
+        buffer.remove(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact(id: u32, phase: ContactPhase, x: f32, y: f32, time: u32) -> Contact {
+        return Contact {
+            id,
+            phase,
+            x,
+            y,
+            time,
+        };
+    }
+
+    /// Everything in the event queue, oldest first.
+    fn drain(recognizer: &mut GestureRecognizer) -> Vec<GestureEvent> {
+        return std::iter::from_fn(|| recognizer.read()).collect();
+    }
+
+    /// Feed a timeline of contacts and return everything that was recognized.
+    fn feed(recognizer: &mut GestureRecognizer, contacts: &[Contact]) -> Vec<GestureEvent> {
+        for contact in contacts {
+            recognizer.on_contact(*contact);
+            recognizer.update(contact.time);
+        }
+        return drain(recognizer);
+    }
+
+    fn kinds(events: &[GestureEvent]) -> Vec<(GestureKind, GesturePhase)> {
+        return events
+            .iter()
+            .map(|event| (event.kind, event.phase))
+            .collect();
+    }
+
+    #[test]
+    fn pinch() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 100.0, 100.0, 0),
+                contact(2, ContactPhase::Down, 200.0, 100.0, 10),
+                contact(2, ContactPhase::Move, 220.0, 100.0, 20),
+                contact(2, ContactPhase::Up, 220.0, 100.0, 30),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![
+                (GestureKind::Pinch, GesturePhase::Begin),
+                (GestureKind::Pinch, GesturePhase::Update),
+                (GestureKind::Pinch, GesturePhase::End),
+            ]
+        );
+        assert!((events[1].scale - 1.2).abs() < 1e-5);
+        assert_eq!(events[1].fingers, 2);
+    }
+
+    #[test]
+    fn pan() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 100.0, 100.0, 0),
+                contact(2, ContactPhase::Down, 200.0, 100.0, 0),
+                // Small steps, so moving one finger at a time doesn't look like a rotate
+                contact(1, ContactPhase::Move, 100.0, 108.0, 10),
+                contact(2, ContactPhase::Move, 200.0, 108.0, 20),
+                contact(1, ContactPhase::Move, 100.0, 116.0, 30),
+                contact(2, ContactPhase::Move, 200.0, 116.0, 40),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![
+                (GestureKind::Pan, GesturePhase::Begin),
+                (GestureKind::Pan, GesturePhase::Update),
+                (GestureKind::Pan, GesturePhase::Update),
+            ]
+        );
+        // The deltas start at the last frame before the pan began
+        let moved: f32 = events.iter().map(|event| event.translation_y).sum();
+        assert!((moved - 8.0).abs() < 1e-4);
+        assert_eq!((events[2].x, events[2].y), (150.0, 116.0));
+    }
+
+    #[test]
+    fn rotate() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        // Turn both fingers 0.3 radians around their centroid, one finger at a time
+        let (cos, sin): (f32, f32) = (0.3f32.cos() * 50.0, 0.3f32.sin() * 50.0);
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 100.0, 100.0, 0),
+                contact(2, ContactPhase::Down, 200.0, 100.0, 0),
+                contact(1, ContactPhase::Move, 150.0 - cos, 100.0 - sin, 10),
+                contact(2, ContactPhase::Move, 150.0 + cos, 100.0 + sin, 20),
+            ],
+        );
+
+        assert_eq!(events[0].kind, GestureKind::Rotate);
+        assert_eq!(events[0].phase, GesturePhase::Begin);
+        assert!(events.iter().all(|event| event.kind == GestureKind::Rotate));
+        let rotation: f32 = events.iter().map(|event| event.rotation).sum();
+        assert!((rotation - 0.3).abs() < 1e-4);
+    }
+
+    #[test]
+    fn swipe() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        // The last finger only reports where it went with the up message
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 100.0, 100.0, 0),
+                contact(2, ContactPhase::Down, 150.0, 100.0, 0),
+                contact(3, ContactPhase::Down, 200.0, 100.0, 0),
+                contact(1, ContactPhase::Move, 100.0, 220.0, 100),
+                contact(2, ContactPhase::Move, 150.0, 220.0, 100),
+                contact(3, ContactPhase::Up, 200.0, 220.0, 200),
+                contact(1, ContactPhase::Up, 100.0, 220.0, 210),
+                contact(2, ContactPhase::Up, 150.0, 220.0, 220),
+            ],
+        );
+
+        assert_eq!(
+            kinds(&events),
+            vec![(GestureKind::Swipe(SwipeDirection::Down), GesturePhase::End)]
+        );
+        assert!((events[0].translation_y - 120.0).abs() < 1e-4);
+        assert_eq!(events[0].fingers, 3);
+    }
+
+    #[test]
+    fn slow_swipe_is_ignored() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 100.0, 100.0, 0),
+                contact(2, ContactPhase::Down, 150.0, 100.0, 0),
+                contact(3, ContactPhase::Down, 200.0, 100.0, 0),
+                contact(1, ContactPhase::Up, 0.0, 100.0, 600),
+                contact(2, ContactPhase::Up, 50.0, 100.0, 600),
+                contact(3, ContactPhase::Up, 100.0, 100.0, 600),
+            ],
+        );
+
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn long_press() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        recognizer.on_contact(contact(1, ContactPhase::Down, 50.0, 60.0, 1000));
+        recognizer.on_contact(contact(1, ContactPhase::Move, 55.0, 60.0, 1100));
+        recognizer.update(1499);
+        assert!(drain(&mut recognizer).is_empty());
+
+        // Fires without any movement, only time passing
+        recognizer.update(1500);
+        recognizer.update(1600);
+        recognizer.on_contact(contact(1, ContactPhase::Up, 55.0, 60.0, 1700));
+        let events: Vec<GestureEvent> = drain(&mut recognizer);
+        assert_eq!(
+            kinds(&events),
+            vec![
+                (GestureKind::LongPress, GesturePhase::Begin),
+                (GestureKind::LongPress, GesturePhase::End),
+            ]
+        );
+        assert_eq!((events[0].x, events[0].y), (55.0, 60.0));
+    }
+
+    #[test]
+    fn long_press_cancelled_by_movement() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        let events: Vec<GestureEvent> = feed(
+            &mut recognizer,
+            &[
+                contact(1, ContactPhase::Down, 50.0, 60.0, 0),
+                contact(1, ContactPhase::Move, 80.0, 60.0, 100),
+            ],
+        );
+        recognizer.update(1000);
+
+        assert!(events.is_empty());
+        assert!(drain(&mut recognizer).is_empty());
+    }
+
+    #[test]
+    fn long_press_wraps_with_the_tick_count() {
+        let mut recognizer: GestureRecognizer = GestureRecognizer::new(GestureConfig::DEFAULT);
+        recognizer.on_contact(contact(1, ContactPhase::Down, 0.0, 0.0, u32::MAX - 100));
+        recognizer.update(400);
+
+        assert_eq!(
+            kinds(&drain(&mut recognizer)),
+            vec![(GestureKind::LongPress, GesturePhase::Begin)]
+        );
+    }
+}
@@ -1,4 +1,6 @@
 const MAX_BUFFER_SIZE: usize = 16;
+/// The number of virtual-key codes. Every key code is below this.
+pub const KEY_CODE_COUNT: usize = 256;
 
 #[derive(Debug, Clone)]
 pub struct Keyboard {
@@ -19,9 +21,12 @@ pub struct Keyboard {
 
     /// If the user keeps a key pressed in this is true.
     pub auto_repeat_enabled: bool,
+
+    /// The first half of a UTF-16 surrogate pair. Characters outside of the BMP (like emoji) arrive as two WM_CHAR's.
+    pub high_surrogate: Option<u16>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 /// A event with info about the [KeyState] and the keycode.
 pub struct KeyEvent {
     pub key_state: KeyState,
@@ -37,12 +42,31 @@ pub enum KeyState {
     Release,
 }
 
+impl Default for Keyboard {
+    fn default() -> Self {
+        return Keyboard::new();
+    }
+}
+
 impl Keyboard {
+    pub fn new() -> Keyboard {
+        let mut keyboard: Keyboard = Keyboard {
+            key_states: vec![],
+            key_queue: vec![],
+            char_queue: vec![],
+            auto_repeat_enabled: false,
+            high_surrogate: None,
+        };
+        keyboard.reset();
+        return keyboard;
+    }
+
     /// Reset buffers and keystate map
     pub fn reset(&mut self) {
-        self.key_states = vec![0; 256];
+        self.key_states = vec![0; KEY_CODE_COUNT];
         self.key_queue = vec![];
         self.char_queue = vec![];
+        self.high_surrogate = None;
     }
 
     /// Check if key is pressed and remove it from the [KeyEvent] queue.<br>
@@ -99,27 +123,53 @@ impl Keyboard {
         self.auto_repeat_enabled = true;
     }
 
-    pub fn on_key_press(&mut self, key_code: u32) {
+    /// Returns the event that was added to the queue.
+    pub fn on_key_press(&mut self, key_code: u32) -> KeyEvent {
         self.key_states[key_code as usize] = 1;
-        self.key_queue.push(KeyEvent {
+        let event: KeyEvent = KeyEvent {
             key_state: KeyState::Press,
             key_code,
-        });
+        };
+        self.key_queue.push(event);
         trim_buffer(&mut self.key_queue);
+        return event;
     }
 
-    pub fn on_key_release(&mut self, key_code: u32) {
+    /// Returns the event that was added to the queue.
+    pub fn on_key_release(&mut self, key_code: u32) -> KeyEvent {
         self.key_states[key_code as usize] = 0;
-        self.key_queue.push(KeyEvent {
+        let event: KeyEvent = KeyEvent {
             key_state: KeyState::Release,
             key_code,
-        });
-        trim_buffer(&mut self.key_queue.as_mut());
+        };
+        self.key_queue.push(event);
+        trim_buffer(self.key_queue.as_mut());
+        return event;
     }
 
-    pub fn on_char(&mut self, char_code: u32) {
-        self.char_queue.push(char::from_u32(char_code).unwrap());
-        trim_buffer(&mut self.char_queue.as_mut());
+    /// `char_code` is a UTF-16 code unit, the window class is registered with RegisterClassExW.<br>
+    /// Returns the char that was added to the queue, or [None] when it was the first half of a surrogate pair.
+    pub fn on_char(&mut self, char_code: u32) -> Option<char> {
+        let unit: u16 = char_code as u16;
+        let ch: Option<char> = match (self.high_surrogate.take(), unit) {
+            // The first half of a pair, wait for the second WM_CHAR
+            (_, 0xD800..=0xDBFF) => {
+                self.high_surrogate = Some(unit);
+                None
+            }
+            (Some(high), 0xDC00..=0xDFFF) => char::decode_utf16([high, unit])
+                .next()
+                .and_then(|result| result.ok()),
+            // A lone surrogate can't be turned into a char
+            (None, 0xDC00..=0xDFFF) => None,
+            _ => char::from_u32(unit.into()),
+        };
+
+        if let Some(ch) = ch {
+            self.char_queue.push(ch);
+            trim_buffer(self.char_queue.as_mut());
+        }
+        return ch;
     }
 }
 
@@ -0,0 +1,267 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{GetLastError, HINSTANCE, HWND},
+        Graphics::Gdi::{COLOR_WINDOW, HBRUSH},
+        UI::{
+            Input::KeyboardAndMouse::EnableWindow,
+            WindowsAndMessaging::{
+                LoadCursorW, LoadIconW, PostQuitMessage, RegisterClassExW, HCURSOR, HICON,
+                IDC_ARROW, IDI_APPLICATION, WNDCLASSEXW, WNDCLASS_STYLES, WNDPROC,
+            },
+        },
+    },
+};
+
+use super::{error::BuildError, state::WindowState, wide::WideCString};
+
+/// A typed id for a window. Unlike a HWND it is never reused after the window is destroyed.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct WindowId(pub u64);
+
+/// How a window relates to the other windows of the app.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum WindowRelation {
+    /// A normal window with its own taskbar entry.
+    TopLevel,
+    /// Always on top of its owner and minimized together with it.
+    Owned(WindowId),
+    /// Lives inside the client area of its parent.
+    Child(WindowId),
+    /// An owned window that disables its owner until it is destroyed.
+    Modal(WindowId),
+}
+
+/// A registered window class. The class name has to stay alive as long as the class is registered.
+pub struct RegisteredClass {
+    pub atom: u16,
+    pub name: WideCString,
+    pub class: WNDCLASSEXW,
+}
+
+/// A window the [WindowManager] knows about.
+pub struct WindowEntry {
+    pub id: WindowId,
+    pub hwnd: HWND,
+    pub relation: WindowRelation,
+    pub state: Rc<RefCell<WindowState>>,
+}
+
+/**
+    Keeps track of all the windows of the current thread. Win32 windows belong to the thread that created them
+    and all their messages are dispatched on that thread, so there is one manager per thread.
+
+    The manager
+    - registers every window class once and hands out the same class for the next window with that name
+    - maps a HWND to the [WindowId] and state of the window so events end up at the window they belong to
+    - keeps the owner, child and modal relations
+    - posts WM_QUIT when the last window, or the main window, is destroyed
+*/
+#[derive(Default)]
+pub struct WindowManager {
+    classes: HashMap<String, RegisteredClass>,
+    windows: Vec<WindowEntry>,
+    main_window: Option<WindowId>,
+    next_id: u64,
+}
+
+thread_local! {
+    static MANAGER: RefCell<WindowManager> = RefCell::new(WindowManager::default());
+}
+
+/// Run `f` with the window manager of this thread. Don't call any Win32 function that sends messages from `f`.
+pub fn with_manager<R>(f: impl FnOnce(&mut WindowManager) -> R) -> R {
+    return MANAGER.with(|manager| f(&mut manager.borrow_mut()));
+}
+
+impl WindowManager {
+    /**
+        Register a window class, or return the atom of the class if it is already registered. Registering the same
+        class twice makes RegisterClassEx fail, so this is what makes it possible to create more than one window.
+        A registered class can't be changed, asking for it with another style or wndproc gives
+        [BuildError::ClassMismatch].
+
+        For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-registerclassexw
+    */
+    pub fn register_class(
+        &mut self,
+        class_name: &str,
+        style: WNDCLASS_STYLES,
+        wndproc: WNDPROC,
+        instance: HINSTANCE,
+    ) -> Result<(u16, PCWSTR), BuildError> {
+        if let Some(registered) = self.classes.get(class_name) {
+            let same_wndproc: bool =
+                registered.class.lpfnWndProc.map(|f| f as usize) == wndproc.map(|f| f as usize);
+            if registered.class.style != style || !same_wndproc {
+                return Err(BuildError::ClassMismatch);
+            }
+            return Ok((registered.atom, registered.name.as_pcwstr()));
+        }
+
+        let name: WideCString =
+            WideCString::new(class_name).map_err(|_| BuildError::InteriorNul("class name"))?;
+        let cursor: HCURSOR = unsafe { LoadCursorW(None, IDC_ARROW) }
+            .map_err(|_| BuildError::RegisterClassFailed(unsafe { GetLastError().0 }))?;
+
+        /*
+            Contains window class information. It is used with the RegisterClassEx
+            and GetClassInfoEx functions.
+            For more info about the fields of this class:
+            https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-wndclassexw
+        */
+        let class: WNDCLASSEXW = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style,
+            lpfnWndProc: wndproc,
+            hInstance: instance,
+            hCursor: cursor,
+            lpszClassName: name.as_pcwstr(),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hIcon: unsafe { LoadIconW(None, IDI_APPLICATION).unwrap_or_default() },
+            // The system colors need a +1, see the hbrBackground field in the link above
+            hbrBackground: HBRUSH(COLOR_WINDOW.0 as isize + 1),
+            lpszMenuName: PCWSTR::null(),
+            hIconSm: HICON(0isize as _),
+        };
+
+        /*
+            If you register the window class by using RegisterClassExW, the application tells the system that
+            the windows of the created class expect messages with text or character parameters to use the Unicode
+            (UTF-16) character set. The ANSI (A) functions can't show characters outside of the system code page.
+
+            If the function succeeds, the return value is a class atom that uniquely identifies the class being
+            registered. If the function fails, the return value is zero.
+        */
+        let atom: u16 = unsafe { RegisterClassExW(&class) };
+
+        if atom == 0 {
+            return Err(BuildError::RegisterClassFailed(unsafe { GetLastError().0 }));
+        }
+
+        // The WideCString owns its buffer on the heap, so the pointer stays valid after moving it into the map.
+        let class_name_ptr: PCWSTR = name.as_pcwstr();
+        self.classes.insert(
+            class_name.to_string(),
+            RegisteredClass { atom, name, class },
+        );
+        return Ok((atom, class_name_ptr));
+    }
+
+    /// Get the class info of a registered class.
+    pub fn class(&self, class_name: &str) -> Option<&RegisteredClass> {
+        return self.classes.get(class_name);
+    }
+
+    /// Reserve a [WindowId] for a window that is about to be created.
+    pub fn next_window_id(&mut self) -> WindowId {
+        self.next_id += 1;
+        return WindowId(self.next_id);
+    }
+
+    pub fn insert(&mut self, entry: WindowEntry) {
+        if self.main_window.is_none() && entry.relation == WindowRelation::TopLevel {
+            // The first top level window is the main window until the app says otherwise
+            self.main_window = Some(entry.id);
+        }
+        self.windows.push(entry);
+    }
+
+    /// Remove a window from the registry and return its entry.
+    pub fn remove(&mut self, hwnd: HWND) -> Option<WindowEntry> {
+        let index: usize = self.windows.iter().position(|entry| entry.hwnd == hwnd)?;
+        return Some(self.windows.remove(index));
+    }
+
+    pub fn get(&self, id: WindowId) -> Option<&WindowEntry> {
+        return self.windows.iter().find(|entry| entry.id == id);
+    }
+
+    pub fn get_by_hwnd(&self, hwnd: HWND) -> Option<&WindowEntry> {
+        return self.windows.iter().find(|entry| entry.hwnd == hwnd);
+    }
+
+    pub fn window_id(&self, hwnd: HWND) -> Option<WindowId> {
+        return self.get_by_hwnd(hwnd).map(|entry| entry.id);
+    }
+
+    pub fn hwnd(&self, id: WindowId) -> Option<HWND> {
+        return self.get(id).map(|entry| entry.hwnd);
+    }
+
+    /// The owner of `id` when it is a modal window.
+    pub fn modal_owner(&self, id: WindowId) -> Option<HWND> {
+        return match self.get(id)?.relation {
+            WindowRelation::Modal(owner) => self.hwnd(owner),
+            _ => None,
+        };
+    }
+
+    /// The windows which are owned by, child of or modal to `id`.
+    pub fn dependents(&self, id: WindowId) -> Vec<WindowId> {
+        return self
+            .windows
+            .iter()
+            .filter(|entry| match entry.relation {
+                WindowRelation::Owned(owner)
+                | WindowRelation::Child(owner)
+                | WindowRelation::Modal(owner) => owner == id,
+                WindowRelation::TopLevel => false,
+            })
+            .map(|entry| entry.id)
+            .collect();
+    }
+
+    pub fn window_count(&self) -> usize {
+        return self.windows.len();
+    }
+
+    pub fn main_window(&self) -> Option<WindowId> {
+        return self.main_window;
+    }
+
+    /// Quit the app when this window closes instead of waiting for the last window. Pass [None] to only
+    /// quit when all the windows are closed.
+    pub fn set_main_window(&mut self, id: Option<WindowId>) {
+        self.main_window = id;
+    }
+}
+
+/**
+    Called from the wndproc on WM_DESTROY. Removes the window from the registry, gives the owner of a modal window
+    its input back and posts WM_QUIT when this was the last window or the main window.<br>
+    [Backend::destroy_window()](super::backend::Backend::destroy_window()) enables the owner before the window is
+    destroyed already, this is for a modal window that windows destroys by itself, like along with its owner.
+
+    # Safety
+    Only call it from the wndproc of `hwnd`, on the thread that created the window.
+*/
+pub unsafe fn on_destroy(hwnd: HWND) {
+    let (entry, quit): (Option<WindowEntry>, bool) = with_manager(|manager| {
+        let entry: Option<WindowEntry> = manager.remove(hwnd);
+        let was_main: bool = match &entry {
+            Some(entry) => manager.main_window == Some(entry.id),
+            None => false,
+        };
+        let quit: bool = entry.is_some() && (was_main || manager.window_count() == 0);
+        return (entry, quit);
+    });
+
+    if let Some(WindowEntry {
+        relation: WindowRelation::Modal(owner),
+        ..
+    }) = entry
+    {
+        if let Some(owner_hwnd) = with_manager(|manager| manager.hwnd(owner)) {
+            // See https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-enablewindow
+            EnableWindow(owner_hwnd, true);
+        }
+    }
+
+    if quit {
+        PostQuitMessage(0);
+    }
+}
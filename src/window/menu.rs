@@ -0,0 +1,656 @@
+use super::keyboard::Keyboard;
+
+/// The virtual key codes of the modifiers, the generic ones WM_KEYDOWN gives for both the left and right key.
+const VK_SHIFT: u32 = 0x10;
+const VK_CONTROL: u32 = 0x11;
+const VK_MENU: u32 = 0x12;
+
+/// The names of the keys for [Accelerator::parse()] and [Accelerator::label()]. Letters and digits are their own name.
+const KEY_NAMES: [(u32, &str); 23] = [
+    (0x08, "Backspace"),
+    (0x09, "Tab"),
+    (0x0D, "Enter"),
+    (0x1B, "Esc"),
+    (0x20, "Space"),
+    (0x21, "PgUp"),
+    (0x22, "PgDn"),
+    (0x23, "End"),
+    (0x24, "Home"),
+    (0x25, "Left"),
+    (0x26, "Up"),
+    (0x27, "Right"),
+    (0x28, "Down"),
+    (0x2D, "Ins"),
+    (0x2E, "Del"),
+    (0x6B, "Num+"),
+    (0x6D, "Num-"),
+    (0xBB, "Plus"),
+    (0xBC, "Comma"),
+    (0xBD, "Minus"),
+    (0xBE, "Period"),
+    (0xBF, "Slash"),
+    (0xC0, "Tilde"),
+];
+
+/**
+    The id of a menu item, the app picks them. This is what [Event::MenuActivated](super::event::Event::MenuActivated)
+    carries, and the id WM_COMMAND gives. 0 is not a valid id, TrackPopupMenu uses it for "nothing was picked".
+*/
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct MenuId(pub u16);
+
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+        alt: false,
+    };
+
+    /// The modifiers that are held down right now.
+    pub fn from_keyboard(keyboard: &Keyboard) -> Modifiers {
+        return Modifiers {
+            ctrl: keyboard.key_is_pressed(VK_CONTROL as u16),
+            shift: keyboard.key_is_pressed(VK_SHIFT as u16),
+            alt: keyboard.key_is_pressed(VK_MENU as u16),
+        };
+    }
+}
+
+/// A keyboard shortcut: a virtual key code with the modifiers that have to be held down.
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Accelerator {
+    pub key_code: u32,
+    pub modifiers: Modifiers,
+}
+
+impl Accelerator {
+    pub fn new(key_code: u32, modifiers: Modifiers) -> Accelerator {
+        return Accelerator {
+            key_code,
+            modifiers,
+        };
+    }
+
+    /**
+        Read a shortcut like `"Ctrl+S"`, `"Ctrl+Shift+Z"` or `"F5"`. The modifiers are `Ctrl`, `Shift` and `Alt`,
+        the key is a letter, a digit, `F1` to `F24` or one of the names [Accelerator::label()] uses. Case doesn't
+        matter. [None] when the text isn't a shortcut.
+    */
+    pub fn parse(text: &str) -> Option<Accelerator> {
+        let text: &str = text.trim();
+        // The key can be a plus or end with one, like "Ctrl++" and "Num+"
+        let split_at: Option<usize> = match text.strip_suffix('+') {
+            Some(rest) => rest.rfind('+'),
+            None => text.rfind('+'),
+        };
+        let (modifier_text, key): (Option<&str>, &str) = match split_at {
+            Some(index) => (Some(&text[..index]), text[index + 1..].trim()),
+            None => (None, text),
+        };
+        let key: &str = if key == "+" { "Plus" } else { key };
+
+        let mut modifiers: Modifiers = Modifiers::NONE;
+        for part in modifier_text.into_iter().flat_map(|text| text.split('+')) {
+            match part.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                _ => return None,
+            }
+        }
+        return Some(Accelerator::new(key_code_from_name(key)?, modifiers));
+    }
+
+    /// The text windows shows next to the item, like `Ctrl+Shift+S`.
+    pub fn label(&self) -> String {
+        let mut label: String = String::new();
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl+"),
+            (self.modifiers.shift, "Shift+"),
+            (self.modifiers.alt, "Alt+"),
+        ] {
+            if held {
+                label.push_str(name);
+            }
+        }
+        label.push_str(&key_name(self.key_code));
+        return label;
+    }
+
+    pub fn matches(&self, key_code: u32, modifiers: Modifiers) -> bool {
+        return self.key_code == key_code && self.modifiers == modifiers;
+    }
+}
+
+/// Whether an item has a check mark.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum MenuCheck {
+    #[default]
+    None,
+    /// A check mark that is toggled on its own.
+    Check(bool),
+    /// A dot. Radio items next to each other form a group, only one of the group is checked.
+    Radio(bool),
+}
+
+/// An item that sends [Event::MenuActivated](super::event::Event::MenuActivated) when it is picked.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MenuAction {
+    pub id: MenuId,
+    /// Put a `&` before the letter that picks the item while the menu is open, like `"&Save"`.
+    pub label: String,
+    pub accelerator: Option<Accelerator>,
+    pub enabled: bool,
+    pub check: MenuCheck,
+}
+
+impl MenuAction {
+    pub fn new(id: MenuId, label: &str) -> MenuAction {
+        return MenuAction {
+            id,
+            label: label.to_string(),
+            accelerator: None,
+            enabled: true,
+            check: MenuCheck::None,
+        };
+    }
+
+    pub fn check(id: MenuId, label: &str, checked: bool) -> MenuAction {
+        return MenuAction {
+            check: MenuCheck::Check(checked),
+            ..MenuAction::new(id, label)
+        };
+    }
+
+    pub fn radio(id: MenuId, label: &str, checked: bool) -> MenuAction {
+        return MenuAction {
+            check: MenuCheck::Radio(checked),
+            ..MenuAction::new(id, label)
+        };
+    }
+
+    pub fn accelerator(mut self, accelerator: Accelerator) -> Self {
+        self.accelerator = Some(accelerator);
+        return self;
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        return self;
+    }
+
+    pub fn checked(&self) -> bool {
+        return matches!(self.check, MenuCheck::Check(true) | MenuCheck::Radio(true));
+    }
+
+    /// The label with the accelerator after a tab, which windows puts in a column on the right.
+    pub fn full_label(&self) -> String {
+        return match &self.accelerator {
+            Some(accelerator) => format!("{}\t{}", self.label, accelerator.label()),
+            None => self.label.clone(),
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MenuItem {
+    Action(MenuAction),
+    Submenu {
+        label: String,
+        menu: Menu,
+        /// A disabled submenu can't be opened, so none of its items can be picked.
+        enabled: bool,
+    },
+    Separator,
+}
+
+/**
+    A menu described as data. The same model is a menu bar, see [Window::set_menu()](super::Window::set_menu()), or
+    a context menu, see [Window::set_context_menu()](super::Window::set_context_menu()):
+    ```ignore
+    let menu: Menu = Menu::new()
+        .submenu("&File", Menu::new()
+            .item(MenuAction::new(OPEN, "&Open...").accelerator(Accelerator::parse("Ctrl+O").unwrap()))
+            .separator()
+            .item(MenuAction::new(EXIT, "E&xit")))
+        .submenu("&View", Menu::new()
+            .item(MenuAction::check(GRID, "&Grid", true))
+            .separator()
+            .item(MenuAction::radio(SMALL, "&Small icons", true))
+            .item(MenuAction::radio(LARGE, "&Large icons", false)));
+    ```
+    The accelerators of the enabled items work while the window has focus, also when the menu is closed.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/menurc/about-menus
+*/
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Menu {
+    pub items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        return Menu::default();
+    }
+
+    pub fn item(mut self, action: MenuAction) -> Self {
+        self.items.push(MenuItem::Action(action));
+        return self;
+    }
+
+    pub fn submenu(mut self, label: &str, menu: Menu) -> Self {
+        self.items.push(MenuItem::Submenu {
+            label: label.to_string(),
+            menu,
+            enabled: true,
+        });
+        return self;
+    }
+
+    pub fn separator(mut self) -> Self {
+        self.items.push(MenuItem::Separator);
+        return self;
+    }
+
+    /// The item with `id`, also in the submenus.
+    pub fn find(&self, id: MenuId) -> Option<&MenuAction> {
+        for item in self.items.iter() {
+            match item {
+                MenuItem::Action(action) if action.id == id => return Some(action),
+                MenuItem::Submenu { menu, .. } => {
+                    if let Some(action) = menu.find(id) {
+                        return Some(action);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    pub fn find_mut(&mut self, id: MenuId) -> Option<&mut MenuAction> {
+        for item in self.items.iter_mut() {
+            match item {
+                MenuItem::Action(action) if action.id == id => return Some(action),
+                MenuItem::Submenu { menu, .. } => {
+                    if let Some(action) = menu.find_mut(id) {
+                        return Some(action);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    /// True when the item with `id` can be picked: it and all the submenus it is in are enabled.
+    pub fn is_enabled(&self, id: MenuId) -> bool {
+        for item in self.items.iter() {
+            match item {
+                MenuItem::Action(action) if action.id == id => return action.enabled,
+                MenuItem::Submenu { menu, enabled, .. } if menu.find(id).is_some() => {
+                    return *enabled && menu.is_enabled(id);
+                }
+                _ => {}
+            }
+        }
+        return false;
+    }
+
+    /// Returns false when there is no item with `id`.
+    pub fn set_enabled(&mut self, id: MenuId, enabled: bool) -> bool {
+        return match self.find_mut(id) {
+            Some(action) => {
+                action.enabled = enabled;
+                true
+            }
+            None => false,
+        };
+    }
+
+    /**
+        Check or uncheck an item. Checking a radio item unchecks the other items of its group. Returns false when
+        there is no item with `id` or it has no check mark.
+    */
+    pub fn set_checked(&mut self, id: MenuId, checked: bool) -> bool {
+        let index: Option<usize> = self.items.iter().position(|item| match item {
+            MenuItem::Action(action) => action.id == id,
+            _ => false,
+        });
+        let index: usize = match index {
+            Some(index) => index,
+            None => {
+                return self.items.iter_mut().any(|item| match item {
+                    MenuItem::Submenu { menu, .. } => menu.set_checked(id, checked),
+                    _ => false,
+                });
+            }
+        };
+
+        let is_radio: bool = match &self.items[index] {
+            MenuItem::Action(action) => match action.check {
+                MenuCheck::None => return false,
+                MenuCheck::Check(_) => false,
+                MenuCheck::Radio(_) => true,
+            },
+            _ => return false,
+        };
+        if is_radio && checked {
+            let group: std::ops::Range<usize> = self.radio_group(index);
+            for item in self.items[group].iter_mut() {
+                if let MenuItem::Action(action) = item {
+                    action.check = MenuCheck::Radio(false);
+                }
+            }
+        }
+        if let MenuItem::Action(action) = &mut self.items[index] {
+            action.check = match is_radio {
+                true => MenuCheck::Radio(checked),
+                false => MenuCheck::Check(checked),
+            };
+        }
+        return true;
+    }
+
+    /// The id of the enabled item with this shortcut, [None] when no item has it.
+    pub fn find_accelerator(&self, key_code: u32, modifiers: Modifiers) -> Option<MenuId> {
+        for item in self.items.iter() {
+            match item {
+                MenuItem::Action(action) if action.enabled => {
+                    if let Some(accelerator) = action.accelerator {
+                        if accelerator.matches(key_code, modifiers) {
+                            return Some(action.id);
+                        }
+                    }
+                }
+                MenuItem::Submenu {
+                    menu,
+                    enabled: true,
+                    ..
+                } => {
+                    if let Some(id) = menu.find_accelerator(key_code, modifiers) {
+                        return Some(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return None;
+    }
+
+    /// The radio items next to the item at `index`, which is a radio item itself.
+    fn radio_group(&self, index: usize) -> std::ops::Range<usize> {
+        let is_radio = |item: &MenuItem| -> bool {
+            return matches!(
+                item,
+                MenuItem::Action(MenuAction {
+                    check: MenuCheck::Radio(_),
+                    ..
+                })
+            );
+        };
+        let mut start: usize = index;
+        while start > 0 && is_radio(&self.items[start - 1]) {
+            start -= 1;
+        }
+        let mut end: usize = index + 1;
+        while end < self.items.len() && is_radio(&self.items[end]) {
+            end += 1;
+        }
+        return start..end;
+    }
+}
+
+/**
+    Shortcuts that don't belong to a menu item, like Ctrl+Tab to switch documents. They send the same
+    [Event::MenuActivated](super::event::Event::MenuActivated) as the menu items do, see
+    [Window::set_accelerators()](super::Window::set_accelerators()).
+*/
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct AcceleratorTable {
+    pub entries: Vec<(Accelerator, MenuId)>,
+}
+
+impl AcceleratorTable {
+    pub fn new() -> AcceleratorTable {
+        return AcceleratorTable::default();
+    }
+
+    pub fn add(mut self, accelerator: Accelerator, id: MenuId) -> Self {
+        self.entries.push((accelerator, id));
+        return self;
+    }
+
+    pub fn find(&self, key_code: u32, modifiers: Modifiers) -> Option<MenuId> {
+        return self
+            .entries
+            .iter()
+            .find(|(accelerator, _)| accelerator.matches(key_code, modifiers))
+            .map(|(_, id)| *id);
+    }
+}
+
+fn key_name(key_code: u32) -> String {
+    return match key_code {
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(key_code).unwrap().to_string(),
+        0x70..=0x87 => format!("F{}", key_code - 0x70 + 1),
+        _ => match KEY_NAMES.iter().find(|(code, _)| *code == key_code) {
+            Some((_, name)) => name.to_string(),
+            None => format!("0x{:02X}", key_code),
+        },
+    };
+}
+
+fn key_code_from_name(name: &str) -> Option<u32> {
+    let upper: String = name.to_ascii_uppercase();
+    let mut chars = upper.chars();
+    if let (Some(char), None) = (chars.next(), chars.next()) {
+        if char.is_ascii_alphanumeric() {
+            return Some(char as u32);
+        }
+    }
+    if let Some(number) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        if (1..=24).contains(&number) {
+            return Some(0x70 + number - 1);
+        }
+    }
+    return KEY_NAMES
+        .iter()
+        .find(|(_, key)| key.eq_ignore_ascii_case(name))
+        .map(|(code, _)| *code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTRL: Modifiers = Modifiers {
+        ctrl: true,
+        shift: false,
+        alt: false,
+    };
+    const CTRL_SHIFT: Modifiers = Modifiers {
+        ctrl: true,
+        shift: true,
+        alt: false,
+    };
+    const ALL: Modifiers = Modifiers {
+        ctrl: true,
+        shift: true,
+        alt: true,
+    };
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Accelerator::parse("Ctrl+S"),
+            Some(Accelerator::new(0x53, CTRL))
+        );
+        assert_eq!(
+            Accelerator::parse("Ctrl+Shift+Z"),
+            Some(Accelerator::new(0x5A, CTRL_SHIFT))
+        );
+        assert_eq!(
+            Accelerator::parse("Shift+Control+z"),
+            Some(Accelerator::new(0x5A, CTRL_SHIFT))
+        );
+        assert_eq!(
+            Accelerator::parse("alt+shift+ctrl+del"),
+            Some(Accelerator::new(0x2E, ALL))
+        );
+        assert_eq!(
+            Accelerator::parse(" Ctrl + 1 "),
+            Some(Accelerator::new(0x31, CTRL))
+        );
+        assert_eq!(
+            Accelerator::parse("F5"),
+            Some(Accelerator::new(0x74, Modifiers::NONE))
+        );
+        assert_eq!(
+            Accelerator::parse("f24"),
+            Some(Accelerator::new(0x87, Modifiers::NONE))
+        );
+        assert_eq!(
+            Accelerator::parse("Alt+F4").unwrap().modifiers,
+            Modifiers {
+                alt: true,
+                ..Modifiers::NONE
+            }
+        );
+        assert_eq!(
+            Accelerator::parse("Ctrl++"),
+            Some(Accelerator::new(0xBB, CTRL))
+        );
+        assert_eq!(
+            Accelerator::parse("+"),
+            Some(Accelerator::new(0xBB, Modifiers::NONE))
+        );
+        assert_eq!(
+            Accelerator::parse("Ctrl+Num+"),
+            Some(Accelerator::new(0x6B, CTRL))
+        );
+        assert_eq!(
+            Accelerator::parse("Ctrl+Num-"),
+            Some(Accelerator::new(0x6D, CTRL))
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "",
+            "Ctrl",
+            "Ctrl+",
+            "++",
+            "Ctrl+SS",
+            "Win+S",
+            "S+Ctrl",
+            "Ctrl++S",
+            "F0",
+            "F25",
+            "é",
+            "Ctrl+0x41",
+        ] {
+            assert_eq!(Accelerator::parse(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn label_round_trip() {
+        let mut key_codes: Vec<u32> = KEY_NAMES.iter().map(|(code, _)| *code).collect();
+        key_codes.extend(0x30..=0x39);
+        key_codes.extend(0x41..=0x5A);
+        key_codes.extend(0x70..=0x87);
+        for key_code in key_codes {
+            for modifiers in [Modifiers::NONE, CTRL, CTRL_SHIFT, ALL] {
+                let accelerator: Accelerator = Accelerator::new(key_code, modifiers);
+                assert_eq!(Accelerator::parse(&accelerator.label()), Some(accelerator));
+            }
+        }
+        assert_eq!(Accelerator::new(0x53, CTRL_SHIFT).label(), "Ctrl+Shift+S");
+        assert_eq!(Accelerator::new(0xFF, Modifiers::NONE).label(), "0xFF");
+    }
+
+    #[test]
+    fn matches_needs_the_exact_modifiers() {
+        let save: Accelerator = Accelerator::parse("Ctrl+S").unwrap();
+        assert!(save.matches(0x53, CTRL));
+        assert!(!save.matches(0x53, CTRL_SHIFT));
+        assert!(!save.matches(0x53, Modifiers::NONE));
+        assert!(!save.matches(0x41, CTRL));
+
+        let rename: Accelerator = Accelerator::parse("F2").unwrap();
+        assert!(rename.matches(0x71, Modifiers::NONE));
+        assert!(!rename.matches(0x71, ALL));
+    }
+
+    #[test]
+    fn find_accelerator() {
+        let accelerator = |text: &str| Accelerator::parse(text).unwrap();
+        let menu: Menu = Menu::new()
+            .submenu(
+                "&File",
+                Menu::new()
+                    .item(MenuAction::new(MenuId(1), "&Save").accelerator(accelerator("Ctrl+S")))
+                    .item(
+                        MenuAction::new(MenuId(2), "Save &As")
+                            .accelerator(accelerator("Ctrl+Shift+S")),
+                    )
+                    .item(
+                        MenuAction::new(MenuId(3), "&Print")
+                            .accelerator(accelerator("Ctrl+P"))
+                            .enabled(false),
+                    ),
+            )
+            .submenu(
+                "&Edit",
+                Menu::new()
+                    .submenu(
+                        "&Find",
+                        Menu::new().item(
+                            MenuAction::new(MenuId(4), "&Next").accelerator(accelerator("F3")),
+                        ),
+                    )
+                    // The first item with a shortcut wins
+                    .item(MenuAction::new(MenuId(5), "&Store").accelerator(accelerator("Ctrl+S"))),
+            );
+        let mut menu: Menu = menu;
+        menu.items.push(MenuItem::Submenu {
+            label: "&Tools".to_string(),
+            menu: Menu::new()
+                .item(MenuAction::new(MenuId(6), "&Options").accelerator(accelerator("Ctrl+O"))),
+            enabled: false,
+        });
+
+        assert_eq!(menu.find_accelerator(0x53, CTRL), Some(MenuId(1)));
+        assert_eq!(menu.find_accelerator(0x53, CTRL_SHIFT), Some(MenuId(2)));
+        assert_eq!(
+            menu.find_accelerator(0x72, Modifiers::NONE),
+            Some(MenuId(4))
+        );
+        assert_eq!(menu.find_accelerator(0x72, CTRL), None);
+        // Disabled items and the items of a disabled submenu can't be picked
+        assert_eq!(menu.find_accelerator(0x50, CTRL), None);
+        assert_eq!(menu.find_accelerator(0x4F, CTRL), None);
+
+        assert!(menu.set_enabled(MenuId(3), true));
+        assert_eq!(menu.find_accelerator(0x50, CTRL), Some(MenuId(3)));
+    }
+
+    #[test]
+    fn accelerator_table() {
+        let table: AcceleratorTable = AcceleratorTable::new()
+            .add(Accelerator::parse("Ctrl+Tab").unwrap(), MenuId(10))
+            .add(Accelerator::parse("Ctrl+Shift+Tab").unwrap(), MenuId(11));
+        assert_eq!(table.find(0x09, CTRL), Some(MenuId(10)));
+        assert_eq!(table.find(0x09, CTRL_SHIFT), Some(MenuId(11)));
+        assert_eq!(table.find(0x09, Modifiers::NONE), None);
+    }
+}
@@ -279,6 +279,7 @@ pub fn _id_to_name(id: u32) -> String {
         (791, "WM_PRINT"),
         (792, "WM_PRINTCLIENT"),
         (793, "WM_APPCOMMAND"),
+        (797, "WM_CLIPBOARDUPDATE"),
         (856, "WM_HANDHELDFIRST"),
         (863, "WM_HANDHELDLAST"),
         (864, "WM_AFXFIRST"),
@@ -0,0 +1,292 @@
+use windows::Win32::UI::WindowsAndMessaging::{WINDOW_EX_STYLE, WINDOW_STYLE};
+
+use super::{
+    coords::{scale_factor_from_dpi, PhysicalPoint, ScreenPoint},
+    rect::Rect,
+};
+
+/// The handle of a monitor. The HMONITOR on the Win32 backend, the number of a fake monitor on the headless one.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct MonitorId(pub isize);
+
+/// A resolution a monitor can switch to for [WindowMode::ExclusiveFullscreen].
+#[derive(Debug, Default, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct VideoMode {
+    pub width: u32,
+    pub height: u32,
+    /// In Hz.
+    pub refresh_rate: u32,
+    /// Bits per pixel.
+    pub bit_depth: u32,
+}
+
+/**
+    A monitor of the desktop. All the rects are physical pixels in screen coordinates, the primary monitor has its
+    top-left corner at (0, 0) and the other monitors are placed around it.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/gdi/multiple-display-monitors
+*/
+#[derive(Debug, PartialEq, Clone)]
+pub struct Monitor {
+    pub id: MonitorId,
+    /// The name of the monitor, or of the display device when windows doesn't know it.
+    pub name: String,
+    /// The whole monitor.
+    pub bounds: Rect,
+    /// The part of the monitor that isn't covered by the taskbar and docked toolbars.
+    pub work_area: Rect,
+    pub dpi: u32,
+    /// In Hz, [None] when the driver uses the hardware default.
+    pub refresh_rate: Option<u32>,
+    pub primary: bool,
+    /// The modes for [WindowMode::ExclusiveFullscreen], without duplicates.
+    pub video_modes: Vec<VideoMode>,
+}
+
+impl Monitor {
+    pub fn scale_factor(&self) -> f64 {
+        return scale_factor_from_dpi(self.dpi);
+    }
+
+    pub fn contains(&self, point: ScreenPoint) -> bool {
+        return point.x >= self.bounds.left
+            && point.x < self.bounds.right
+            && point.y >= self.bounds.top
+            && point.y < self.bounds.bottom;
+    }
+
+    /**
+        GetWindowPlacement and SetWindowPlacement use workspace coordinates, which start at the top-left corner of
+        the work area instead of the monitor. Add this to a workspace point to get a screen point.
+    */
+    pub fn workspace_offset(&self) -> (i32, i32) {
+        return (
+            self.work_area.left - self.bounds.left,
+            self.work_area.top - self.bounds.top,
+        );
+    }
+}
+
+/// How a window covers the screen, see [Window::set_window_mode()](super::Window::set_window_mode()).
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum WindowMode {
+    /// A normal window with the placement it had before going fullscreen.
+    #[default]
+    Windowed,
+    /// A window without borders that covers the whole monitor. Switching is instant and other windows can still
+    /// be shown on top of it.
+    BorderlessFullscreen(MonitorId),
+    /// Like borderless fullscreen, but the monitor switches to another video mode. The monitor goes back to its
+    /// own mode when the window leaves this mode.
+    ExclusiveFullscreen(MonitorId, VideoMode),
+}
+
+impl WindowMode {
+    pub fn monitor(&self) -> Option<MonitorId> {
+        return match self {
+            WindowMode::Windowed => None,
+            WindowMode::BorderlessFullscreen(monitor)
+            | WindowMode::ExclusiveFullscreen(monitor, _) => Some(*monitor),
+        };
+    }
+}
+
+/// Where a window is and what it looks like, saved when a window goes fullscreen so it can be restored.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Placement {
+    /// The outer rect of the window in screen coordinates. For a maximized window this is the rect it is restored to.
+    pub rect: Rect,
+    pub maximized: bool,
+    pub style: WINDOW_STYLE,
+    pub ex_style: WINDOW_EX_STYLE,
+}
+
+pub fn primary_monitor(monitors: &[Monitor]) -> Option<&Monitor> {
+    return monitors
+        .iter()
+        .find(|monitor| monitor.primary)
+        .or(monitors.first());
+}
+
+pub fn find_monitor(monitors: &[Monitor], id: MonitorId) -> Option<&Monitor> {
+    return monitors.iter().find(|monitor| monitor.id == id);
+}
+
+/// The monitor that contains `point`, or the nearest one. Like MonitorFromPoint with MONITOR_DEFAULTTONEAREST.
+pub fn monitor_from_point(monitors: &[Monitor], point: ScreenPoint) -> Option<&Monitor> {
+    return monitors
+        .iter()
+        .min_by_key(|monitor| distance_squared(monitor.bounds, point));
+}
+
+/**
+    The monitor that has the largest part of `rect`, or the nearest one when `rect` is on none of them. This is the
+    monitor windows considers the window to be on, like MonitorFromRect with MONITOR_DEFAULTTONEAREST.
+*/
+pub fn monitor_from_rect(monitors: &[Monitor], rect: Rect) -> Option<&Monitor> {
+    let largest: Option<&Monitor> = monitors
+        .iter()
+        .filter(|monitor| intersection_area(monitor.bounds, rect) > 0)
+        .max_by_key(|monitor| intersection_area(monitor.bounds, rect));
+    if largest.is_some() {
+        return largest;
+    }
+    let center: ScreenPoint = ScreenPoint {
+        x: rect.left + rect.width() / 2,
+        y: rect.top + rect.height() / 2,
+    };
+    return monitor_from_point(monitors, center);
+}
+
+/// A rect of `width` x `height` in the middle of `area`.
+pub fn center_in(area: Rect, width: i32, height: i32) -> Rect {
+    let left: i32 = area.left + (area.width() - width) / 2;
+    let top: i32 = area.top + (area.height() - height) / 2;
+    return Rect {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    };
+}
+
+/**
+    Where a new window of `width` x `height` goes when it isn't fullscreen or maximized: with its top-left corner at
+    `corner` when the builder has a position, else in the middle of `work_area`. Both backends use this for the rect
+    a window that opens fullscreen goes back to.
+*/
+pub fn windowed_rect(
+    corner: Option<PhysicalPoint>,
+    work_area: Rect,
+    width: i32,
+    height: i32,
+) -> Rect {
+    return match corner {
+        Some(corner) => Rect {
+            left: corner.x,
+            top: corner.y,
+            right: corner.x + width,
+            bottom: corner.y + height,
+        },
+        None => center_in(work_area, width, height),
+    };
+}
+
+/**
+    Move a window rect from the work area of `from` to the work area of `to`, at the same offset from the top-left
+    corner. The rect is pushed back into the work area when it sticks out and shrunk when it is larger. The size
+    isn't scaled for the DPI of `to`, windows does that with WM_DPICHANGED once the window is on the new monitor.
+*/
+pub fn move_to_monitor(rect: Rect, from: &Monitor, to: &Monitor) -> Rect {
+    let moved: Rect = Rect {
+        left: to.work_area.left + (rect.left - from.work_area.left),
+        top: to.work_area.top + (rect.top - from.work_area.top),
+        right: to.work_area.left + (rect.right - from.work_area.left),
+        bottom: to.work_area.top + (rect.bottom - from.work_area.top),
+    };
+    return clamp_to_area(moved, to.work_area);
+}
+
+/// Move `rect` into `area`, shrinking it when it doesn't fit.
+pub fn clamp_to_area(rect: Rect, area: Rect) -> Rect {
+    let width: i32 = rect.width().min(area.width());
+    let height: i32 = rect.height().min(area.height());
+    let left: i32 = rect.left.clamp(area.left, area.right - width);
+    let top: i32 = rect.top.clamp(area.top, area.bottom - height);
+    return Rect {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    };
+}
+
+fn intersection_area(a: Rect, b: Rect) -> i64 {
+    let width: i64 = (a.right.min(b.right) - a.left.max(b.left)).max(0) as i64;
+    let height: i64 = (a.bottom.min(b.bottom) - a.top.max(b.top)).max(0) as i64;
+    return width * height;
+}
+
+/// 0 for a point inside `rect`.
+fn distance_squared(rect: Rect, point: ScreenPoint) -> i64 {
+    let dx: i64 = (rect.left - point.x).max(point.x - (rect.right - 1)).max(0) as i64;
+    let dy: i64 = (rect.top - point.y).max(point.y - (rect.bottom - 1)).max(0) as i64;
+    return dx * dx + dy * dy;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::backend::headless::fake_monitor;
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        return Rect {
+            left,
+            top,
+            right,
+            bottom,
+        };
+    }
+
+    #[test]
+    fn clamp_to_area_pushes_the_rect_back() {
+        let area: Rect = rect(0, 0, 1920, 1040);
+        assert_eq!(
+            clamp_to_area(rect(100, 100, 900, 700), area),
+            rect(100, 100, 900, 700)
+        );
+        assert_eq!(
+            clamp_to_area(rect(1500, 800, 2300, 1400), area),
+            rect(1120, 440, 1920, 1040)
+        );
+        assert_eq!(
+            clamp_to_area(rect(-300, -50, 500, 550), area),
+            rect(0, 0, 800, 600)
+        );
+        // Too large, it is shrunk to the area
+        assert_eq!(clamp_to_area(rect(-10, 20, 2500, 1500), area), area);
+        // An area left of and above the primary monitor
+        let left: Rect = rect(-1280, -200, 0, 520);
+        assert_eq!(
+            clamp_to_area(rect(100, 100, 900, 700), left),
+            rect(-800, -80, 0, 520)
+        );
+    }
+
+    #[test]
+    fn monitor_from_rect_picks_the_largest_part() {
+        let monitors: Vec<Monitor> = vec![
+            fake_monitor(1, 0, 0, 1920, 1080, 96),
+            fake_monitor(2, 1920, 0, 1280, 720, 144),
+        ];
+        let on: MonitorId = monitor_from_rect(&monitors, rect(1800, 100, 2200, 400))
+            .unwrap()
+            .id;
+        assert_eq!(on, MonitorId(2));
+        let on: MonitorId = monitor_from_rect(&monitors, rect(1600, 100, 2000, 400))
+            .unwrap()
+            .id;
+        assert_eq!(on, MonitorId(1));
+        // Off screen, below the second monitor
+        let on: MonitorId = monitor_from_rect(&monitors, rect(2000, 900, 2400, 1000))
+            .unwrap()
+            .id;
+        assert_eq!(on, MonitorId(2));
+        assert_eq!(primary_monitor(&monitors).unwrap().id, MonitorId(1));
+        assert!(monitor_from_rect(&[], rect(0, 0, 10, 10)).is_none());
+    }
+
+    #[test]
+    fn move_to_monitor_keeps_the_work_area_offset() {
+        let mut from: Monitor = fake_monitor(1, 0, 0, 1920, 1080, 96);
+        from.work_area.top += 40;
+        let to: Monitor = fake_monitor(2, -1280, 0, 1280, 720, 96);
+        assert_eq!(from.workspace_offset(), (0, 40));
+
+        let moved: Rect = move_to_monitor(rect(100, 140, 900, 740), &from, &to);
+        assert_eq!(moved, rect(-1180, 100, -380, 700));
+        // Doesn't fit below, pushed up into the work area
+        let moved: Rect = move_to_monitor(rect(100, 340, 900, 940), &from, &to);
+        assert_eq!(moved, rect(-1180, 120, -380, 720));
+    }
+}
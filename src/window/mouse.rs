@@ -1,4 +1,4 @@
-use windows::Win32::Foundation::POINTS;
+use super::coords::{ClientPoint, LogicalPoint};
 const MAX_BUFFER_SIZE: usize = 16;
 const WHEEL_DELTA: i16 = 120;
 
@@ -9,18 +9,18 @@ pub struct Mouse {
     pub left_pressed: bool,
     pub right_pressed: bool,
     pub wheel_pressed: bool,
-    pub x: i16,
-    pub y: i16,
+    /// The last known cursor position in client coordinates.
+    pub pos: ClientPoint,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub struct MouseEvent {
     pub mouse_state: MouseState,
     pub left_pressed: bool,
     pub right_pressed: bool,
     pub wheel_pressed: bool,
-    pub x: i16,
-    pub y: i16,
+    /// The last known cursor position in client coordinates.
+    pub pos: ClientPoint,
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -38,192 +38,239 @@ pub enum MouseState {
     Leave,
 }
 
+impl Default for Mouse {
+    fn default() -> Self {
+        return Mouse::new();
+    }
+}
+
 impl Mouse {
+    pub fn new() -> Mouse {
+        return Mouse {
+            event_queue: vec![],
+            is_in_window: false,
+            wheel_delta_carry: 0,
+            left_pressed: false,
+            right_pressed: false,
+            wheel_pressed: false,
+            pos: ClientPoint { x: 0, y: 0 },
+        };
+    }
+
     pub fn reset(&mut self) {
         self.event_queue = vec![];
     }
 
-    pub fn on_wheel_delta(&mut self, x: i16, y: i16, delta: i16) {
+    /// `pos` has to be in client coordinates, WM_MOUSEWHEEL gives screen coordinates.
+    pub fn on_wheel_delta(&mut self, pos: ClientPoint, delta: i16) {
         self.wheel_delta_carry += delta;
 
         while self.wheel_delta_carry >= WHEEL_DELTA {
             self.wheel_delta_carry -= WHEEL_DELTA;
-            self.on_wheel_up(x, y);
+            self.on_wheel_up(pos);
         }
 
         while self.wheel_delta_carry <= -WHEEL_DELTA {
             self.wheel_delta_carry += WHEEL_DELTA;
-            self.on_wheel_down(x, y);
+            self.on_wheel_down(pos);
         }
     }
 
-    fn on_wheel_up(&mut self, x: i16, y: i16) {
-        self.event_queue.push(MouseEvent {
+    fn on_wheel_up(&mut self, pos: ClientPoint) -> MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::WheelUp,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x,
-            y,
+            pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    fn on_wheel_down(&mut self, x: i16, y: i16) {
-        self.event_queue.push(MouseEvent {
+    fn on_wheel_down(&mut self, pos: ClientPoint) -> MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::WHeelDown,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x,
-            y,
+            pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_left_press(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_left_press(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.left_pressed = true;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::LPress,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_right_press(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_right_press(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.right_pressed = true;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::RPress,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_left_release(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_left_release(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.left_pressed = false;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::LRelease,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_wheel_press(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_wheel_press(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.wheel_pressed = true;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::WheelPress,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_wheel_release(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_wheel_release(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.wheel_pressed = false;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::WHeelRelease,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_right_release(&mut self) {
+    /// `pos` is in client coordinates, like the button messages give it. Returns the event that was added to the queue.
+    pub fn on_right_release(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
         self.right_pressed = false;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::RRelease,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_mouse_move(&mut self, points: POINTS) {
-        self.x = points.x;
-        self.y = points.y;
+    /// Returns the event that was added to the queue.
+    pub fn on_mouse_move(&mut self, pos: ClientPoint) -> MouseEvent {
+        self.pos = pos;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::Move,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_mouse_leave(&mut self) {
+    /// Returns the event that was added to the queue.
+    pub fn on_mouse_leave(&mut self) -> MouseEvent {
         self.is_in_window = false;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::Leave,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn on_mouse_enter(&mut self) {
+    /// Returns the event that was added to the queue.
+    pub fn on_mouse_enter(&mut self) -> MouseEvent {
         self.is_in_window = true;
 
-        self.event_queue.push(MouseEvent {
+        let event: MouseEvent = MouseEvent {
             mouse_state: MouseState::Enter,
             left_pressed: self.left_pressed,
             right_pressed: self.right_pressed,
-            x: self.x,
-            y: self.y,
+            pos: self.pos,
             wheel_pressed: self.wheel_pressed,
-        });
+        };
 
+        self.event_queue.push(event);
         trim_buffer(&mut self.event_queue);
+        return event;
     }
 
-    pub fn get_pos(&self) -> POINTS {
-        POINTS {
-            x: self.x,
-            y: self.y,
-        }
+    /// Get the cursor position in client coordinates.
+    pub fn get_pos(&self) -> ClientPoint {
+        self.pos
+    }
+
+    /// Get the cursor position in logical pixels. See [ClientPoint::to_logical()].
+    pub fn get_logical_pos(&self, scale_factor: f64) -> LogicalPoint {
+        self.pos.to_logical(scale_factor)
     }
 
     pub fn read(&mut self) -> Option<MouseEvent> {
@@ -241,3 +288,48 @@ fn trim_buffer<T>(buffer: &mut Vec<T>) {
         buffer.remove(0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_take_the_position_of_the_message() {
+        let mut mouse: Mouse = Mouse {
+            event_queue: vec![],
+            is_in_window: true,
+            wheel_delta_carry: 0,
+            left_pressed: false,
+            right_pressed: false,
+            wheel_pressed: false,
+            pos: ClientPoint::default(),
+        };
+        let buttons: [MouseState; 6] = [
+            MouseState::LPress,
+            MouseState::LRelease,
+            MouseState::RPress,
+            MouseState::RRelease,
+            MouseState::WheelPress,
+            MouseState::WHeelRelease,
+        ];
+        for (index, mouse_state) in buttons.into_iter().enumerate() {
+            // No mouse move in between, like a click on a window that was just shown
+            let pos: ClientPoint = ClientPoint {
+                x: index as i32 * 10,
+                y: 5,
+            };
+            let event: MouseEvent = match mouse_state {
+                MouseState::LPress => mouse.on_left_press(pos),
+                MouseState::LRelease => mouse.on_left_release(pos),
+                MouseState::RPress => mouse.on_right_press(pos),
+                MouseState::RRelease => mouse.on_right_release(pos),
+                MouseState::WheelPress => mouse.on_wheel_press(pos),
+                _ => mouse.on_wheel_release(pos),
+            };
+            assert_eq!(mouse.get_pos(), pos);
+            assert_eq!(event.mouse_state, mouse_state);
+            assert_eq!(event.pos, pos);
+            assert_eq!(mouse.read(), Some(event));
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    monitor::{self, Monitor, Placement},
+    rect::Rect,
+};
+
+/// The version of the file format, a file with another version is ignored.
+const FORMAT_VERSION: u32 = 1;
+/// How much of a restored window has to be on a work area, in physical pixels. Less than that and the window is
+/// moved back onto a monitor, so there is always enough of the title bar to grab.
+pub const MIN_VISIBLE: i32 = 64;
+
+/// The monitor a window was on, by the name and bounds it had. HMONITOR's don't survive a restart.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MonitorIdentity {
+    pub name: String,
+    pub bounds: Rect,
+}
+
+/// What a [PlacementStore] remembers of a window.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SavedPlacement {
+    /// The outer rect in screen coordinates. For a maximized window the rect it is restored to.
+    pub rect: Rect,
+    pub maximized: bool,
+    pub monitor: Option<MonitorIdentity>,
+}
+
+/**
+    Saves where a window was when it closed and puts it back there on the next launch. Opt in with
+    [WindowBuilder::placement_store()](super::builder::WindowBuilder::placement_store()):
+    ```ignore
+    let window: Window = WindowBuilder::new("Editor")
+        .inner_size(LogicalSize::new(800.0, 600.0))
+        .placement_store(PlacementStore::in_config_dir("Editor"))
+        .build()?;
+    ```
+    The placement is saved by [Window::close()](super::Window::close()). The size and position of the builder are
+    only used on the first launch, or when the file is missing or can't be read.
+
+    The file is plain text with one `key=value` per line, so it can be edited or deleted by hand.
+*/
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlacementStore {
+    pub path: PathBuf,
+}
+
+impl PlacementStore {
+    pub fn new(path: impl Into<PathBuf>) -> PlacementStore {
+        return PlacementStore { path: path.into() };
+    }
+
+    /**
+        A store in the config directory of the user: `%APPDATA%\<app_name>\placement.cfg` on windows and
+        `$XDG_CONFIG_HOME/<app_name>/placement.cfg` (or `~/.config`) everywhere else. Falls back to the working
+        directory when there is no config directory.
+    */
+    pub fn in_config_dir(app_name: &str) -> PlacementStore {
+        let base: PathBuf = config_dir().unwrap_or_default();
+        return PlacementStore::new(base.join(app_name).join("placement.cfg"));
+    }
+
+    /// The saved placement, or [None] when there is no file yet or it can't be read.
+    pub fn load(&self) -> Option<SavedPlacement> {
+        let text: String = std::fs::read_to_string(&self.path).ok()?;
+        return SavedPlacement::from_text(&text);
+    }
+
+    /// Write the placement, creating the directory of the file when it doesn't exist.
+    pub fn save(&self, placement: &SavedPlacement) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            if !dir.as_os_str().is_empty() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        return std::fs::write(&self.path, placement.to_text());
+    }
+
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+}
+
+impl SavedPlacement {
+    /// Remember `placement`, on the monitor that has the largest part of it.
+    pub fn new(placement: &Placement, monitors: &[Monitor]) -> SavedPlacement {
+        let monitor: Option<MonitorIdentity> = monitor::monitor_from_rect(monitors, placement.rect)
+            .map(|monitor| MonitorIdentity {
+                name: monitor.name.clone(),
+                bounds: monitor.bounds,
+            });
+        return SavedPlacement {
+            rect: placement.rect,
+            maximized: placement.maximized,
+            monitor,
+        };
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text: String = format!(
+            "version={}\nrect={}\nmaximized={}\n",
+            FORMAT_VERSION,
+            rect_to_text(self.rect),
+            self.maximized
+        );
+        if let Some(monitor) = &self.monitor {
+            // A name can't contain a line break, but don't let a weird driver break the file
+            let name: String = monitor.name.replace(['\r', '\n'], " ");
+            text.push_str(&format!(
+                "monitor={}\nmonitor_bounds={}\n",
+                name,
+                rect_to_text(monitor.bounds)
+            ));
+        }
+        return text;
+    }
+
+    /// Read the text of [SavedPlacement::to_text()]. Unknown keys are skipped, [None] when a required key is
+    /// missing or invalid.
+    pub fn from_text(text: &str) -> Option<SavedPlacement> {
+        let mut version: Option<u32> = None;
+        let mut rect: Option<Rect> = None;
+        let mut maximized: bool = false;
+        let mut monitor_name: Option<String> = None;
+        let mut monitor_bounds: Option<Rect> = None;
+
+        for line in text.lines() {
+            let line: &str = line.trim_end_matches('\r');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value): (&str, &str) = line.split_once('=')?;
+            match key.trim() {
+                "version" => version = value.trim().parse().ok(),
+                "rect" => rect = Some(rect_from_text(value)?),
+                "maximized" => maximized = value.trim().parse().ok()?,
+                "monitor" => monitor_name = Some(value.to_string()),
+                "monitor_bounds" => monitor_bounds = Some(rect_from_text(value)?),
+                _ => {}
+            }
+        }
+
+        if version != Some(FORMAT_VERSION) {
+            return None;
+        }
+        let rect: Rect = rect?;
+        if rect.width() <= 0 || rect.height() <= 0 {
+            return None;
+        }
+        let monitor: Option<MonitorIdentity> = match (monitor_name, monitor_bounds) {
+            (Some(name), Some(bounds)) => Some(MonitorIdentity { name, bounds }),
+            _ => None,
+        };
+        return Some(SavedPlacement {
+            rect,
+            maximized,
+            monitor,
+        });
+    }
+
+    /**
+        The rect to restore on the current monitors. When the monitor of the window is still there but moved in
+        the layout, the rect moves along with it. A rect that ended up (mostly) off-screen, like after the monitor
+        was disconnected, is pushed back into the work area of the nearest monitor.
+    */
+    pub fn restore_rect(&self, monitors: &[Monitor]) -> Rect {
+        let mut rect: Rect = self.rect;
+        if let Some(saved) = &self.monitor {
+            // The same monitor at the same place, or else the same monitor somewhere else in the layout
+            let same: Option<&Monitor> = monitors
+                .iter()
+                .find(|monitor| monitor.name == saved.name && monitor.bounds == saved.bounds)
+                .or_else(|| monitors.iter().find(|monitor| monitor.name == saved.name));
+            if let Some(monitor) = same {
+                let (dx, dy): (i32, i32) = (
+                    monitor.bounds.left - saved.bounds.left,
+                    monitor.bounds.top - saved.bounds.top,
+                );
+                rect = Rect {
+                    left: rect.left + dx,
+                    top: rect.top + dy,
+                    right: rect.right + dx,
+                    bottom: rect.bottom + dy,
+                };
+            }
+        }
+
+        if is_visible(rect, monitors) {
+            return rect;
+        }
+        return match monitor::monitor_from_rect(monitors, rect) {
+            Some(monitor) => monitor::clamp_to_area(rect, monitor.work_area),
+            None => rect,
+        };
+    }
+}
+
+/// True when at least [MIN_VISIBLE] x [MIN_VISIBLE] pixels of `rect` are on the work area of a monitor.
+pub fn is_visible(rect: Rect, monitors: &[Monitor]) -> bool {
+    let min_width: i32 = MIN_VISIBLE.min(rect.width());
+    let min_height: i32 = MIN_VISIBLE.min(rect.height());
+    return monitors.iter().any(|monitor| {
+        let area: Rect = monitor.work_area;
+        let width: i32 = rect.right.min(area.right) - rect.left.max(area.left);
+        let height: i32 = rect.bottom.min(area.bottom) - rect.top.max(area.top);
+        return width >= min_width && height >= min_height;
+    });
+}
+
+fn rect_to_text(rect: Rect) -> String {
+    return format!("{},{},{},{}", rect.left, rect.top, rect.right, rect.bottom);
+}
+
+fn rect_from_text(text: &str) -> Option<Rect> {
+    let values: Vec<i32> = text
+        .split(',')
+        .map(|value| value.trim().parse().ok())
+        .collect::<Option<Vec<i32>>>()?;
+    return match values[..] {
+        // A width or height that doesn't fit in an i32 would overflow in Rect::width() and Rect::height()
+        [left, top, right, bottom]
+            if right.checked_sub(left).is_none() || bottom.checked_sub(top).is_none() =>
+        {
+            None
+        }
+        [left, top, right, bottom] => Some(Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }),
+        _ => None,
+    };
+}
+
+fn config_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        return std::env::var_os("APPDATA").map(PathBuf::from);
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir));
+    }
+    return std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::backend::headless::fake_monitor;
+
+    fn saved() -> SavedPlacement {
+        return SavedPlacement {
+            rect: Rect {
+                left: 2200,
+                top: 100,
+                right: 3000,
+                bottom: 600,
+            },
+            maximized: true,
+            monitor: Some(MonitorIdentity {
+                name: "Headless 2".to_string(),
+                bounds: Rect {
+                    left: 1920,
+                    top: 0,
+                    right: 3200,
+                    bottom: 720,
+                },
+            }),
+        };
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let placement: SavedPlacement = saved();
+        assert_eq!(
+            SavedPlacement::from_text(&placement.to_text()),
+            Some(placement)
+        );
+        assert_eq!(
+            SavedPlacement::from_text("version=2\nrect=0,0,10,10\n"),
+            None
+        );
+        assert_eq!(
+            SavedPlacement::from_text("version=1\nrect=0,0,0,10\n"),
+            None
+        );
+        assert_eq!(SavedPlacement::from_text("version=1\nrect=0,0,10\n"), None);
+        // The width and height don't fit in an i32
+        assert_eq!(
+            SavedPlacement::from_text("version=1\nrect=-2147483648,0,2147483647,10\n"),
+            None
+        );
+        assert_eq!(
+            SavedPlacement::from_text(
+                "version=1\nrect=0,0,10,10\nmonitor_bounds=0,2147483647,10,-2\n"
+            ),
+            None
+        );
+        assert!(
+            SavedPlacement::from_text("# comment\nversion=1\nrect=0,0,10,10\nextra=1\n").is_some()
+        );
+    }
+
+    #[test]
+    fn restore_follows_a_moved_monitor() {
+        // The second monitor moved from the right to the left of the primary one
+        let monitors: Vec<Monitor> = vec![
+            fake_monitor(1, 0, 0, 1920, 1080, 96),
+            fake_monitor(2, -1280, 0, 1280, 720, 96),
+        ];
+        let rect: Rect = saved().restore_rect(&monitors);
+        assert_eq!(
+            rect,
+            Rect {
+                left: -1000,
+                top: 100,
+                right: -200,
+                bottom: 600
+            }
+        );
+        assert!(is_visible(rect, &monitors));
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::Sender,
+    Arc,
+};
+
+use super::error::EventLoopClosed;
+
+/**
+    Wakes the thread of a window when it is blocked in [Window::handle_messages()](super::Window::handle_messages()),
+    see [Backend::create_waker()](super::backend::Backend::create_waker()). Returns false when the window can't be
+    woken anymore.
+*/
+pub type Waker = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/**
+    Sends user events to a window from any thread. The payloads arrive as [Event::User](super::event::Event::User) in
+    the order they were sent, after the other events of the frame:
+    ```ignore
+    let mut window: Window<Loaded> = WindowBuilder::new("Viewer").build_for::<Loaded>()?;
+    let proxy: EventLoopProxy<Loaded> = window.create_proxy();
+    std::thread::spawn(move || {
+        let image: Loaded = load_image("big.png");
+        proxy.send_event(image).ok();
+    });
+    ```
+    Sending wakes a loop that is blocked in [ControlFlow::Wait](super::control_flow::ControlFlow::Wait).
+*/
+pub struct EventLoopProxy<T> {
+    sender: Sender<T>,
+    waker: Waker,
+    /// Set by the UI thread when the window is destroyed.
+    closed: Arc<AtomicBool>,
+}
+
+impl<T> EventLoopProxy<T> {
+    pub fn new(sender: Sender<T>, waker: Waker, closed: Arc<AtomicBool>) -> EventLoopProxy<T> {
+        return EventLoopProxy {
+            sender,
+            waker,
+            closed,
+        };
+    }
+
+    /// Send a payload to the window. Fails with the payload when the window is closed or dropped.
+    pub fn send_event(&self, event: T) -> Result<(), EventLoopClosed<T>> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(EventLoopClosed(event));
+        }
+        if let Err(error) = self.sender.send(event) {
+            return Err(EventLoopClosed(error.0));
+        }
+        // A lost wake only delays the payload until the next frame, it is already in the channel
+        (self.waker)();
+        return Ok(());
+    }
+
+    pub fn is_closed(&self) -> bool {
+        return self.closed.load(Ordering::Acquire);
+    }
+}
+
+// A derive would require T: Clone, but only the sender is cloned
+impl<T> Clone for EventLoopProxy<T> {
+    fn clone(&self) -> Self {
+        return EventLoopProxy {
+            sender: self.sender.clone(),
+            waker: self.waker.clone(),
+            closed: self.closed.clone(),
+        };
+    }
+}
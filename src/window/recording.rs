@@ -0,0 +1,1573 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use super::{
+    chrome::CaptionButton,
+    coords::ClientPoint,
+    drag_drop::DragDropEvent,
+    error::RecordingError,
+    event::{Event, WindowEvent},
+    gesture::{GestureEvent, GestureKind, GesturePhase, SwipeDirection},
+    keyboard::{KeyEvent, KeyState, KEY_CODE_COUNT},
+    menu::MenuId,
+    mouse::{MouseEvent, MouseState},
+};
+
+/// The version of the file format. Files with a newer version are refused by [Recording::from_bytes()].
+pub const FORMAT_VERSION: u16 = 1;
+/// The first bytes of a binary recording.
+const MAGIC: [u8; 4] = *b"WREC";
+/// The `format` field of the first line of a JSON-lines recording.
+const JSON_FORMAT: &str = "win32-input-recording";
+/// The fastest [Replayer::set_speed()].
+pub const MAX_REPLAY_SPEED: f64 = 1000.0;
+
+/// The events one call of [Window::handle_messages()](super::Window::handle_messages()) produced.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RecordedFrame {
+    /// The frame number, counting from 0 when the recording started.
+    pub frame: u64,
+    /// The time since the recording started, in microseconds precision.
+    pub time: Duration,
+    pub events: Vec<Event>,
+}
+
+/**
+    A list of recorded frames. Frames without events are kept as well, they hold the frame times which a replay
+    needs to be deterministic.
+
+    There are two file formats:
+    - The binary format: `WREC`, the version as u16 little endian and the number of frames. Every frame is the
+      frame number and time (in microseconds) relative to the previous frame, the number of events and the events.
+      Integers are LEB128 varints, signed integers are zigzag encoded and floats are f32 little endian. Paths are
+      the length and the UTF-8 bytes.
+    - The JSON-lines format for reading and editing by hand. The first line is
+      `{"format":"win32-input-recording","version":1}`, every line after that is one event with its `frame` and
+      `time_us`. A frame without events is a line without an `event` field.
+*/
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// The number of events in all the frames.
+    pub fn event_count(&self) -> usize {
+        return self.frames.iter().map(|frame| frame.events.len()).sum();
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_varint(&mut bytes, self.frames.len() as u64);
+
+        let (mut last_frame, mut last_time): (u64, u64) = (0, 0);
+        for frame in self.frames.iter() {
+            let time: u64 = frame.time.as_micros() as u64;
+            write_varint(&mut bytes, frame.frame.saturating_sub(last_frame));
+            write_varint(&mut bytes, time.saturating_sub(last_time));
+            write_varint(&mut bytes, frame.events.len() as u64);
+            for event in frame.events.iter() {
+                write_event(&mut bytes, event);
+            }
+            (last_frame, last_time) = (frame.frame, time);
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Recording, RecordingError> {
+        let mut reader: Reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(RecordingError::BadMagic);
+        }
+        let version: u16 = u16::from_le_bytes([reader.byte()?, reader.byte()?]);
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+
+        let frame_count: u64 = reader.varint()?;
+        let mut frames: Vec<RecordedFrame> = vec![];
+        let (mut last_frame, mut last_time): (u64, u64) = (0, 0);
+        for _ in 0..frame_count {
+            last_frame = last_frame
+                .checked_add(reader.varint()?)
+                .ok_or(RecordingError::InvalidValue("frame number"))?;
+            last_time = last_time
+                .checked_add(reader.varint()?)
+                .ok_or(RecordingError::InvalidValue("frame time"))?;
+            let event_count: u64 = reader.varint()?;
+            let mut events: Vec<Event> = vec![];
+            for _ in 0..event_count {
+                events.push(read_event(&mut reader)?);
+            }
+            frames.push(RecordedFrame {
+                frame: last_frame,
+                time: Duration::from_micros(last_time),
+                events,
+            });
+        }
+        return Ok(Recording { frames });
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        let mut text: String = format!(
+            "{{\"format\":\"{}\",\"version\":{}}}\n",
+            JSON_FORMAT, FORMAT_VERSION
+        );
+        for frame in self.frames.iter() {
+            let prefix: String = format!(
+                "{{\"frame\":{},\"time_us\":{}",
+                frame.frame,
+                frame.time.as_micros()
+            );
+            if frame.events.is_empty() {
+                text.push_str(&prefix);
+                text.push_str("}\n");
+            }
+            for event in frame.events.iter() {
+                text.push_str(&prefix);
+                text.push_str(&event_to_json(event));
+                text.push_str("}\n");
+            }
+        }
+        return text;
+    }
+
+    pub fn from_json_lines(text: &str) -> Result<Recording, RecordingError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+
+        let (_, header) = lines.next().ok_or(RecordingError::UnexpectedEnd)?;
+        let header: JsonObject = parse_json_object(header).map_err(|reason| {
+            return RecordingError::InvalidLine { line: 1, reason };
+        })?;
+        if header.string("format").ok() != Some(JSON_FORMAT) {
+            return Err(RecordingError::BadMagic);
+        }
+        let version: u16 = header
+            .number("version")
+            .map_err(|reason| RecordingError::InvalidLine { line: 1, reason })?
+            as u16;
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+
+        let mut frames: Vec<RecordedFrame> = vec![];
+        for (index, line) in lines {
+            let invalid = |reason: String| RecordingError::InvalidLine {
+                line: index + 1,
+                reason,
+            };
+            let object: JsonObject = parse_json_object(line).map_err(invalid)?;
+            let frame: u64 = object.number("frame").map_err(invalid)? as u64;
+            let time: Duration =
+                Duration::from_micros(object.number("time_us").map_err(invalid)? as u64);
+
+            // The lines of one frame are next to each other
+            if frames.last().map(|last| last.frame) != Some(frame) {
+                frames.push(RecordedFrame {
+                    frame,
+                    time,
+                    events: vec![],
+                });
+            }
+            if object.get("event").is_some() {
+                let event: Event = event_from_json(&object).map_err(invalid)?;
+                frames.last_mut().unwrap().events.push(event);
+            }
+        }
+        return Ok(Recording { frames });
+    }
+
+    /// Write the binary format to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        return std::fs::write(path, self.to_bytes()).map_err(RecordingError::from);
+    }
+
+    /// Write the JSON-lines format to a file.
+    pub fn save_json_lines(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        return std::fs::write(path, self.to_json_lines()).map_err(RecordingError::from);
+    }
+
+    /// Read a file in either format. The format is detected from the first bytes, not from the extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Recording, RecordingError> {
+        let bytes: Vec<u8> = std::fs::read(path)?;
+        if bytes.starts_with(&MAGIC) {
+            return Recording::from_bytes(&bytes);
+        }
+        let text: &str = std::str::from_utf8(&bytes).map_err(|_| RecordingError::BadMagic)?;
+        return Recording::from_json_lines(text);
+    }
+}
+
+/**
+    Collects the events of a window while it records, see [Window::start_recording()](super::Window::start_recording()).
+    Every event that reaches the event stream is recorded, except:
+    - [Event::User], the payload can't be saved.
+    - [Event::Timer], the timers fire again during the replay. Drive them with the replay time, see
+      [Timers::set_clock()](super::timer::Timers::set_clock()).
+    - [WindowEvent::Destroyed], a replay can't destroy a window. The app has to close it on
+      [WindowEvent::CloseRequested] just like it did while recording.
+*/
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    start: Instant,
+    frames: Vec<RecordedFrame>,
+}
+
+impl InputRecorder {
+    /// Start recording at `start`, which is frame 0.
+    pub fn new(start: Instant) -> InputRecorder {
+        return InputRecorder {
+            start,
+            frames: vec![RecordedFrame {
+                frame: 0,
+                time: Duration::ZERO,
+                events: vec![],
+            }],
+        };
+    }
+
+    /// Start the next frame. The events recorded after this belong to it.
+    pub fn begin_frame(&mut self, now: Instant) {
+        let last: &RecordedFrame = self.frames.last().unwrap();
+        // Cut the time to whole microseconds here so a saved recording replays exactly the same
+        let elapsed: Duration = now.saturating_duration_since(self.start);
+        let time: Duration = Duration::from_micros(elapsed.as_micros() as u64).max(last.time);
+        let frame: u64 = last.frame + 1;
+        self.frames.push(RecordedFrame {
+            frame,
+            time,
+            events: vec![],
+        });
+    }
+
+    pub fn record(&mut self, event: &Event) {
+        if let Event::User(_) | Event::Timer(_) | Event::Window(WindowEvent::Destroyed) = event {
+            return;
+        }
+        self.frames.last_mut().unwrap().events.push(event.clone());
+    }
+
+    /// The number of the frame that is being recorded.
+    pub fn frame(&self) -> u64 {
+        return self.frames.last().unwrap().frame;
+    }
+
+    pub fn finish(self) -> Recording {
+        return Recording {
+            frames: self.frames,
+        };
+    }
+}
+
+/**
+    Plays a [Recording] back one frame at a time, see [Window::replay_frame()](super::Window::replay_frame()).
+
+    A frame is due when the replay clock reaches its recorded time. The replay clock runs at [Replayer::speed()]
+    times the real time and stops while paused, [Replayer::step()] plays the next frame while paused. At most one
+    frame is played per call, so the app sees the exact same frames as while recording, even when it runs slower.
+
+    For a deterministic replay the app has to use [Replayer::time()] as its clock instead of [Instant::now()]:
+    ```ignore
+    let mut replayer: Replayer = Replayer::new(Recording::load("bug.wrec")?);
+    while !replayer.is_finished() {
+        app.window.replay_frame(&mut replayer);
+        let now: Duration = replayer.time();
+        app.update(now - last);
+        last = now;
+    }
+    ```
+*/
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    recording: Recording,
+    next: usize,
+    paused: bool,
+    step_requested: bool,
+    speed: f64,
+    clock: Duration,
+    last_advance: Option<Instant>,
+}
+
+impl Replayer {
+    pub fn new(recording: Recording) -> Replayer {
+        return Replayer {
+            recording,
+            next: 0,
+            paused: false,
+            step_requested: false,
+            speed: 1.0,
+            clock: Duration::ZERO,
+            last_advance: None,
+        };
+    }
+
+    pub fn recording(&self) -> &Recording {
+        return &self.recording;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.step_requested = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        return self.paused;
+    }
+
+    /// Play the next frame on the next [Replayer::advance()], even though the replay is paused.
+    pub fn step(&mut self) {
+        self.step_requested = true;
+    }
+
+    /// 2.0 plays twice as fast, 0.5 half as fast. Clamped to 0.0 up to [MAX_REPLAY_SPEED], NaN is treated as 0.0.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = match speed.is_nan() {
+            true => 0.0,
+            false => speed.clamp(0.0, MAX_REPLAY_SPEED),
+        };
+    }
+
+    pub fn speed(&self) -> f64 {
+        return self.speed;
+    }
+
+    /// The recorded time of the last frame that was played. Use this as the app clock during a replay.
+    pub fn time(&self) -> Duration {
+        return match self.next {
+            0 => Duration::ZERO,
+            next => self.recording.frames[next - 1].time,
+        };
+    }
+
+    /// The number of the last frame that was played.
+    pub fn frame(&self) -> Option<u64> {
+        return self
+            .next
+            .checked_sub(1)
+            .map(|index| self.recording.frames[index].frame);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.next >= self.recording.frames.len();
+    }
+
+    /// Start again from the first frame. Paused and the speed stay the same.
+    pub fn restart(&mut self) {
+        self.next = 0;
+        self.clock = Duration::ZERO;
+        self.last_advance = None;
+        self.step_requested = false;
+    }
+
+    /// Returns the next frame when it is due at `now`, see [Replayer].
+    pub fn advance(&mut self, now: Instant) -> Option<&RecordedFrame> {
+        if let Some(last_advance) = self.last_advance {
+            if !self.paused {
+                self.clock += now
+                    .saturating_duration_since(last_advance)
+                    .mul_f64(self.speed);
+            }
+        }
+        self.last_advance = Some(now);
+
+        let frame: &RecordedFrame = self.recording.frames.get(self.next)?;
+        let due: bool = if self.paused {
+            std::mem::take(&mut self.step_requested)
+        } else {
+            frame.time <= self.clock
+        };
+        if !due {
+            return None;
+        }
+        // After a step the clock continues from the stepped frame
+        self.clock = self.clock.max(frame.time);
+        self.next += 1;
+        return Some(frame);
+    }
+}
+
+// Binary encoding
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], RecordingError> {
+        let end: usize = self.pos + count;
+        let bytes: &[u8] = self
+            .bytes
+            .get(self.pos..end)
+            .ok_or(RecordingError::UnexpectedEnd)?;
+        self.pos = end;
+        return Ok(bytes);
+    }
+
+    fn byte(&mut self) -> Result<u8, RecordingError> {
+        return Ok(self.take(1)?[0]);
+    }
+
+    fn varint(&mut self) -> Result<u64, RecordingError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte: u8 = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        return Err(RecordingError::InvalidValue("varint"));
+    }
+
+    fn signed(&mut self) -> Result<i32, RecordingError> {
+        let value: u64 = self.varint()?;
+        return Ok(((value >> 1) as i64 ^ -((value & 1) as i64)) as i32);
+    }
+
+    fn float(&mut self) -> Result<f32, RecordingError> {
+        let bytes: &[u8] = self.take(4)?;
+        return Ok(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+    }
+
+    fn double(&mut self) -> Result<f64, RecordingError> {
+        let mut bytes: [u8; 8] = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        return Ok(f64::from_le_bytes(bytes));
+    }
+
+    fn path(&mut self) -> Result<PathBuf, RecordingError> {
+        let length: u64 = self.varint()?;
+        if length > (self.bytes.len() - self.pos) as u64 {
+            return Err(RecordingError::UnexpectedEnd);
+        }
+        let text: &str = std::str::from_utf8(self.take(length as usize)?)
+            .map_err(|_| RecordingError::InvalidValue("path"))?;
+        return Ok(PathBuf::from(text));
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte: u8 = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_signed(bytes: &mut Vec<u8>, value: i32) {
+    let value: i64 = value as i64;
+    write_varint(bytes, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn write_path(bytes: &mut Vec<u8>, path: &Path) {
+    let text: String = path.to_string_lossy().into_owned();
+    write_varint(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+fn write_event(bytes: &mut Vec<u8>, event: &Event) {
+    match event {
+        Event::Key(key) => {
+            bytes.push(0);
+            bytes.push(key_state_index(key.key_state));
+            write_varint(bytes, key.key_code as u64);
+        }
+        Event::Char(char) => {
+            bytes.push(1);
+            write_varint(bytes, *char as u64);
+        }
+        Event::Mouse(mouse) => {
+            bytes.push(2);
+            bytes.push(mouse_state_index(mouse.mouse_state));
+            bytes.push(
+                mouse.left_pressed as u8
+                    | (mouse.right_pressed as u8) << 1
+                    | (mouse.wheel_pressed as u8) << 2,
+            );
+            write_signed(bytes, mouse.pos.x);
+            write_signed(bytes, mouse.pos.y);
+        }
+        Event::Wheel { pos, delta } => {
+            bytes.push(3);
+            write_signed(bytes, pos.x);
+            write_signed(bytes, pos.y);
+            write_signed(bytes, *delta as i32);
+        }
+        Event::Gesture(gesture) => {
+            bytes.push(4);
+            bytes.push(gesture_kind_index(gesture.kind));
+            bytes.push(gesture_phase_index(gesture.phase));
+            for value in [
+                gesture.x,
+                gesture.y,
+                gesture.scale,
+                gesture.rotation,
+                gesture.translation_x,
+                gesture.translation_y,
+            ] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            write_varint(bytes, gesture.fingers as u64);
+        }
+        Event::Window(window_event) => {
+            bytes.push(5);
+            bytes.push(window_event_index(window_event));
+            match window_event {
+                WindowEvent::Moved { x, y } => {
+                    write_signed(bytes, *x);
+                    write_signed(bytes, *y);
+                }
+                WindowEvent::Resized { width, height } => {
+                    write_signed(bytes, *width);
+                    write_signed(bytes, *height);
+                }
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    width,
+                    height,
+                } => {
+                    bytes.extend_from_slice(&scale_factor.to_le_bytes());
+                    write_signed(bytes, *width);
+                    write_signed(bytes, *height);
+                }
+                WindowEvent::CaptionButtonsChanged { hovered, pressed } => {
+                    bytes.push(caption_button_index(*hovered));
+                    bytes.push(caption_button_index(*pressed));
+                }
+                _ => {}
+            }
+        }
+        Event::DragDrop(drag_drop) => {
+            bytes.push(6);
+            bytes.push(drag_drop_index(drag_drop));
+            match drag_drop {
+                DragDropEvent::HoveredFile(path) => write_path(bytes, path),
+                DragDropEvent::HoveredFileCancelled => {}
+                DragDropEvent::DroppedFiles { paths, position } => {
+                    write_varint(bytes, paths.len() as u64);
+                    for path in paths {
+                        write_path(bytes, path);
+                    }
+                    write_signed(bytes, position.x);
+                    write_signed(bytes, position.y);
+                }
+            }
+        }
+        Event::MenuActivated(id) => {
+            bytes.push(7);
+            write_varint(bytes, id.0 as u64);
+        }
+        // The recorder never records these
+        Event::Timer(_) | Event::User(_) => {}
+    }
+}
+
+fn read_event(reader: &mut Reader) -> Result<Event, RecordingError> {
+    return match reader.byte()? {
+        0 => {
+            let key_state: KeyState = *KEY_STATES
+                .get(reader.byte()? as usize)
+                .ok_or(RecordingError::InvalidValue("key state"))?;
+            // The keyboard keeps a state per key code, a larger code would index out of bounds
+            let key_code: u64 = reader.varint()?;
+            if key_code >= KEY_CODE_COUNT as u64 {
+                return Err(RecordingError::InvalidValue("key code"));
+            }
+            Ok(Event::Key(KeyEvent {
+                key_state,
+                key_code: key_code as u32,
+            }))
+        }
+        1 => {
+            let char: char = char::from_u32(reader.varint()? as u32)
+                .ok_or(RecordingError::InvalidValue("char"))?;
+            Ok(Event::Char(char))
+        }
+        2 => {
+            let mouse_state: MouseState = *MOUSE_STATES
+                .get(reader.byte()? as usize)
+                .ok_or(RecordingError::InvalidValue("mouse state"))?;
+            let buttons: u8 = reader.byte()?;
+            Ok(Event::Mouse(MouseEvent {
+                mouse_state,
+                left_pressed: buttons & 1 != 0,
+                right_pressed: buttons & 2 != 0,
+                wheel_pressed: buttons & 4 != 0,
+                pos: ClientPoint {
+                    x: reader.signed()?,
+                    y: reader.signed()?,
+                },
+            }))
+        }
+        3 => {
+            let pos: ClientPoint = ClientPoint {
+                x: reader.signed()?,
+                y: reader.signed()?,
+            };
+            Ok(Event::Wheel {
+                pos,
+                delta: reader.signed()? as i16,
+            })
+        }
+        4 => {
+            let kind: GestureKind = *GESTURE_KINDS
+                .get(reader.byte()? as usize)
+                .ok_or(RecordingError::InvalidValue("gesture kind"))?;
+            let phase: GesturePhase = *GESTURE_PHASES
+                .get(reader.byte()? as usize)
+                .ok_or(RecordingError::InvalidValue("gesture phase"))?;
+            Ok(Event::Gesture(GestureEvent {
+                kind,
+                phase,
+                x: reader.float()?,
+                y: reader.float()?,
+                scale: reader.float()?,
+                rotation: reader.float()?,
+                translation_x: reader.float()?,
+                translation_y: reader.float()?,
+                fingers: reader.varint()? as usize,
+            }))
+        }
+        5 => {
+            let index: u8 = reader.byte()?;
+            let window_event: WindowEvent = match index {
+                2 => WindowEvent::Moved {
+                    x: reader.signed()?,
+                    y: reader.signed()?,
+                },
+                3 => WindowEvent::Resized {
+                    width: reader.signed()?,
+                    height: reader.signed()?,
+                },
+                12 => WindowEvent::ScaleFactorChanged {
+                    scale_factor: reader.double()?,
+                    width: reader.signed()?,
+                    height: reader.signed()?,
+                },
+                14 => WindowEvent::CaptionButtonsChanged {
+                    hovered: caption_button_from_index(reader.byte()? as usize)?,
+                    pressed: caption_button_from_index(reader.byte()? as usize)?,
+                },
+                _ => *WINDOW_EVENTS
+                    .get(index as usize)
+                    .ok_or(RecordingError::InvalidValue("window event"))?,
+            };
+            Ok(Event::Window(window_event))
+        }
+        6 => {
+            let drag_drop: DragDropEvent = match reader.byte()? {
+                0 => DragDropEvent::HoveredFile(reader.path()?),
+                1 => DragDropEvent::HoveredFileCancelled,
+                2 => {
+                    let count: u64 = reader.varint()?;
+                    let mut paths: Vec<PathBuf> = vec![];
+                    for _ in 0..count {
+                        paths.push(reader.path()?);
+                    }
+                    DragDropEvent::DroppedFiles {
+                        paths,
+                        position: ClientPoint {
+                            x: reader.signed()?,
+                            y: reader.signed()?,
+                        },
+                    }
+                }
+                _ => return Err(RecordingError::InvalidValue("drag and drop event")),
+            };
+            Ok(Event::DragDrop(drag_drop))
+        }
+        7 => {
+            let id: u16 = u16::try_from(reader.varint()?)
+                .map_err(|_| RecordingError::InvalidValue("menu id"))?;
+            Ok(Event::MenuActivated(MenuId(id)))
+        }
+        _ => Err(RecordingError::InvalidValue("event type")),
+    };
+}
+
+/*
+    The tables below give every variant its number in the binary format and its name in the JSON-lines format.
+    Only add to the end of a table, the numbers are part of the file format.
+*/
+
+const KEY_STATES: [KeyState; 2] = [KeyState::Press, KeyState::Release];
+const KEY_STATE_NAMES: [&str; 2] = ["press", "release"];
+
+const MOUSE_STATES: [MouseState; 11] = [
+    MouseState::LPress,
+    MouseState::LRelease,
+    MouseState::RPress,
+    MouseState::RRelease,
+    MouseState::WheelUp,
+    MouseState::WHeelDown,
+    MouseState::WheelPress,
+    MouseState::WHeelRelease,
+    MouseState::Move,
+    MouseState::Enter,
+    MouseState::Leave,
+];
+const MOUSE_STATE_NAMES: [&str; 11] = [
+    "left_press",
+    "left_release",
+    "right_press",
+    "right_release",
+    "wheel_up",
+    "wheel_down",
+    "wheel_press",
+    "wheel_release",
+    "move",
+    "enter",
+    "leave",
+];
+
+const GESTURE_KINDS: [GestureKind; 8] = [
+    GestureKind::Pinch,
+    GestureKind::Pan,
+    GestureKind::Rotate,
+    GestureKind::Swipe(SwipeDirection::Left),
+    GestureKind::Swipe(SwipeDirection::Right),
+    GestureKind::Swipe(SwipeDirection::Up),
+    GestureKind::Swipe(SwipeDirection::Down),
+    GestureKind::LongPress,
+];
+const GESTURE_KIND_NAMES: [&str; 8] = [
+    "pinch",
+    "pan",
+    "rotate",
+    "swipe_left",
+    "swipe_right",
+    "swipe_up",
+    "swipe_down",
+    "long_press",
+];
+
+const GESTURE_PHASES: [GesturePhase; 3] =
+    [GesturePhase::Begin, GesturePhase::Update, GesturePhase::End];
+const GESTURE_PHASE_NAMES: [&str; 3] = ["begin", "update", "end"];
+
+/// Moved, Resized, ScaleFactorChanged and CaptionButtonsChanged are placeholders, their fields are read separately.
+const WINDOW_EVENTS: [WindowEvent; 15] = [
+    WindowEvent::CloseRequested,
+    WindowEvent::Destroyed,
+    WindowEvent::Moved { x: 0, y: 0 },
+    WindowEvent::Resized {
+        width: 0,
+        height: 0,
+    },
+    WindowEvent::Minimized,
+    WindowEvent::Maximized,
+    WindowEvent::Restored,
+    WindowEvent::FocusGained,
+    WindowEvent::FocusLost,
+    WindowEvent::Shown,
+    WindowEvent::Hidden,
+    WindowEvent::RedrawRequested,
+    WindowEvent::ScaleFactorChanged {
+        scale_factor: 1.0,
+        width: 0,
+        height: 0,
+    },
+    WindowEvent::ClipboardChanged,
+    WindowEvent::CaptionButtonsChanged {
+        hovered: None,
+        pressed: None,
+    },
+];
+const WINDOW_EVENT_NAMES: [&str; 15] = [
+    "close_requested",
+    "destroyed",
+    "moved",
+    "resized",
+    "minimized",
+    "maximized",
+    "restored",
+    "focus_gained",
+    "focus_lost",
+    "shown",
+    "hidden",
+    "redraw_requested",
+    "scale_factor_changed",
+    "clipboard_changed",
+    "caption_buttons_changed",
+];
+
+/// The number of a button is its position plus one, 0 and the first name are for no button.
+const CAPTION_BUTTONS: [CaptionButton; 3] = [
+    CaptionButton::Minimize,
+    CaptionButton::Maximize,
+    CaptionButton::Close,
+];
+const CAPTION_BUTTON_NAMES: [&str; 4] = ["none", "minimize", "maximize", "close"];
+
+/// The events carry their own fields, so there is no table with values.
+const DRAG_DROP_NAMES: [&str; 3] = ["hovered_file", "hovered_file_cancelled", "dropped_files"];
+
+fn key_state_index(key_state: KeyState) -> u8 {
+    return KEY_STATES
+        .iter()
+        .position(|state| *state == key_state)
+        .unwrap() as u8;
+}
+
+fn mouse_state_index(mouse_state: MouseState) -> u8 {
+    return MOUSE_STATES
+        .iter()
+        .position(|state| *state == mouse_state)
+        .unwrap() as u8;
+}
+
+fn gesture_kind_index(kind: GestureKind) -> u8 {
+    return GESTURE_KINDS.iter().position(|k| *k == kind).unwrap() as u8;
+}
+
+fn gesture_phase_index(phase: GesturePhase) -> u8 {
+    return GESTURE_PHASES.iter().position(|p| *p == phase).unwrap() as u8;
+}
+
+fn drag_drop_index(drag_drop: &DragDropEvent) -> u8 {
+    return match drag_drop {
+        DragDropEvent::HoveredFile(_) => 0,
+        DragDropEvent::HoveredFileCancelled => 1,
+        DragDropEvent::DroppedFiles { .. } => 2,
+    };
+}
+
+fn caption_button_index(button: Option<CaptionButton>) -> u8 {
+    return match button {
+        Some(button) => CAPTION_BUTTONS.iter().position(|b| *b == button).unwrap() as u8 + 1,
+        None => 0,
+    };
+}
+
+fn caption_button_from_index(index: usize) -> Result<Option<CaptionButton>, RecordingError> {
+    return match index {
+        0 => Ok(None),
+        _ => CAPTION_BUTTONS
+            .get(index - 1)
+            .map(|button| Some(*button))
+            .ok_or(RecordingError::InvalidValue("caption button")),
+    };
+}
+
+fn window_event_index(window_event: &WindowEvent) -> u8 {
+    return match window_event {
+        WindowEvent::Moved { .. } => 2,
+        WindowEvent::Resized { .. } => 3,
+        WindowEvent::ScaleFactorChanged { .. } => 12,
+        WindowEvent::CaptionButtonsChanged { .. } => 14,
+        _ => WINDOW_EVENTS
+            .iter()
+            .position(|event| event == window_event)
+            .unwrap() as u8,
+    };
+}
+
+// JSON-lines encoding
+
+fn event_to_json(event: &Event) -> String {
+    return match event {
+        Event::Key(key) => format!(
+            ",\"event\":\"key\",\"state\":\"{}\",\"code\":{}",
+            KEY_STATE_NAMES[key_state_index(key.key_state) as usize],
+            key.key_code
+        ),
+        Event::Char(char) => format!(
+            ",\"event\":\"char\",\"char\":{}",
+            json_string(&char.to_string())
+        ),
+        Event::Mouse(mouse) => format!(
+            ",\"event\":\"mouse\",\"state\":\"{}\",\"left\":{},\"right\":{},\"wheel\":{},\"x\":{},\"y\":{}",
+            MOUSE_STATE_NAMES[mouse_state_index(mouse.mouse_state) as usize],
+            mouse.left_pressed,
+            mouse.right_pressed,
+            mouse.wheel_pressed,
+            mouse.pos.x,
+            mouse.pos.y
+        ),
+        Event::Wheel { pos, delta } => format!(
+            ",\"event\":\"wheel\",\"x\":{},\"y\":{},\"delta\":{}",
+            pos.x, pos.y, delta
+        ),
+        Event::Gesture(gesture) => format!(
+            ",\"event\":\"gesture\",\"kind\":\"{}\",\"phase\":\"{}\",\"x\":{},\"y\":{},\"scale\":{},\"rotation\":{},\"translation_x\":{},\"translation_y\":{},\"fingers\":{}",
+            GESTURE_KIND_NAMES[gesture_kind_index(gesture.kind) as usize],
+            GESTURE_PHASE_NAMES[gesture_phase_index(gesture.phase) as usize],
+            gesture.x,
+            gesture.y,
+            gesture.scale,
+            gesture.rotation,
+            gesture.translation_x,
+            gesture.translation_y,
+            gesture.fingers
+        ),
+        Event::Window(window_event) => {
+            let name: &str = WINDOW_EVENT_NAMES[window_event_index(window_event) as usize];
+            match window_event {
+                WindowEvent::Moved { x, y } => {
+                    format!(",\"event\":\"window\",\"kind\":\"{}\",\"x\":{},\"y\":{}", name, x, y)
+                }
+                WindowEvent::Resized { width, height } => format!(
+                    ",\"event\":\"window\",\"kind\":\"{}\",\"width\":{},\"height\":{}",
+                    name, width, height
+                ),
+                WindowEvent::ScaleFactorChanged {
+                    scale_factor,
+                    width,
+                    height,
+                } => format!(
+                    ",\"event\":\"window\",\"kind\":\"{}\",\"scale_factor\":{},\"width\":{},\"height\":{}",
+                    name, scale_factor, width, height
+                ),
+                WindowEvent::CaptionButtonsChanged { hovered, pressed } => format!(
+                    ",\"event\":\"window\",\"kind\":\"{}\",\"hovered\":\"{}\",\"pressed\":\"{}\"",
+                    name,
+                    CAPTION_BUTTON_NAMES[caption_button_index(*hovered) as usize],
+                    CAPTION_BUTTON_NAMES[caption_button_index(*pressed) as usize]
+                ),
+                _ => format!(",\"event\":\"window\",\"kind\":\"{}\"", name),
+            }
+        }
+        Event::DragDrop(drag_drop) => {
+            let name: &str = DRAG_DROP_NAMES[drag_drop_index(drag_drop) as usize];
+            match drag_drop {
+                DragDropEvent::HoveredFile(path) => format!(
+                    ",\"event\":\"drag_drop\",\"kind\":\"{}\",\"path\":{}",
+                    name,
+                    json_string(&path.to_string_lossy())
+                ),
+                DragDropEvent::HoveredFileCancelled => {
+                    format!(",\"event\":\"drag_drop\",\"kind\":\"{}\"", name)
+                }
+                DragDropEvent::DroppedFiles { paths, position } => {
+                    // There are no arrays in a line, a path can't contain a line break on windows
+                    let paths: Vec<String> = paths
+                        .iter()
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .collect();
+                    format!(
+                        ",\"event\":\"drag_drop\",\"kind\":\"{}\",\"paths\":{},\"x\":{},\"y\":{}",
+                        name,
+                        json_string(&paths.join("\n")),
+                        position.x,
+                        position.y
+                    )
+                }
+            }
+        }
+        Event::MenuActivated(id) => format!(",\"event\":\"menu\",\"id\":{}", id.0),
+        Event::Timer(_) | Event::User(_) => String::new(),
+    };
+}
+
+fn event_from_json(object: &JsonObject) -> Result<Event, String> {
+    return match object.string("event")? {
+        "key" => {
+            let key_code: f64 = object.number("code")?;
+            if !(0.0..KEY_CODE_COUNT as f64).contains(&key_code) || key_code.fract() != 0.0 {
+                return Err(format!(
+                    "\"code\" must be a key code below {}",
+                    KEY_CODE_COUNT
+                ));
+            }
+            Ok(Event::Key(KeyEvent {
+                key_state: KEY_STATES[object.name("state", &KEY_STATE_NAMES)?],
+                key_code: key_code as u32,
+            }))
+        }
+        "char" => {
+            let mut chars = object.string("char")?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => Ok(Event::Char(char)),
+                _ => Err("\"char\" must be a single character".to_string()),
+            }
+        }
+        "mouse" => Ok(Event::Mouse(MouseEvent {
+            mouse_state: MOUSE_STATES[object.name("state", &MOUSE_STATE_NAMES)?],
+            left_pressed: object.bool("left")?,
+            right_pressed: object.bool("right")?,
+            wheel_pressed: object.bool("wheel")?,
+            pos: ClientPoint {
+                x: object.number("x")? as i32,
+                y: object.number("y")? as i32,
+            },
+        })),
+        "wheel" => Ok(Event::Wheel {
+            pos: ClientPoint {
+                x: object.number("x")? as i32,
+                y: object.number("y")? as i32,
+            },
+            delta: object.number("delta")? as i16,
+        }),
+        "gesture" => Ok(Event::Gesture(GestureEvent {
+            kind: GESTURE_KINDS[object.name("kind", &GESTURE_KIND_NAMES)?],
+            phase: GESTURE_PHASES[object.name("phase", &GESTURE_PHASE_NAMES)?],
+            x: object.number("x")? as f32,
+            y: object.number("y")? as f32,
+            scale: object.number("scale")? as f32,
+            rotation: object.number("rotation")? as f32,
+            translation_x: object.number("translation_x")? as f32,
+            translation_y: object.number("translation_y")? as f32,
+            fingers: object.number("fingers")? as usize,
+        })),
+        "window" => {
+            let index: usize = object.name("kind", &WINDOW_EVENT_NAMES)?;
+            let window_event: WindowEvent = match index {
+                2 => WindowEvent::Moved {
+                    x: object.number("x")? as i32,
+                    y: object.number("y")? as i32,
+                },
+                3 => WindowEvent::Resized {
+                    width: object.number("width")? as i32,
+                    height: object.number("height")? as i32,
+                },
+                12 => WindowEvent::ScaleFactorChanged {
+                    scale_factor: object.number("scale_factor")?,
+                    width: object.number("width")? as i32,
+                    height: object.number("height")? as i32,
+                },
+                14 => WindowEvent::CaptionButtonsChanged {
+                    hovered: object
+                        .name("hovered", &CAPTION_BUTTON_NAMES)?
+                        .checked_sub(1)
+                        .map(|index| CAPTION_BUTTONS[index]),
+                    pressed: object
+                        .name("pressed", &CAPTION_BUTTON_NAMES)?
+                        .checked_sub(1)
+                        .map(|index| CAPTION_BUTTONS[index]),
+                },
+                _ => WINDOW_EVENTS[index],
+            };
+            Ok(Event::Window(window_event))
+        }
+        "drag_drop" => {
+            let drag_drop: DragDropEvent = match object.name("kind", &DRAG_DROP_NAMES)? {
+                0 => DragDropEvent::HoveredFile(PathBuf::from(object.string("path")?)),
+                1 => DragDropEvent::HoveredFileCancelled,
+                _ => {
+                    let paths: &str = object.string("paths")?;
+                    DragDropEvent::DroppedFiles {
+                        paths: paths
+                            .split('\n')
+                            .filter(|path| !path.is_empty())
+                            .map(PathBuf::from)
+                            .collect(),
+                        position: ClientPoint {
+                            x: object.number("x")? as i32,
+                            y: object.number("y")? as i32,
+                        },
+                    }
+                }
+            };
+            Ok(Event::DragDrop(drag_drop))
+        }
+        "menu" => {
+            let id: f64 = object.number("id")?;
+            if !(0.0..=u16::MAX as f64).contains(&id) {
+                return Err("\"id\" must be a menu id".to_string());
+            }
+            Ok(Event::MenuActivated(MenuId(id as u16)))
+        }
+        other => Err(format!("Unknown event \"{}\"", other)),
+    };
+}
+
+fn json_string(text: &str) -> String {
+    let mut json: String = String::from("\"");
+    for char in text.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if (char as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", char as u32)),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    return json;
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum JsonValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+}
+
+/// A JSON object without nesting, which is all a line of a recording is.
+struct JsonObject {
+    fields: Vec<(String, JsonValue)>,
+}
+
+impl JsonObject {
+    fn get(&self, key: &str) -> Option<&JsonValue> {
+        return self
+            .fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value);
+    }
+
+    fn string(&self, key: &str) -> Result<&str, String> {
+        return match self.get(key) {
+            Some(JsonValue::String(value)) => Ok(value),
+            _ => Err(format!("\"{}\" must be a string", key)),
+        };
+    }
+
+    fn number(&self, key: &str) -> Result<f64, String> {
+        return match self.get(key) {
+            Some(JsonValue::Number(value)) => Ok(*value),
+            _ => Err(format!("\"{}\" must be a number", key)),
+        };
+    }
+
+    fn bool(&self, key: &str) -> Result<bool, String> {
+        return match self.get(key) {
+            Some(JsonValue::Bool(value)) => Ok(*value),
+            _ => Err(format!("\"{}\" must be true or false", key)),
+        };
+    }
+
+    /// The index of the string value in `names`.
+    fn name(&self, key: &str, names: &[&str]) -> Result<usize, String> {
+        let value: &str = self.string(key)?;
+        return names
+            .iter()
+            .position(|name| *name == value)
+            .ok_or(format!("Unknown {} \"{}\"", key, value));
+    }
+}
+
+fn parse_json_object(line: &str) -> Result<JsonObject, String> {
+    let mut chars = line.trim().chars().peekable();
+    let mut fields: Vec<(String, JsonValue)> = vec![];
+
+    if chars.next() != Some('{') {
+        return Err("Expected '{'".to_string());
+    }
+    loop {
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some('}') if fields.is_empty() => break,
+            Some('"') => {}
+            _ => return Err("Expected a key".to_string()),
+        }
+        let key: String = parse_json_string(&mut chars)?;
+        skip_whitespace(&mut chars);
+        if chars.next() != Some(':') {
+            return Err(format!("Expected ':' after \"{}\"", key));
+        }
+        skip_whitespace(&mut chars);
+
+        let value: JsonValue = match chars.peek() {
+            Some('"') => {
+                chars.next();
+                JsonValue::String(parse_json_string(&mut chars)?)
+            }
+            Some('t') | Some('f') => {
+                let word: String = take_while(&mut chars, |char| char.is_ascii_alphabetic());
+                match word.as_str() {
+                    "true" => JsonValue::Bool(true),
+                    "false" => JsonValue::Bool(false),
+                    _ => return Err(format!("Invalid value for \"{}\"", key)),
+                }
+            }
+            _ => {
+                let number: String = take_while(&mut chars, |char| {
+                    char.is_ascii_digit() || matches!(char, '-' | '+' | '.' | 'e' | 'E')
+                });
+                JsonValue::Number(
+                    number
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid value for \"{}\"", key))?,
+                )
+            }
+        };
+        fields.push((key, value));
+
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => break,
+            _ => return Err("Expected ',' or '}'".to_string()),
+        }
+    }
+    skip_whitespace(&mut chars);
+    if chars.next().is_some() {
+        return Err("Unexpected text after the object".to_string());
+    }
+    return Ok(JsonObject { fields });
+}
+
+/// Parse the rest of a string, the opening quote is already taken.
+fn parse_json_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    let mut text: String = String::new();
+    let mut high_surrogate: Option<u16> = None;
+    loop {
+        let char: char = chars.next().ok_or("Unterminated string")?;
+        let unit: Option<u16> = match char {
+            '"' if high_surrogate.is_some() => return Err("Lone surrogate".to_string()),
+            '"' => break,
+            '\\' => match chars.next().ok_or("Unterminated string")? {
+                '"' => Some('"' as u16),
+                '\\' => Some('\\' as u16),
+                '/' => Some('/' as u16),
+                'b' => Some(0x08),
+                'f' => Some(0x0C),
+                'n' => Some('\n' as u16),
+                'r' => Some('\r' as u16),
+                't' => Some('\t' as u16),
+                'u' => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    Some(u16::from_str_radix(&hex, 16).map_err(|_| "Invalid \\u escape")?)
+                }
+                _ => return Err("Invalid escape".to_string()),
+            },
+            // The second half of a pair has to follow right away
+            _ if high_surrogate.is_some() => return Err("Lone surrogate".to_string()),
+            char => {
+                text.push(char);
+                None
+            }
+        };
+        // \u escapes are UTF-16, characters outside of the BMP are written as two of them
+        if let Some(unit) = unit {
+            match (high_surrogate.take(), unit) {
+                (None, 0xD800..=0xDBFF) => high_surrogate = Some(unit),
+                (Some(high), 0xDC00..=0xDFFF) => {
+                    text.extend(char::decode_utf16([high, unit]).filter_map(|c| c.ok()))
+                }
+                (Some(_), _) => return Err("Lone surrogate".to_string()),
+                (None, unit) => text.push(char::from_u32(unit as u32).ok_or("Lone surrogate")?),
+            }
+        }
+    }
+    return Ok(text);
+}
+
+fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
+    while chars.next_if(|char| char.is_whitespace()).is_some() {}
+}
+
+fn take_while(
+    chars: &mut std::iter::Peekable<std::str::Chars>,
+    predicate: impl Fn(char) -> bool,
+) -> String {
+    let mut text: String = String::new();
+    while let Some(char) = chars.next_if(|char| predicate(*char)) {
+        text.push(char);
+    }
+    return text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(frame: u64, time_us: u64, events: Vec<Event>) -> RecordedFrame {
+        return RecordedFrame {
+            frame,
+            time: Duration::from_micros(time_us),
+            events,
+        };
+    }
+
+    /// A recording with every kind of event that can be recorded.
+    fn sample() -> Recording {
+        let mouse: MouseEvent = MouseEvent {
+            mouse_state: MouseState::Move,
+            left_pressed: true,
+            right_pressed: false,
+            wheel_pressed: true,
+            pos: ClientPoint { x: -5, y: 7 },
+        };
+        let pinch: GestureEvent = GestureEvent {
+            kind: GestureKind::Pinch,
+            phase: GesturePhase::Update,
+            x: 1.5,
+            y: 2.25,
+            scale: 1.2,
+            rotation: -0.3,
+            translation_x: 0.1,
+            translation_y: -1.0,
+            fingers: 2,
+        };
+        return Recording {
+            frames: vec![
+                frame(0, 0, vec![]),
+                frame(
+                    1,
+                    16_667,
+                    vec![
+                        Event::Key(KeyEvent {
+                            key_state: KeyState::Press,
+                            key_code: 0x41,
+                        }),
+                        Event::Char('é'),
+                        Event::Char('🦀'),
+                        Event::Char('"'),
+                        Event::Mouse(mouse),
+                        Event::Wheel {
+                            pos: ClientPoint { x: 3, y: 4 },
+                            delta: -240,
+                        },
+                    ],
+                ),
+                // Frame 2 had nothing, a recording can skip frames
+                frame(
+                    3,
+                    50_000,
+                    vec![
+                        Event::Gesture(pinch),
+                        Event::Gesture(GestureEvent {
+                            kind: GestureKind::Swipe(SwipeDirection::Left),
+                            phase: GesturePhase::End,
+                            fingers: 3,
+                            ..pinch
+                        }),
+                    ],
+                ),
+                frame(
+                    4,
+                    66_667,
+                    vec![
+                        Event::Window(WindowEvent::CloseRequested),
+                        Event::Window(WindowEvent::Moved { x: -10, y: 20 }),
+                        Event::Window(WindowEvent::Resized {
+                            width: 800,
+                            height: 600,
+                        }),
+                        Event::Window(WindowEvent::ScaleFactorChanged {
+                            scale_factor: 1.5,
+                            width: 1200,
+                            height: 900,
+                        }),
+                        Event::Window(WindowEvent::CaptionButtonsChanged {
+                            hovered: Some(CaptionButton::Close),
+                            pressed: None,
+                        }),
+                    ],
+                ),
+                frame(
+                    5,
+                    u32::MAX as u64 * 10,
+                    vec![
+                        Event::DragDrop(DragDropEvent::HoveredFile(PathBuf::from(
+                            "C:\\dir\\a \"b\".txt",
+                        ))),
+                        Event::DragDrop(DragDropEvent::HoveredFileCancelled),
+                        Event::DragDrop(DragDropEvent::DroppedFiles {
+                            paths: vec![PathBuf::from("C:\\a.txt"), PathBuf::from("日本.png")],
+                            position: ClientPoint { x: 10, y: -2 },
+                        }),
+                        Event::MenuActivated(MenuId(7)),
+                    ],
+                ),
+            ],
+        };
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let recording: Recording = sample();
+        let bytes: Vec<u8> = recording.to_bytes();
+        assert!(bytes.starts_with(b"WREC"));
+        assert_eq!(Recording::from_bytes(&bytes), Ok(recording));
+        assert_eq!(
+            Recording::from_bytes(&Recording::default().to_bytes()),
+            Ok(Recording::default())
+        );
+    }
+
+    #[test]
+    fn json_lines_round_trip() {
+        let recording: Recording = sample();
+        let text: String = recording.to_json_lines();
+        assert!(text.starts_with("{\"format\":\"win32-input-recording\",\"version\":1}\n"));
+        assert_eq!(Recording::from_json_lines(&text), Ok(recording.clone()));
+        // Both formats hold the same
+        assert_eq!(
+            Recording::from_bytes(&Recording::from_json_lines(&text).unwrap().to_bytes()),
+            Ok(recording)
+        );
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let bytes: Vec<u8> = sample().to_bytes();
+        for length in 6..bytes.len() {
+            assert_eq!(
+                Recording::from_bytes(&bytes[..length]),
+                Err(RecordingError::UnexpectedEnd),
+                "cut at {} of {} bytes",
+                length,
+                bytes.len()
+            );
+        }
+        assert_eq!(
+            Recording::from_bytes(b"WRE"),
+            Err(RecordingError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Recording::from_bytes(b"RIFF\x01\x00"),
+            Err(RecordingError::BadMagic)
+        );
+        assert_eq!(
+            Recording::from_bytes(b"WREC\x02\x00\x00"),
+            Err(RecordingError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn truncated_json_lines() {
+        let text: String = sample().to_json_lines();
+        assert_eq!(
+            Recording::from_json_lines(""),
+            Err(RecordingError::UnexpectedEnd)
+        );
+        // Cut in the middle of the third line
+        let cut: usize = text.match_indices('\n').nth(1).unwrap().0 + 10;
+        assert!(matches!(
+            Recording::from_json_lines(&text[..cut]),
+            Err(RecordingError::InvalidLine { line: 3, .. })
+        ));
+    }
+
+    /// A recording of one frame with one key press of `key_code`.
+    fn key_bytes(key_code: u64) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        for value in [1, 0, 0, 1] {
+            write_varint(&mut bytes, value);
+        }
+        bytes.extend_from_slice(&[0, 0]);
+        write_varint(&mut bytes, key_code);
+        return bytes;
+    }
+
+    #[test]
+    fn key_codes_are_checked() {
+        assert!(Recording::from_bytes(&key_bytes(255)).is_ok());
+        for key_code in [256, u32::MAX as u64 + 0x41] {
+            assert_eq!(
+                Recording::from_bytes(&key_bytes(key_code)),
+                Err(RecordingError::InvalidValue("key code"))
+            );
+        }
+
+        let header: &str = "{\"format\":\"win32-input-recording\",\"version\":1}\n";
+        let line = |code: &str| {
+            return format!(
+                "{}{{\"frame\":0,\"time_us\":0,\"event\":\"key\",\"state\":\"press\",\"code\":{}}}\n",
+                header, code
+            );
+        };
+        assert!(Recording::from_json_lines(&line("255")).is_ok());
+        for code in ["256", "-1", "65.5"] {
+            assert!(matches!(
+                Recording::from_json_lines(&line(code)),
+                Err(RecordingError::InvalidLine { line: 2, .. })
+            ));
+        }
+    }
+
+    fn parse_string(json: &str) -> Result<String, String> {
+        // The opening quote is already taken by the caller
+        return parse_json_string(&mut json.chars().peekable());
+    }
+
+    #[test]
+    fn json_surrogate_pairs() {
+        assert_eq!(parse_string(r#"\ud83e\udd80""#), Ok("🦀".to_string()));
+        assert_eq!(parse_string(r#"a\u00e9""#), Ok("aé".to_string()));
+
+        let lone: Result<String, String> = Err("Lone surrogate".to_string());
+        for json in [
+            r#"\ud83e""#,
+            r#"\ud83ex""#,
+            r#"\ud83e\n""#,
+            r#"\ud83e\u0041""#,
+            r#"\ud83e\ud83e\udd80""#,
+            r#"\udd80""#,
+        ] {
+            assert_eq!(parse_string(json), lone, "{}", json);
+        }
+    }
+
+    #[test]
+    fn replay_speed_is_clamped() {
+        let mut replayer: Replayer = Replayer::new(Recording::default());
+        for (speed, expected) in [
+            (2.0, 2.0),
+            (-1.0, 0.0),
+            (f64::NAN, 0.0),
+            (f64::INFINITY, MAX_REPLAY_SPEED),
+            (f64::NEG_INFINITY, 0.0),
+            (1e300, MAX_REPLAY_SPEED),
+        ] {
+            replayer.set_speed(speed);
+            assert_eq!(replayer.speed(), expected);
+        }
+    }
+
+    #[test]
+    fn replay_at_the_fastest_speed() {
+        let recording: Recording = Recording {
+            frames: vec![
+                frame(0, 0, vec![]),
+                frame(1, 5_000_000, vec![Event::Char('a')]),
+            ],
+        };
+        let mut replayer: Replayer = Replayer::new(recording);
+        replayer.set_speed(f64::INFINITY);
+
+        let start: Instant = Instant::now();
+        assert!(replayer.advance(start).is_some());
+        // 5ms of real time are 5s of recorded time
+        assert!(replayer.advance(start + Duration::from_millis(4)).is_none());
+        assert!(replayer.advance(start + Duration::from_millis(5)).is_some());
+        assert!(replayer.is_finished());
+    }
+
+    #[test]
+    fn frame_overflow_is_an_error() {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_varint(&mut bytes, 2);
+        for value in [u64::MAX, 0, 0, 1, 0, 0] {
+            write_varint(&mut bytes, value);
+        }
+        assert_eq!(
+            Recording::from_bytes(&bytes),
+            Err(RecordingError::InvalidValue("frame number"))
+        );
+
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        write_varint(&mut bytes, 2);
+        for value in [0, u64::MAX, 0, 1, 1, 0] {
+            write_varint(&mut bytes, value);
+        }
+        assert_eq!(
+            Recording::from_bytes(&bytes),
+            Err(RecordingError::InvalidValue("frame time"))
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use windows::Win32::{
+    Foundation::RECT,
+    UI::HiDpi::{GetSystemMetricsForDpi, SystemParametersInfoForDpi},
+    UI::WindowsAndMessaging::{
+        GetSystemMetrics, SystemParametersInfoW, NONCLIENTMETRICSW, SM_CXEDGE, SM_CYEDGE,
+        SPI_GETNONCLIENTMETRICS, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WINDOW_EX_STYLE,
+        WINDOW_STYLE, WMSZ_BOTTOMLEFT, WMSZ_LEFT, WMSZ_TOP, WMSZ_TOPLEFT, WMSZ_TOPRIGHT, WS_BORDER,
+        WS_CAPTION, WS_DLGFRAME, WS_EX_CLIENTEDGE, WS_EX_DLGMODALFRAME, WS_EX_STATICEDGE,
+        WS_EX_TOOLWINDOW, WS_THICKFRAME,
+    },
+};
+
+use super::coords::ClientPoint;
+
+/// A rectangle with an exclusive right and bottom edge, just like a Win32 [RECT].
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    /// A rect at (0, 0) with the given size. Used for client rects.
+    pub fn from_size(width: i32, height: i32) -> Rect {
+        return Rect {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        };
+    }
+
+    pub fn width(&self) -> i32 {
+        return self.right - self.left;
+    }
+
+    pub fn height(&self) -> i32 {
+        return self.bottom - self.top;
+    }
+
+    pub fn contains(&self, point: ClientPoint) -> bool {
+        return point.x >= self.left
+            && point.x < self.right
+            && point.y >= self.top
+            && point.y < self.bottom;
+    }
+
+    /// Grow the rect by `dx` on the left and right and `dy` on the top and bottom.
+    pub fn inflate(&self, dx: i32, dy: i32) -> Rect {
+        return Rect {
+            left: self.left - dx,
+            top: self.top - dy,
+            right: self.right + dx,
+            bottom: self.bottom + dy,
+        };
+    }
+}
+
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        return Rect {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        };
+    }
+}
+
+impl From<Rect> for RECT {
+    fn from(rect: Rect) -> Self {
+        return RECT {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        };
+    }
+}
+
+/// The sizes of the non-client area (title bar, borders, menu) that [adjust_window_rect()] needs.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub struct FrameMetrics {
+    pub border_width: i32,
+    pub padded_border_width: i32,
+    pub caption_height: i32,
+    pub small_caption_height: i32,
+    pub menu_height: i32,
+    pub edge_width: i32,
+    pub edge_height: i32,
+}
+
+impl FrameMetrics {
+    /**
+        Read the metrics of the current system.
+
+        For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/ns-winuser-nonclientmetricsw
+    */
+    pub fn from_system() -> FrameMetrics {
+        let mut metrics: NONCLIENTMETRICSW = NONCLIENTMETRICSW {
+            cbSize: std::mem::size_of::<NONCLIENTMETRICSW>() as u32,
+            ..Default::default()
+        };
+        unsafe {
+            SystemParametersInfoW(
+                SPI_GETNONCLIENTMETRICS,
+                metrics.cbSize,
+                Some(&mut metrics as *mut _ as *mut _),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            );
+        }
+
+        return FrameMetrics {
+            border_width: metrics.iBorderWidth,
+            padded_border_width: metrics.iPaddedBorderWidth,
+            caption_height: metrics.iCaptionHeight,
+            small_caption_height: metrics.iSmCaptionHeight,
+            menu_height: metrics.iMenuHeight,
+            edge_width: unsafe { GetSystemMetrics(SM_CXEDGE) },
+            edge_height: unsafe { GetSystemMetrics(SM_CYEDGE) },
+        };
+    }
+}
+
+impl FrameMetrics {
+    /**
+        Read the metrics of the current system for a monitor with `dpi`. A per-monitor DPI aware window has to use
+        these, [FrameMetrics::from_system()] gives the metrics for the DPI of the primary monitor at login.
+
+        For more info see: https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-systemparametersinfofordpi
+    */
+    pub fn for_dpi(dpi: u32) -> FrameMetrics {
+        let mut metrics: NONCLIENTMETRICSW = NONCLIENTMETRICSW {
+            cbSize: std::mem::size_of::<NONCLIENTMETRICSW>() as u32,
+            ..Default::default()
+        };
+        let ok: bool = unsafe {
+            SystemParametersInfoForDpi(
+                SPI_GETNONCLIENTMETRICS.0,
+                metrics.cbSize,
+                Some(&mut metrics as *mut _ as *mut _),
+                0,
+                dpi,
+            )
+            .as_bool()
+        };
+        if !ok {
+            // Before Windows 10 1607 there are no per-monitor metrics
+            return FrameMetrics::from_system();
+        }
+
+        return FrameMetrics {
+            border_width: metrics.iBorderWidth,
+            padded_border_width: metrics.iPaddedBorderWidth,
+            caption_height: metrics.iCaptionHeight,
+            small_caption_height: metrics.iSmCaptionHeight,
+            menu_height: metrics.iMenuHeight,
+            edge_width: unsafe { GetSystemMetricsForDpi(SM_CXEDGE, dpi) },
+            edge_height: unsafe { GetSystemMetricsForDpi(SM_CYEDGE, dpi) },
+        };
+    }
+}
+
+/**
+    Calculate the window rect that is needed for a client rect. This does the same as
+    [AdjustWindowRectEx](https://learn.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-adjustwindowrectex)
+    but with the metrics passed in, so the math doesn't depend on the machine it runs on.
+*/
+pub fn adjust_window_rect(
+    client: Rect,
+    style: WINDOW_STYLE,
+    ex_style: WINDOW_EX_STYLE,
+    has_menu: bool,
+    metrics: &FrameMetrics,
+) -> Rect {
+    let has_style = |flag: WINDOW_STYLE| style.0 & flag.0 != 0;
+    let has_ex_style = |flag: WINDOW_EX_STYLE| ex_style.0 & flag.0 != 0;
+
+    // The outer frame
+    let mut adjust: i32 = 0;
+    if has_ex_style(WS_EX_STATICEDGE) && !has_ex_style(WS_EX_DLGMODALFRAME) {
+        adjust = 1;
+    } else if has_ex_style(WS_EX_DLGMODALFRAME)
+        || has_style(WS_THICKFRAME)
+        || has_style(WS_DLGFRAME)
+    {
+        adjust = 2;
+    }
+    // The resize border
+    if has_style(WS_THICKFRAME) {
+        adjust += metrics.border_width + metrics.padded_border_width;
+    }
+    // The inner border
+    if has_style(WS_BORDER) || has_style(WS_DLGFRAME) || has_ex_style(WS_EX_DLGMODALFRAME) {
+        adjust += 1;
+    }
+
+    let mut rect: Rect = client.inflate(adjust, adjust);
+
+    // WS_CAPTION is WS_BORDER | WS_DLGFRAME, both have to be set for a title bar
+    if style.0 & WS_CAPTION.0 == WS_CAPTION.0 {
+        if has_ex_style(WS_EX_TOOLWINDOW) {
+            rect.top -= metrics.small_caption_height + 1;
+        } else {
+            rect.top -= metrics.caption_height + 1;
+        }
+    }
+    if has_menu {
+        rect.top -= metrics.menu_height + 1;
+    }
+    if has_ex_style(WS_EX_CLIENTEDGE) {
+        rect = rect.inflate(metrics.edge_width, metrics.edge_height);
+    }
+
+    return rect;
+}
+
+/// The size of the title bar and borders: the outer size minus the client size.
+pub fn frame_size(
+    style: WINDOW_STYLE,
+    ex_style: WINDOW_EX_STYLE,
+    has_menu: bool,
+    metrics: &FrameMetrics,
+) -> (i32, i32) {
+    let frame: Rect = adjust_window_rect(Rect::default(), style, ex_style, has_menu, metrics);
+    return (frame.width(), frame.height());
+}
+
+/// Clamp a client size between the optional minimum and maximum size.
+pub fn clamp_size(
+    width: i32,
+    height: i32,
+    min_size: Option<(i32, i32)>,
+    max_size: Option<(i32, i32)>,
+) -> (i32, i32) {
+    let (mut width, mut height): (i32, i32) = (width, height);
+    if let Some((max_width, max_height)) = max_size {
+        width = width.min(max_width);
+        height = height.min(max_height);
+    }
+    if let Some((min_width, min_height)) = min_size {
+        width = width.max(min_width);
+        height = height.max(min_height);
+    }
+    return (width, height);
+}
+
+/**
+    Constrain the window rect the user is dragging on WM_SIZING. `edge` is the WMSZ_* value from the wParam, it tells
+    which edge is being dragged so we move that edge and keep the opposite one in place. `frame` is the size from
+    [frame_size()] and the limits are client sizes.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-sizing
+*/
+pub fn constrain_sizing_rect(
+    rect: Rect,
+    edge: u32,
+    frame: (i32, i32),
+    min_size: Option<(i32, i32)>,
+    max_size: Option<(i32, i32)>,
+) -> Rect {
+    let (client_width, client_height): (i32, i32) = clamp_size(
+        rect.width() - frame.0,
+        rect.height() - frame.1,
+        min_size,
+        max_size,
+    );
+    let width: i32 = client_width + frame.0;
+    let height: i32 = client_height + frame.1;
+
+    let mut constrained: Rect = rect;
+    match edge {
+        WMSZ_LEFT | WMSZ_TOPLEFT | WMSZ_BOTTOMLEFT => constrained.left = rect.right - width,
+        _ => constrained.right = rect.left + width,
+    }
+    match edge {
+        WMSZ_TOP | WMSZ_TOPLEFT | WMSZ_TOPRIGHT => constrained.top = rect.bottom - height,
+        _ => constrained.bottom = rect.top + height,
+    }
+    return constrained;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        WMSZ_BOTTOM, WMSZ_BOTTOMRIGHT, WMSZ_RIGHT, WS_EX_TOPMOST, WS_MAXIMIZEBOX, WS_MINIMIZEBOX,
+        WS_OVERLAPPEDWINDOW, WS_POPUP, WS_SYSMENU,
+    };
+
+    /// The metrics of Windows 10 at 96 DPI with the default theme.
+    const METRICS: FrameMetrics = FrameMetrics {
+        border_width: 1,
+        padded_border_width: 4,
+        caption_height: 22,
+        small_caption_height: 22,
+        menu_height: 19,
+        edge_width: 2,
+        edge_height: 2,
+    };
+
+    const CLIENT: Rect = Rect {
+        left: 0,
+        top: 0,
+        right: 800,
+        bottom: 600,
+    };
+
+    #[test]
+    fn adjust_overlapped_window() {
+        // What AdjustWindowRectEx gives for WS_OVERLAPPEDWINDOW
+        let rect: Rect = adjust_window_rect(
+            CLIENT,
+            WS_OVERLAPPEDWINDOW,
+            WINDOW_EX_STYLE(0),
+            false,
+            &METRICS,
+        );
+        assert_eq!(
+            rect,
+            Rect {
+                left: -8,
+                top: -31,
+                right: 808,
+                bottom: 608,
+            }
+        );
+
+        let with_menu: Rect = adjust_window_rect(
+            CLIENT,
+            WS_OVERLAPPEDWINDOW,
+            WINDOW_EX_STYLE(0),
+            true,
+            &METRICS,
+        );
+        assert_eq!(
+            with_menu,
+            Rect {
+                left: -8,
+                top: -51,
+                right: 808,
+                bottom: 608,
+            }
+        );
+    }
+
+    #[test]
+    fn adjust_fixed_window() {
+        let style: WINDOW_STYLE = WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX;
+        let rect: Rect = adjust_window_rect(CLIENT, style, WINDOW_EX_STYLE(0), false, &METRICS);
+        assert_eq!(
+            rect,
+            Rect {
+                left: -3,
+                top: -26,
+                right: 803,
+                bottom: 603,
+            }
+        );
+        assert_eq!(
+            frame_size(style, WINDOW_EX_STYLE(0), true, &METRICS),
+            (6, 49)
+        );
+        // The extended styles which don't change the frame
+        assert_eq!(
+            adjust_window_rect(CLIENT, style, WS_EX_TOPMOST, false, &METRICS),
+            rect
+        );
+    }
+
+    #[test]
+    fn adjust_popup_and_tool_windows() {
+        assert_eq!(
+            adjust_window_rect(CLIENT, WS_POPUP, WINDOW_EX_STYLE(0), false, &METRICS),
+            CLIENT
+        );
+        assert_eq!(
+            frame_size(
+                WS_POPUP | WS_THICKFRAME,
+                WINDOW_EX_STYLE(0),
+                false,
+                &METRICS
+            ),
+            (14, 14)
+        );
+        assert_eq!(
+            frame_size(
+                WS_OVERLAPPEDWINDOW,
+                WS_EX_TOOLWINDOW | WS_EX_CLIENTEDGE,
+                false,
+                &METRICS
+            ),
+            (20, 43)
+        );
+        assert_eq!(
+            frame_size(
+                WS_CAPTION | WS_SYSMENU | WS_MAXIMIZEBOX,
+                WS_EX_STATICEDGE,
+                false,
+                &METRICS
+            ),
+            (4, 27)
+        );
+    }
+
+    #[test]
+    fn constrain_every_edge() {
+        let frame: (i32, i32) =
+            frame_size(WS_OVERLAPPEDWINDOW, WINDOW_EX_STYLE(0), false, &METRICS);
+        let min_size: Option<(i32, i32)> = Some((200, 100));
+        let max_size: Option<(i32, i32)> = Some((1000, 800));
+        // The edge and whether the left and top edge move with it
+        let edges: [(u32, bool, bool); 8] = [
+            (WMSZ_LEFT, true, false),
+            (WMSZ_RIGHT, false, false),
+            (WMSZ_TOP, false, true),
+            (WMSZ_TOPLEFT, true, true),
+            (WMSZ_TOPRIGHT, false, true),
+            (WMSZ_BOTTOM, false, false),
+            (WMSZ_BOTTOMLEFT, true, false),
+            (WMSZ_BOTTOMRIGHT, false, false),
+        ];
+        let rect: Rect = Rect {
+            left: 100,
+            top: 100,
+            right: 500,
+            bottom: 400,
+        };
+        let too_small: Rect = Rect {
+            left: 300,
+            top: 300,
+            right: 350,
+            bottom: 320,
+        };
+        let too_large: Rect = Rect {
+            left: -1000,
+            top: -1000,
+            right: 2000,
+            bottom: 2000,
+        };
+
+        for (edge, moves_left, moves_top) in edges {
+            // Inside the limits nothing changes
+            assert_eq!(
+                constrain_sizing_rect(rect, edge, frame, min_size, max_size),
+                rect
+            );
+
+            for (dragged, (width, height)) in [(too_small, (200, 100)), (too_large, (1000, 800))] {
+                let constrained: Rect =
+                    constrain_sizing_rect(dragged, edge, frame, min_size, max_size);
+                assert_eq!(constrained.width(), width + frame.0, "edge {}", edge);
+                assert_eq!(constrained.height(), height + frame.1, "edge {}", edge);
+                // The edge opposite of the dragged one stays where it is
+                match moves_left {
+                    true => assert_eq!(constrained.right, dragged.right, "edge {}", edge),
+                    false => assert_eq!(constrained.left, dragged.left, "edge {}", edge),
+                }
+                match moves_top {
+                    true => assert_eq!(constrained.bottom, dragged.bottom, "edge {}", edge),
+                    false => assert_eq!(constrained.top, dragged.top, "edge {}", edge),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn constrain_without_limits() {
+        let rect: Rect = Rect {
+            left: 10,
+            top: 20,
+            right: 30,
+            bottom: 40,
+        };
+        assert_eq!(
+            constrain_sizing_rect(rect, WMSZ_BOTTOMRIGHT, (16, 39), None, None),
+            rect
+        );
+        assert_eq!(
+            clamp_size(50, 5000, Some((100, 100)), Some((800, 600))),
+            (100, 600)
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use windows::Win32::{
+    Foundation::{HWND, LPARAM},
+    UI::WindowsAndMessaging::{
+        GetWindowLongPtrW, SetWindowLongPtrW, CREATESTRUCTW, GWLP_USERDATA, SIZE_MAXIMIZED,
+        SIZE_MINIMIZED, SIZE_RESTORED, WINDOW_EX_STYLE, WINDOW_STYLE, WS_THICKFRAME,
+    },
+};
+
+use super::{
+    backend::CursorIcon,
+    chrome::{CaptionButtonState, ChromeLayout, HitTest},
+    coords::{
+        dpi_from_scale_factor, scale_factor_from_dpi, ClientPoint, PhysicalSize, ScreenPoint,
+        BASE_DPI,
+    },
+    event::{Event, WindowEvent},
+    gesture::{GestureConfig, GestureRecognizer},
+    keyboard::{KeyEvent, KeyState, Keyboard, KEY_CODE_COUNT},
+    menu::{AcceleratorTable, Menu, MenuId, Modifiers},
+    monitor::{Placement, WindowMode},
+    mouse::{Mouse, MouseEvent, MouseState},
+    recording::InputRecorder,
+    rect::{frame_size, FrameMetrics, Rect},
+};
+
+const MAX_BUFFER_SIZE: usize = 16;
+/// The event stream holds a lot more events than the queues of the state trackers.
+const MAX_EVENTS: usize = 1024;
+
+/**
+    All the input state of a single window. This is what [wndproc](super::Window::wndproc) writes to.<br>
+    We can't pass our own arguments to the wndproc, so the [Window](super::Window) hands a pointer to this state to
+    CreateWindowEx. The wndproc stores that pointer in the GWLP_USERDATA slot of the window when it receives
+    WM_NCCREATE, and looks it up again for every message after that. This way every window has its own state.
+*/
+pub struct WindowState {
+    pub keyboard: Keyboard,
+    pub mouse: Mouse,
+    /// The touch gesture recognizers. Ctrl + mouse wheel is mapped to the pinch gesture as well.
+    pub gestures: GestureRecognizer,
+    /// Width of the client area. Updated on every WM_SIZE.
+    pub client_width: i32,
+    /// Height of the client area. Updated on every WM_SIZE.
+    pub client_height: i32,
+    /// All the events in the order the messages arrived, see [Window::poll_events()](super::Window::poll_events()).
+    pub events: VecDeque<Event>,
+    /// A FIFO (First In First Out) list of all the recent [WindowEvent]'s.
+    pub window_event_queue: Vec<WindowEvent>,
+    pub minimized: bool,
+    pub maximized: bool,
+    /// Set on WM_DESTROY, the HWND is invalid after that.
+    pub destroyed: bool,
+    /// Minimum size of the client area, see [WindowBuilder::min_size()](super::builder::WindowBuilder::min_size())
+    pub min_size: Option<(i32, i32)>,
+    /// Maximum size of the client area, see [WindowBuilder::max_size()](super::builder::WindowBuilder::max_size())
+    pub max_size: Option<(i32, i32)>,
+    pub style: WINDOW_STYLE,
+    pub ex_style: WINDOW_EX_STYLE,
+    /// The top-left corner of the client area in screen coordinates. Updated on WM_MOVE, used to turn the screen
+    /// coordinates of WM_MOUSEWHEEL and the pointer messages into client coordinates.
+    pub client_origin: ScreenPoint,
+    /// The time of the message that is being translated, from GetMessageTime. Used by the gesture recognizer.
+    pub message_time: u32,
+    /// The DPI of the monitor the window is on. [BASE_DPI] when the process isn't DPI aware.
+    pub dpi: u32,
+    /// See [Window::set_window_mode()](super::Window::set_window_mode()).
+    pub window_mode: WindowMode,
+    /// The placement to go back to when the window leaves fullscreen.
+    pub windowed_placement: Option<Placement>,
+    /// The cursor shown over the client area, see [Window::set_cursor_icon()](super::Window::set_cursor_icon()).
+    pub cursor_icon: CursorIcon,
+    /// The menu bar, see [Window::set_menu()](super::Window::set_menu()).
+    pub menu: Option<Menu>,
+    /// Shown on a right click, see [Window::set_context_menu()](super::Window::set_context_menu()).
+    pub context_menu: Option<Menu>,
+    /// See [Window::set_accelerators()](super::Window::set_accelerators()).
+    pub accelerators: AcceleratorTable,
+    /// The title bar drawn by the app, see [Window::set_chrome()](super::Window::set_chrome()).
+    pub chrome: Option<ChromeLayout>,
+    /// Which caption button of the chrome is hovered and pressed.
+    pub caption_buttons: CaptionButtonState,
+    /// Records the event stream while set, see [Window::start_recording()](super::Window::start_recording()).
+    pub recorder: Option<InputRecorder>,
+    /// Shared with the [EventLoopProxy](super::proxy::EventLoopProxy)'s of the window, set when it is destroyed.
+    pub closed: Arc<AtomicBool>,
+}
+
+impl WindowState {
+    /// `width` and `height` are the size of the client area.
+    pub fn new(width: i32, height: i32) -> WindowState {
+        return WindowState {
+            keyboard: Keyboard::new(),
+            mouse: Mouse::new(),
+            gestures: GestureRecognizer::new(GestureConfig::DEFAULT),
+            client_width: width,
+            client_height: height,
+            events: VecDeque::new(),
+            window_event_queue: vec![],
+            minimized: false,
+            maximized: false,
+            destroyed: false,
+            min_size: None,
+            max_size: None,
+            style: WINDOW_STYLE(0),
+            ex_style: WINDOW_EX_STYLE(0),
+            client_origin: ScreenPoint::default(),
+            message_time: 0,
+            dpi: BASE_DPI,
+            window_mode: WindowMode::Windowed,
+            windowed_placement: None,
+            cursor_icon: CursorIcon::Arrow,
+            menu: None,
+            context_menu: None,
+            accelerators: AcceleratorTable::new(),
+            chrome: None,
+            caption_buttons: CaptionButtonState::default(),
+            recorder: None,
+            closed: Arc::new(AtomicBool::new(false)),
+        };
+    }
+
+    /// The live client rect, used for the mouse bounds checks.
+    pub fn client_rect(&self) -> Rect {
+        return Rect::from_size(self.client_width, self.client_height);
+    }
+
+    /// The minimum and maximum size are both client sizes, see [frame_size()].
+    pub fn frame_size(&self) -> (i32, i32) {
+        return self.frame_size_for_dpi(self.dpi);
+    }
+
+    /// The frame size on a monitor with `dpi`. A window with a custom chrome has no frame, it is all client area.
+    pub fn frame_size_for_dpi(&self, dpi: u32) -> (i32, i32) {
+        if self.chrome.is_some() {
+            return (0, 0);
+        }
+        return frame_size(
+            self.style,
+            self.ex_style,
+            self.menu.is_some(),
+            &FrameMetrics::for_dpi(dpi),
+        );
+    }
+
+    /**
+        Which part of the window a point in client coordinates is in, see [ChromeLayout::hit_test()]. Without a
+        custom chrome, and in fullscreen, the whole client area is [HitTest::Client].
+    */
+    pub fn hit_test(&self, point: ClientPoint) -> HitTest {
+        let client: Rect = self.client_rect();
+        return match &self.chrome {
+            Some(chrome) if self.window_mode == WindowMode::Windowed => {
+                let resizable: bool = self.style.contains(WS_THICKFRAME) && !self.maximized;
+                chrome.hit_test(point, client, resizable)
+            }
+            _ if client.contains(point) => HitTest::Client,
+            _ => HitTest::Nowhere,
+        };
+    }
+
+    /// Change the caption buttons with `f`. Returns [WindowEvent::CaptionButtonsChanged] when they changed.
+    pub fn update_caption_buttons(
+        &mut self,
+        f: impl FnOnce(&mut CaptionButtonState),
+    ) -> Option<WindowEvent> {
+        let old: CaptionButtonState = self.caption_buttons;
+        f(&mut self.caption_buttons);
+        if self.caption_buttons == old {
+            return None;
+        }
+        return Some(WindowEvent::CaptionButtonsChanged {
+            hovered: self.caption_buttons.hovered,
+            pressed: self.caption_buttons.pressed,
+        });
+    }
+
+    /**
+        The menu item a key press activates, with the modifiers that are held down. The menu bar goes first, then the
+        context menu and then the accelerator table. Call it after the press is applied to the keyboard.
+    */
+    pub fn match_accelerator(&self, key_event: KeyEvent) -> Option<MenuId> {
+        if key_event.key_state != KeyState::Press {
+            return None;
+        }
+        let modifiers: Modifiers = Modifiers::from_keyboard(&self.keyboard);
+        let key_code: u32 = key_event.key_code;
+        return self
+            .menu
+            .iter()
+            .chain(self.context_menu.iter())
+            .find_map(|menu| menu.find_accelerator(key_code, modifiers))
+            .or_else(|| self.accelerators.find(key_code, modifiers));
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        return scale_factor_from_dpi(self.dpi);
+    }
+
+    /**
+        Switch to a new DPI. The size limits are scaled along so they stay the same in logical pixels. `width` and
+        `height` are the new client size, the client size itself is updated by the resize that follows.
+    */
+    pub fn on_dpi_changed(&mut self, dpi: u32, width: i32, height: i32) -> WindowEvent {
+        let scale = |(w, h): (i32, i32)| {
+            let size: PhysicalSize = PhysicalSize::new(w, h).scale_for_dpi(self.dpi, dpi);
+            return (size.width, size.height);
+        };
+        self.min_size = self.min_size.map(scale);
+        self.max_size = self.max_size.map(scale);
+        self.dpi = dpi;
+        return WindowEvent::ScaleFactorChanged {
+            scale_factor: scale_factor_from_dpi(dpi),
+            width,
+            height,
+        };
+    }
+
+    /**
+        Add an event to the event stream. When the app doesn't poll the events the oldest ones are dropped.
+        Window events also go to the [WindowEvent] queue of [Window::read_window_event()](super::Window::read_window_event()).
+    */
+    pub fn push_event(&mut self, event: Event) {
+        if let Event::Window(window_event) = event {
+            self.window_event_queue.push(window_event);
+            trim_buffer(&mut self.window_event_queue);
+        }
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(&event);
+        }
+        self.events.push_back(event);
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    pub fn push_window_event(&mut self, event: WindowEvent) {
+        self.push_event(Event::Window(event));
+    }
+
+    /// Store the new client size. Returns [WindowEvent::Resized] when the size changed.
+    pub fn on_resize(&mut self, width: i32, height: i32) -> Option<WindowEvent> {
+        if width == self.client_width && height == self.client_height {
+            return None;
+        }
+        self.client_width = width;
+        self.client_height = height;
+        return Some(WindowEvent::Resized { width, height });
+    }
+
+    /// Turn the wParam of WM_SIZE (SIZE_MINIMIZED, SIZE_MAXIMIZED, SIZE_RESTORED) into lifecycle events.
+    pub fn on_size_kind(&mut self, kind: u32) -> Option<WindowEvent> {
+        match kind {
+            SIZE_MINIMIZED if !self.minimized => {
+                self.minimized = true;
+                return Some(WindowEvent::Minimized);
+            }
+            SIZE_MAXIMIZED if !self.maximized => {
+                self.minimized = false;
+                self.maximized = true;
+                return Some(WindowEvent::Maximized);
+            }
+            SIZE_RESTORED if self.minimized || self.maximized => {
+                self.minimized = false;
+                self.maximized = false;
+                return Some(WindowEvent::Restored);
+            }
+            _ => return None,
+        }
+    }
+
+    pub fn on_destroy(&mut self) -> WindowEvent {
+        self.destroyed = true;
+        self.closed.store(true, Ordering::Release);
+        return WindowEvent::Destroyed;
+    }
+
+    /**
+        Update the keyboard, mouse and window state for an event that didn't come from a window message, and add it
+        to the event stream. This is how the [headless backend](super::backend::headless) delivers injected events,
+        so the state trackers see exactly what they would see with a real window.
+    */
+    pub fn apply_event(&mut self, event: Event) {
+        match event {
+            // Not a virtual-key code, translate() ignores these too
+            Event::Key(key) if key.key_code as usize >= KEY_CODE_COUNT => {}
+            Event::Key(key) => {
+                let event: KeyEvent = match key.key_state {
+                    KeyState::Press => self.keyboard.on_key_press(key.key_code),
+                    KeyState::Release => self.keyboard.on_key_release(key.key_code),
+                };
+                self.push_event(Event::Key(event));
+            }
+            Event::Char(char) => {
+                // The keyboard expects UTF-16 code units, just like WM_CHAR gives them
+                for unit in char.encode_utf16(&mut [0; 2]) {
+                    self.keyboard.on_char(*unit as u32);
+                }
+                self.push_event(Event::Char(char));
+            }
+            Event::Mouse(mouse) => {
+                let event: MouseEvent = match mouse.mouse_state {
+                    MouseState::LPress => self.mouse.on_left_press(mouse.pos),
+                    MouseState::LRelease => self.mouse.on_left_release(mouse.pos),
+                    MouseState::RPress => self.mouse.on_right_press(mouse.pos),
+                    MouseState::RRelease => self.mouse.on_right_release(mouse.pos),
+                    MouseState::WheelPress => self.mouse.on_wheel_press(mouse.pos),
+                    MouseState::WHeelRelease => self.mouse.on_wheel_release(mouse.pos),
+                    MouseState::Enter => self.mouse.on_mouse_enter(),
+                    MouseState::Leave => self.mouse.on_mouse_leave(),
+                    MouseState::Move => self.mouse.on_mouse_move(mouse.pos),
+                    MouseState::WheelUp | MouseState::WHeelDown => {
+                        // A single notch, use Event::Wheel for other deltas
+                        let delta: i16 = if mouse.mouse_state == MouseState::WheelUp {
+                            120
+                        } else {
+                            -120
+                        };
+                        self.mouse.on_wheel_delta(mouse.pos, delta);
+                        self.push_event(Event::Wheel {
+                            pos: mouse.pos,
+                            delta,
+                        });
+                        return;
+                    }
+                };
+                self.push_event(Event::Mouse(event));
+            }
+            Event::Wheel { pos, delta } => {
+                self.mouse.on_wheel_delta(pos, delta);
+                self.push_event(event);
+            }
+            Event::Window(window_event) => {
+                let event: Option<WindowEvent> = match window_event {
+                    WindowEvent::Resized { width, height } => self.on_resize(width, height),
+                    WindowEvent::Minimized => self.on_size_kind(SIZE_MINIMIZED),
+                    WindowEvent::Maximized => self.on_size_kind(SIZE_MAXIMIZED),
+                    WindowEvent::Restored => self.on_size_kind(SIZE_RESTORED),
+                    WindowEvent::Destroyed => Some(self.on_destroy()),
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        width,
+                        height,
+                    } => Some(self.on_dpi_changed(
+                        dpi_from_scale_factor(scale_factor),
+                        width,
+                        height,
+                    )),
+                    WindowEvent::FocusLost => {
+                        self.keyboard.reset();
+                        self.gestures.reset();
+                        Some(window_event)
+                    }
+                    WindowEvent::CaptionButtonsChanged { hovered, pressed } => self
+                        .update_caption_buttons(|buttons| {
+                            *buttons = CaptionButtonState { hovered, pressed };
+                        }),
+                    _ => Some(window_event),
+                };
+                if let Some(event) = event {
+                    self.push_window_event(event);
+                }
+            }
+            Event::Gesture(_)
+            | Event::DragDrop(_)
+            | Event::MenuActivated(_)
+            | Event::Timer(_)
+            | Event::User(_) => self.push_event(event),
+        }
+    }
+}
+
+fn trim_buffer<T>(buffer: &mut Vec<T>) {
+    while buffer.len() > MAX_BUFFER_SIZE {
+        buffer.remove(0);
+    }
+}
+
+/// The pointer we pass to CreateWindowEx. It holds one strong reference which is released in [release()].
+pub fn into_create_param(state: &Rc<RefCell<WindowState>>) -> *const std::ffi::c_void {
+    return Rc::into_raw(state.clone()) as *const std::ffi::c_void;
+}
+
+/**
+    Store the state pointer from the CREATESTRUCT in the GWLP_USERDATA of the window. Call this on WM_NCCREATE,
+    that is the first message a window receives.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-nccreate
+
+    # Safety
+    `lparam` has to be the lParam of WM_NCCREATE for a window created with [into_create_param()].
+*/
+pub unsafe fn attach(hwnd: HWND, lparam: LPARAM) {
+    let create_struct: *const CREATESTRUCTW = lparam.0 as *const CREATESTRUCTW;
+    if create_struct.is_null() {
+        return;
+    }
+    SetWindowLongPtrW(
+        hwnd,
+        GWLP_USERDATA,
+        (*create_struct).lpCreateParams as isize,
+    );
+}
+
+/**
+    Get the state of a window. Returns [None] for messages that arrive before WM_NCCREATE or after WM_NCDESTROY.
+
+    # Safety
+    The GWLP_USERDATA of `hwnd` has to be set by [attach()] or be 0. Don't keep the reference past WM_NCDESTROY.
+*/
+pub unsafe fn lookup<'a>(hwnd: HWND) -> Option<&'a RefCell<WindowState>> {
+    let ptr: *const RefCell<WindowState> =
+        GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const RefCell<WindowState>;
+    if ptr.is_null() {
+        return None;
+    }
+    return Some(&*ptr);
+}
+
+/**
+    Drop the reference the window holds to its state. Call this on WM_NCDESTROY, that is the last message a window
+    receives. The [Window](super::Window) still holds its own reference so the state stays readable.
+
+    For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-ncdestroy
+
+    # Safety
+    The GWLP_USERDATA of `hwnd` has to be set by [attach()] or be 0, and no reference from [lookup()] may be alive.
+*/
+pub unsafe fn release(hwnd: HWND) {
+    let ptr: *const RefCell<WindowState> =
+        SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0) as *const RefCell<WindowState>;
+    if !ptr.is_null() {
+        drop(Rc::from_raw(ptr));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_kinds_turn_into_lifecycle_events() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        // Windows sends SIZE_MINIMIZED again when the minimized window is moved, that is no new event
+        let kinds: [u32; 6] = [
+            SIZE_MINIMIZED,
+            SIZE_MINIMIZED,
+            SIZE_RESTORED,
+            SIZE_RESTORED,
+            SIZE_MAXIMIZED,
+            SIZE_RESTORED,
+        ];
+        let events: Vec<WindowEvent> = kinds
+            .into_iter()
+            .filter_map(|kind| state.on_size_kind(kind))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                WindowEvent::Minimized,
+                WindowEvent::Restored,
+                WindowEvent::Maximized,
+                WindowEvent::Restored,
+            ]
+        );
+    }
+
+    #[test]
+    fn a_close_request_is_only_an_event() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        // What the wndproc does on WM_CLOSE, the window stays until the app calls Window::close()
+        state.push_window_event(WindowEvent::CloseRequested);
+        assert!(!state.destroyed);
+
+        let event: WindowEvent = state.on_destroy();
+        state.push_window_event(event);
+        assert!(state.destroyed);
+        assert_eq!(
+            state.window_event_queue,
+            vec![WindowEvent::CloseRequested, WindowEvent::Destroyed]
+        );
+    }
+
+    #[test]
+    fn the_event_stream_drops_the_oldest_events() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        for index in 0..MAX_EVENTS + 2 {
+            state.push_event(Event::Char(char::from_u32(0x41 + index as u32).unwrap()));
+        }
+        assert_eq!(state.events.len(), MAX_EVENTS);
+        assert_eq!(state.events.front(), Some(&Event::Char('C')));
+    }
+}
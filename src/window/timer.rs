@@ -0,0 +1,514 @@
+use std::{
+    cell::Cell,
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Where [Timers] get the time from. Swap it for a [ManualClock] in tests or while replaying a recording.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real time.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        return Instant::now();
+    }
+}
+
+/**
+    A clock that only moves when it is told to. Clones share the same time, so keep a clone to move the clock of
+    the [Timers] it was given to:
+    ```ignore
+    let clock: ManualClock = ManualClock::new();
+    window.timers().set_clock(Rc::new(clock.clone()));
+    clock.advance(Duration::from_secs(30));
+    ```
+*/
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Rc<Cell<Instant>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        return ManualClock {
+            now: Rc::new(Cell::new(Instant::now())),
+        };
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    /// Move the clock to `now`. A time in the past is ignored, the clock never goes back.
+    pub fn set(&self, now: Instant) {
+        self.now.set(self.now.get().max(now));
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        return ManualClock::new();
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        return self.now.get();
+    }
+}
+
+/// The shortest interval of a repeating timer, a shorter one would keep [Window::handle_messages()](super::Window::handle_messages()) from ever waiting.
+pub const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// The handle of a timer, used to cancel, pause and resume it. Also the payload of [Event::Timer](super::event::Event::Timer).
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone)]
+pub struct TimerId(pub u64);
+
+struct TimerEntry {
+    /// [None] for a one-shot timer.
+    interval: Option<Duration>,
+    deadline: Instant,
+    /// The time that was left when the timer was paused.
+    paused: Option<Duration>,
+    /// Runs instead of sending an event.
+    callback: Option<Box<dyn FnMut(TimerId)>>,
+    /// Changes every time the deadline changes, heap entries with an old generation are skipped.
+    generation: u64,
+}
+
+/**
+    One-shot and repeating timers for a [Window](super::Window), see [Window::timers()](super::Window::timers()).
+    ```ignore
+    let autosave: TimerId = window.timers().repeat(Duration::from_secs(30));
+    for event in window.poll_events() {
+        if event == Event::Timer(autosave) {
+            save();
+        }
+    }
+    ```
+    The deadlines are kept in a min-heap. [Window::handle_messages()](super::Window::handle_messages()) never waits
+    past the next deadline, so timers fire on time with [ControlFlow::Wait](super::control_flow::ControlFlow::Wait)
+    as well. A timer fires once per [Timers::fire_due()] at most: a repeating timer that fell behind is moved to its
+    next deadline after now instead of firing for every interval it missed.
+
+    This doesn't use SetTimer: WM_TIMER is a low priority message which windows only generates when the queue is
+    empty and it has a resolution of ~15.6ms, a wait with a deadline is both faster and more precise. Timers also
+    keep working on the headless backend this way.
+*/
+pub struct Timers {
+    clock: Rc<dyn Clock>,
+    timers: HashMap<TimerId, TimerEntry>,
+    heap: BinaryHeap<Reverse<(Instant, u64, TimerId)>>,
+    next_id: u64,
+    next_generation: u64,
+}
+
+impl Timers {
+    pub fn new(clock: Rc<dyn Clock>) -> Timers {
+        return Timers {
+            clock,
+            timers: HashMap::new(),
+            heap: BinaryHeap::new(),
+            next_id: 0,
+            next_generation: 0,
+        };
+    }
+
+    /// The time according to the clock of the timers.
+    pub fn now(&self) -> Instant {
+        return self.clock.now();
+    }
+
+    /**
+        Use another clock. The timers keep the time they had left, measured on the old clock. Pass a [ManualClock]
+        driven by [Replayer::time()](super::recording::Replayer::time()) to make the timers part of a deterministic
+        replay.
+    */
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        let old_now: Instant = self.clock.now();
+        let new_now: Instant = clock.now();
+        self.clock = clock;
+
+        let ids: Vec<TimerId> = self.timers.keys().copied().collect();
+        for id in ids {
+            let entry: &TimerEntry = &self.timers[&id];
+            if entry.paused.is_none() {
+                let remaining: Duration = entry.deadline.saturating_duration_since(old_now);
+                self.schedule(id, new_now + remaining);
+            }
+        }
+    }
+
+    /// Send an [Event::Timer](super::event::Event::Timer) once after `delay`.
+    pub fn once(&mut self, delay: Duration) -> TimerId {
+        return self.add(delay, None, None);
+    }
+
+    /// Send an [Event::Timer](super::event::Event::Timer) every `interval`, starting one interval from now. An interval below [MIN_INTERVAL] is raised to it.
+    pub fn repeat(&mut self, interval: Duration) -> TimerId {
+        return self.add(interval, Some(interval), None);
+    }
+
+    /// Call `callback` once after `delay` instead of sending an event.
+    pub fn once_with(
+        &mut self,
+        delay: Duration,
+        callback: impl FnMut(TimerId) + 'static,
+    ) -> TimerId {
+        return self.add(delay, None, Some(Box::new(callback)));
+    }
+
+    /// Call `callback` every `interval` instead of sending an event. An interval below [MIN_INTERVAL] is raised to it.
+    pub fn repeat_with(
+        &mut self,
+        interval: Duration,
+        callback: impl FnMut(TimerId) + 'static,
+    ) -> TimerId {
+        return self.add(interval, Some(interval), Some(Box::new(callback)));
+    }
+
+    /// Stop a timer for good. Returns false when it already fired (one-shot) or was cancelled.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        // The heap entry stays behind and is skipped when it comes up
+        return self.timers.remove(&id).is_some();
+    }
+
+    pub fn is_active(&self, id: TimerId) -> bool {
+        return self.timers.contains_key(&id);
+    }
+
+    /// Stop the time of a timer until [Timers::resume()]. Returns false for an unknown timer.
+    pub fn pause(&mut self, id: TimerId) -> bool {
+        let now: Instant = self.clock.now();
+        return match self.timers.get_mut(&id) {
+            Some(entry) => {
+                if entry.paused.is_none() {
+                    entry.paused = Some(entry.deadline.saturating_duration_since(now));
+                    // Invalidates the heap entry
+                    entry.generation = u64::MAX;
+                }
+                true
+            }
+            None => false,
+        };
+    }
+
+    /// Continue a paused timer with the time it had left. Returns false for an unknown timer.
+    pub fn resume(&mut self, id: TimerId) -> bool {
+        let now: Instant = self.clock.now();
+        let remaining: Option<Duration> = match self.timers.get_mut(&id) {
+            Some(entry) => entry.paused.take(),
+            None => return false,
+        };
+        if let Some(remaining) = remaining {
+            self.schedule(id, now + remaining);
+        }
+        return true;
+    }
+
+    pub fn is_paused(&self, id: TimerId) -> bool {
+        return self
+            .timers
+            .get(&id)
+            .is_some_and(|entry| entry.paused.is_some());
+    }
+
+    /// Pause every timer, like when the app is minimized.
+    pub fn pause_all(&mut self) {
+        let ids: Vec<TimerId> = self.timers.keys().copied().collect();
+        for id in ids {
+            self.pause(id);
+        }
+    }
+
+    pub fn resume_all(&mut self) {
+        let ids: Vec<TimerId> = self.timers.keys().copied().collect();
+        for id in ids {
+            self.resume(id);
+        }
+    }
+
+    /// The earliest deadline of the running timers.
+    pub fn next_deadline(&mut self) -> Option<Instant> {
+        self.drop_stale();
+        return self.heap.peek().map(|Reverse((deadline, _, _))| *deadline);
+    }
+
+    /**
+        Handle the timers whose deadline has passed, in deadline order. Timers with a callback are called, the ids
+        of the other timers are returned so they can be sent as events.
+    */
+    pub fn fire_due(&mut self) -> Vec<TimerId> {
+        let now: Instant = self.clock.now();
+        let mut due: Vec<TimerId> = vec![];
+        // Rescheduling while popping could fire a short repeating timer more than once
+        let mut fired: Vec<(TimerId, Instant)> = vec![];
+
+        loop {
+            self.drop_stale();
+            match self.heap.peek() {
+                Some(Reverse((deadline, _, _))) if *deadline <= now => {}
+                _ => break,
+            }
+            let Reverse((deadline, _, id)) = self.heap.pop().unwrap();
+            fired.push((id, deadline));
+        }
+
+        for (id, deadline) in fired {
+            let interval: Option<Duration> = match self.timers.get(&id) {
+                Some(entry) => entry.interval,
+                None => continue,
+            };
+            let mut callback: Option<Box<dyn FnMut(TimerId)>> = match interval {
+                Some(interval) => {
+                    self.schedule(id, next_deadline(deadline, interval, now));
+                    self.timers.get_mut(&id).unwrap().callback.take()
+                }
+                None => self.timers.remove(&id).unwrap().callback,
+            };
+
+            match callback.as_mut() {
+                Some(callback) => callback(id),
+                None => due.push(id),
+            }
+            // Give a repeating callback back, unless it was cancelled in the meantime
+            if let (Some(callback), Some(entry)) = (callback, self.timers.get_mut(&id)) {
+                entry.callback = Some(callback);
+            }
+        }
+        return due;
+    }
+
+    fn add(
+        &mut self,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: Option<Box<dyn FnMut(TimerId)>>,
+    ) -> TimerId {
+        self.next_id += 1;
+        let id: TimerId = TimerId(self.next_id);
+        let interval: Option<Duration> = interval.map(|interval| interval.max(MIN_INTERVAL));
+        let delay: Duration = interval.unwrap_or(delay);
+        let deadline: Instant = self.clock.now() + delay;
+        self.timers.insert(
+            id,
+            TimerEntry {
+                interval,
+                deadline,
+                paused: None,
+                callback,
+                generation: 0,
+            },
+        );
+        self.schedule(id, deadline);
+        return id;
+    }
+
+    fn schedule(&mut self, id: TimerId, deadline: Instant) {
+        self.next_generation += 1;
+        if let Some(entry) = self.timers.get_mut(&id) {
+            entry.deadline = deadline;
+            entry.generation = self.next_generation;
+            self.heap
+                .push(Reverse((deadline, self.next_generation, id)));
+        }
+    }
+
+    /// Pop the heap entries of cancelled, paused and rescheduled timers.
+    fn drop_stale(&mut self) {
+        while let Some(Reverse((_, generation, id))) = self.heap.peek() {
+            let current: bool = self
+                .timers
+                .get(id)
+                .is_some_and(|entry| entry.generation == *generation);
+            if current {
+                return;
+            }
+            self.heap.pop();
+        }
+    }
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        return Timers::new(Rc::new(SystemClock));
+    }
+}
+
+/// The first deadline of a repeating timer after `now`, keeping the phase of the original deadline.
+pub fn next_deadline(deadline: Instant, interval: Duration, now: Instant) -> Instant {
+    if interval.is_zero() {
+        return now;
+    }
+    let next: Instant = deadline + interval;
+    if next > now {
+        return next;
+    }
+    // Skip the intervals we missed
+    let behind: u128 = (now - deadline).as_nanos() / interval.as_nanos();
+    return deadline + interval * (behind.min(u32::MAX as u128 - 1) as u32 + 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn manual_timers() -> (Timers, ManualClock) {
+        let clock: ManualClock = ManualClock::new();
+        let timers: Timers = Timers::new(Rc::new(clock.clone()));
+        return (timers, clock);
+    }
+
+    #[test]
+    fn one_shot() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let id: TimerId = timers.once(Duration::from_millis(100));
+        assert_eq!(
+            timers.next_deadline(),
+            Some(clock.now() + Duration::from_millis(100))
+        );
+
+        clock.advance(Duration::from_millis(99));
+        assert!(timers.fire_due().is_empty());
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(timers.fire_due(), vec![id]);
+        assert!(!timers.is_active(id));
+        assert_eq!(timers.next_deadline(), None);
+        clock.advance(Duration::from_millis(100));
+        assert!(timers.fire_due().is_empty());
+    }
+
+    #[test]
+    fn repeating() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let start: Instant = clock.now();
+        let id: TimerId = timers.repeat(Duration::from_millis(10));
+
+        clock.advance(Duration::from_millis(10));
+        assert_eq!(timers.fire_due(), vec![id]);
+        assert_eq!(
+            timers.next_deadline(),
+            Some(start + Duration::from_millis(20))
+        );
+
+        // Missed intervals fire once and keep the phase
+        clock.advance(Duration::from_millis(35));
+        assert_eq!(timers.fire_due(), vec![id]);
+        assert!(timers.fire_due().is_empty());
+        assert_eq!(
+            timers.next_deadline(),
+            Some(start + Duration::from_millis(50))
+        );
+    }
+
+    #[test]
+    fn repeating_callback() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let calls: Rc<RefCell<Vec<TimerId>>> = Rc::new(RefCell::new(vec![]));
+        let calls_clone: Rc<RefCell<Vec<TimerId>>> = calls.clone();
+        let id: TimerId = timers.repeat_with(Duration::from_millis(10), move |id| {
+            calls_clone.borrow_mut().push(id);
+        });
+
+        for _ in 0..3 {
+            clock.advance(Duration::from_millis(10));
+            assert!(timers.fire_due().is_empty());
+        }
+        assert_eq!(*calls.borrow(), vec![id, id, id]);
+        assert!(timers.is_active(id));
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let id: TimerId = timers.once(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(40));
+        assert!(timers.pause(id));
+        assert!(timers.is_paused(id));
+        assert_eq!(timers.next_deadline(), None);
+
+        // The time while paused doesn't count
+        clock.advance(Duration::from_millis(500));
+        assert!(timers.fire_due().is_empty());
+        assert!(timers.resume(id));
+        assert!(!timers.is_paused(id));
+        assert_eq!(
+            timers.next_deadline(),
+            Some(clock.now() + Duration::from_millis(60))
+        );
+        clock.advance(Duration::from_millis(60));
+        assert_eq!(timers.fire_due(), vec![id]);
+
+        assert!(!timers.pause(id));
+        assert!(!timers.resume(id));
+    }
+
+    #[test]
+    fn pause_all_and_resume_all() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let first: TimerId = timers.once(Duration::from_millis(10));
+        let second: TimerId = timers.repeat(Duration::from_millis(20));
+        timers.pause_all();
+        clock.advance(Duration::from_millis(100));
+        assert!(timers.fire_due().is_empty());
+
+        timers.resume_all();
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(timers.fire_due(), vec![first, second]);
+    }
+
+    #[test]
+    fn cancel() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let cancelled: TimerId = timers.repeat(Duration::from_millis(10));
+        let kept: TimerId = timers.once(Duration::from_millis(20));
+        assert!(timers.cancel(cancelled));
+        assert!(!timers.cancel(cancelled));
+        assert!(!timers.is_active(cancelled));
+        assert_eq!(
+            timers.next_deadline(),
+            Some(clock.now() + Duration::from_millis(20))
+        );
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(timers.fire_due(), vec![kept]);
+        assert!(!timers.cancel(kept));
+    }
+
+    #[test]
+    fn zero_interval_gets_the_minimum() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let id: TimerId = timers.repeat(Duration::ZERO);
+        assert_eq!(timers.next_deadline(), Some(clock.now() + MIN_INTERVAL));
+        assert!(timers.fire_due().is_empty());
+
+        clock.advance(MIN_INTERVAL);
+        assert_eq!(timers.fire_due(), vec![id]);
+        assert_eq!(timers.next_deadline(), Some(clock.now() + MIN_INTERVAL));
+    }
+
+    #[test]
+    fn set_clock_keeps_the_remaining_time() {
+        let (mut timers, clock): (Timers, ManualClock) = manual_timers();
+        let id: TimerId = timers.once(Duration::from_millis(100));
+        clock.advance(Duration::from_millis(30));
+
+        let other: ManualClock = ManualClock::new();
+        other.advance(Duration::from_secs(5));
+        timers.set_clock(Rc::new(other.clone()));
+        assert_eq!(
+            timers.next_deadline(),
+            Some(other.now() + Duration::from_millis(70))
+        );
+        other.advance(Duration::from_millis(70));
+        assert_eq!(timers.fire_due(), vec![id]);
+    }
+}
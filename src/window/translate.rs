@@ -0,0 +1,505 @@
+use windows::Win32::{
+    Foundation::{LPARAM, POINTS, WPARAM},
+    UI::WindowsAndMessaging::{
+        SIZE_MINIMIZED, WA_INACTIVE, WM_ACTIVATE, WM_CHAR, WM_CLIPBOARDUPDATE, WM_CLOSE,
+        WM_COMMAND, WM_DESTROY, WM_KEYDOWN, WM_KEYUP, WM_KILLFOCUS, WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MBUTTONDOWN, WM_MBUTTONUP, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_MOVE, WM_PAINT,
+        WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE, WM_RBUTTONDOWN, WM_RBUTTONUP,
+        WM_SHOWWINDOW, WM_SIZE, WM_SYSKEYDOWN, WM_SYSKEYUP, WS_CHILD,
+    },
+};
+
+use super::{
+    chrome::HitTest,
+    coords::{ClientPoint, ScreenPoint},
+    event::{Event, WindowEvent},
+    gesture::{Contact, ContactPhase},
+    get_wheel_delta_wparam,
+    keyboard::{KeyEvent, KEY_CODE_COUNT},
+    make_points,
+    menu::MenuId,
+    mouse::MouseEvent,
+    state::WindowState,
+};
+
+const MK_LBUTTON: i32 = 0x0001;
+const MK_RBUTTON: i32 = 0x0002;
+const MK_CONTROL: i32 = 0x0008;
+const MK_MBUTTON: i32 = 0x0010;
+
+/**
+    Decode a window message. This updates the keyboard, mouse and gesture trackers of `state` and returns the
+    events the message produced, in order. It doesn't call Win32 and doesn't add the events to the event stream,
+    so it can be fed made up messages, see [Window::inject()](super::Window::inject()).
+
+    A few things need the real window, the caller takes care of those:
+    - Set [WindowState::client_origin] and [WindowState::message_time] before translating WM_MOUSEWHEEL and the
+      pointer messages. WM_MOVE only updates the client origin of windows without WS_CHILD, the caller keeps the
+      origin of a child window in screen coordinates up to date (on Win32 with ClientToScreen).
+    - Capture the mouse on a [MouseState::Enter](super::mouse::MouseState::Enter) event and release it on a
+      [MouseState::Leave](super::mouse::MouseState::Leave) event.
+
+    Messages we don't know give no events.
+*/
+pub fn translate(msg: u32, wparam: WPARAM, lparam: LPARAM, state: &mut WindowState) -> Vec<Event> {
+    let mut events: Vec<Event> = vec![];
+
+    match msg {
+        // General window messages
+        WM_KILLFOCUS => {
+            // Keys that are released while we don't have focus never send WM_KEYUP
+            state.keyboard.reset();
+            state.gestures.reset();
+        }
+        WM_SIZE => {
+            /*
+                The low word of the lParam is the new width and the high word the new height of the client
+                area. When the window is minimized the size is 0x0, we keep the last size in that case.
+
+                For more info see: https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-size
+            */
+            events.extend(state.on_size_kind(wparam.0 as u32).map(Event::Window));
+            if wparam.0 as u32 != SIZE_MINIMIZED {
+                let width: i32 = (lparam.0 & 0xFFFF) as i32;
+                let height: i32 = ((lparam.0 >> 16) & 0xFFFF) as i32;
+                events.extend(state.on_resize(width, height).map(Event::Window));
+            }
+        }
+        WM_MOVE => {
+            // The position of the client area, as signed words. See https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-move
+            let points: POINTS = make_points(lparam);
+            // A child window gets a position relative to the client area of its parent, not the screen
+            if !state.style.contains(WS_CHILD) {
+                state.client_origin = points.into();
+            }
+            events.push(Event::Window(WindowEvent::Moved {
+                x: points.x.into(),
+                y: points.y.into(),
+            }));
+        }
+        WM_ACTIVATE => {
+            // The low word of the wParam is WA_INACTIVE when the window lost focus. See https://learn.microsoft.com/en-us/windows/win32/inputdev/wm-activate
+            let event: WindowEvent = if (wparam.0 & 0xFFFF) as u32 == WA_INACTIVE {
+                WindowEvent::FocusLost
+            } else {
+                WindowEvent::FocusGained
+            };
+            events.push(Event::Window(event));
+        }
+        WM_SHOWWINDOW => {
+            // See https://learn.microsoft.com/en-us/windows/win32/winmsg/wm-showwindow
+            let event: WindowEvent = if wparam.0 != 0 {
+                WindowEvent::Shown
+            } else {
+                WindowEvent::Hidden
+            };
+            events.push(Event::Window(event));
+        }
+        WM_CLOSE => {
+            // Don't destroy the window yet, the app decides with Window::close()
+            events.push(Event::Window(WindowEvent::CloseRequested));
+        }
+        WM_DESTROY => {
+            events.push(Event::Window(state.on_destroy()));
+        }
+        WM_PAINT => {
+            // See https://learn.microsoft.com/en-us/windows/win32/gdi/wm-paint
+            events.push(Event::Window(WindowEvent::RedrawRequested));
+        }
+        WM_CLIPBOARDUPDATE => {
+            // See https://learn.microsoft.com/en-us/windows/win32/dataxchg/wm-clipboardupdate
+            events.push(Event::Window(WindowEvent::ClipboardChanged));
+        }
+        /*
+            The low word of the wParam is the id. The high word is 0 for a menu item and 1 for an accelerator,
+            notifications of child controls have the HWND of the control in the lParam.
+
+            For more info see: https://learn.microsoft.com/en-us/windows/win32/menurc/wm-command
+        */
+        WM_COMMAND if lparam.0 == 0 && (wparam.0 >> 16) & 0xFFFF <= 1 => {
+            events.push(Event::MenuActivated(MenuId((wparam.0 & 0xFFFF) as u16)));
+        }
+
+        // Keyboard messages
+        WM_CHAR => {
+            events.extend(state.keyboard.on_char(wparam.0 as u32).map(Event::Char));
+        }
+        // The key state map only has room for the virtual-key codes, anything else is not a key
+        WM_KEYDOWN | WM_SYSKEYDOWN if wparam.0 < KEY_CODE_COUNT => {
+            // See https://learn.microsoft.com/en-us/windows/win32/inputdev/about-keyboard-input#keystroke-message-flags
+            let auto_repeat: bool = (lparam.0 >> 30) & 1 == 1;
+            if auto_repeat {
+                state.keyboard.enable_auto_repeat();
+            }
+            let key_event: KeyEvent = state.keyboard.on_key_press(wparam.0 as u32);
+            events.push(Event::Key(key_event));
+            // Our own accelerators instead of TranslateAccelerator, the menu model can change at any time
+            events.extend(state.match_accelerator(key_event).map(Event::MenuActivated));
+        }
+        WM_KEYUP | WM_SYSKEYUP if wparam.0 < KEY_CODE_COUNT => {
+            state.keyboard.disable_auto_repeat();
+            events.push(Event::Key(state.keyboard.on_key_release(wparam.0 as u32)));
+        }
+
+        // Mouse messages
+        WM_MOUSEMOVE => {
+            // WM_MOUSEMOVE is in client coordinates
+            let pos: ClientPoint = make_points(lparam).into();
+            // The title bar and borders of a custom chrome count as outside, so the capture is released there and
+            // windows can move and resize the window
+            if state.hit_test(pos) == HitTest::Client {
+                events.push(Event::Mouse(state.mouse.on_mouse_move(pos)));
+                if !state.mouse.is_in_window {
+                    // The caller captures the mouse so we keep getting WM_MOUSEMOVE outside of the window
+                    events.push(Event::Mouse(state.mouse.on_mouse_enter()));
+                }
+            } else if (wparam.0 as i32) & (MK_LBUTTON | MK_MBUTTON | MK_RBUTTON) > 0 {
+                // Keep tracking a drag that left the window
+                events.push(Event::Mouse(state.mouse.on_mouse_move(pos)));
+            } else {
+                events.push(Event::Mouse(state.mouse.on_mouse_leave()));
+            }
+        }
+        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_RBUTTONDOWN | WM_RBUTTONUP | WM_MBUTTONDOWN
+        | WM_MBUTTONUP => {
+            // The button messages are in client coordinates, a click doesn't always come after a WM_MOUSEMOVE
+            let pos: ClientPoint = make_points(lparam).into();
+            let event: MouseEvent = match msg {
+                WM_LBUTTONDOWN => state.mouse.on_left_press(pos),
+                WM_LBUTTONUP => state.mouse.on_left_release(pos),
+                WM_RBUTTONDOWN => state.mouse.on_right_press(pos),
+                WM_RBUTTONUP => state.mouse.on_right_release(pos),
+                WM_MBUTTONDOWN => state.mouse.on_wheel_press(pos),
+                _ => state.mouse.on_wheel_release(pos),
+            };
+            events.push(Event::Mouse(event));
+        }
+        WM_MOUSEWHEEL => {
+            // WM_MOUSEWHEEL is in screen coordinates, unlike the other mouse messages
+            let screen_pos: ScreenPoint = make_points(lparam).into();
+            let pos: ClientPoint = screen_pos.to_client(state.client_origin);
+            let delta: i16 = get_wheel_delta_wparam(wparam);
+
+            if (wparam.0 as i32) & MK_CONTROL > 0 {
+                // Ctrl + wheel zooms, just like pinching on a touch screen
+                state
+                    .gestures
+                    .on_wheel_zoom(pos.x as f32, pos.y as f32, delta);
+            } else {
+                state.mouse.on_wheel_delta(pos, delta);
+                events.push(Event::Wheel { pos, delta });
+            }
+        }
+
+        // Pointer (touch) messages
+        WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP => {
+            /*
+                The low word of the wParam is the pointer id and the lParam holds the screen coordinates
+                of the pointer. We convert them to client coordinates so they match the mouse messages.
+
+                For more info see: https://learn.microsoft.com/en-us/windows/win32/inputmsg/wm-pointerupdate
+            */
+            let screen_pos: ScreenPoint = make_points(lparam).into();
+            let point: ClientPoint = screen_pos.to_client(state.client_origin);
+
+            let phase: ContactPhase = match msg {
+                WM_POINTERDOWN => ContactPhase::Down,
+                WM_POINTERUP => ContactPhase::Up,
+                _ => ContactPhase::Move,
+            };
+            let time: u32 = state.message_time;
+            state.gestures.on_contact(Contact {
+                id: (wparam.0 & 0xFFFF) as u32,
+                phase,
+                x: point.x as f32,
+                y: point.y as f32,
+                time,
+            });
+            state.gestures.update(time);
+        }
+
+        _ => {}
+    }
+
+    events.extend(
+        state
+            .gestures
+            .take_new_events()
+            .into_iter()
+            .map(Event::Gesture),
+    );
+    return events;
+}
+
+/**
+    True for the messages [translate()] handles completely, the wndproc returns 0 for those. Everything else still
+    goes to DefWindowProc, like WM_ACTIVATE (which sets the keyboard focus) and the pointer messages (which generate
+    the legacy mouse messages).
+*/
+pub fn is_handled(msg: u32) -> bool {
+    return matches!(
+        msg,
+        WM_KILLFOCUS
+            | WM_SIZE
+            | WM_MOVE
+            | WM_SHOWWINDOW
+            | WM_CLOSE
+            | WM_DESTROY
+            | WM_CLIPBOARDUPDATE
+            | WM_COMMAND
+            | WM_CHAR
+            | WM_KEYDOWN
+            | WM_SYSKEYDOWN
+            | WM_KEYUP
+            | WM_SYSKEYUP
+            | WM_MOUSEMOVE
+            | WM_LBUTTONDOWN
+            | WM_LBUTTONUP
+            | WM_RBUTTONDOWN
+            | WM_RBUTTONUP
+            | WM_MBUTTONDOWN
+            | WM_MBUTTONUP
+            | WM_MOUSEWHEEL
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::{
+        keyboard::{KeyEvent, KeyState},
+        mouse::MouseState,
+    };
+    use windows::Win32::UI::Input::KeyboardAndMouse::VK_A;
+
+    /// An lParam with `x` in the low and `y` in the high word, like MAKELPARAM.
+    fn points(x: i16, y: i16) -> LPARAM {
+        return LPARAM(((y as u16 as isize) << 16) | x as u16 as isize);
+    }
+
+    fn wheel(delta: i16, keys: i32) -> WPARAM {
+        return WPARAM(((delta as u16 as usize) << 16) | keys as usize);
+    }
+
+    fn mouse_states(events: &[Event]) -> Vec<MouseState> {
+        return events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Mouse(mouse) => Some(mouse.mouse_state),
+                _ => None,
+            })
+            .collect();
+    }
+
+    #[test]
+    fn enter_and_leave() {
+        let mut state: WindowState = WindowState::new(800, 600);
+
+        // The first move inside enters, the caller captures the mouse on that
+        let events: Vec<Event> = translate(WM_MOUSEMOVE, WPARAM(0), points(10, 20), &mut state);
+        assert_eq!(
+            mouse_states(&events),
+            vec![MouseState::Move, MouseState::Enter]
+        );
+        assert!(state.mouse.is_in_window);
+        let events: Vec<Event> = translate(WM_MOUSEMOVE, WPARAM(0), points(11, 20), &mut state);
+        assert_eq!(mouse_states(&events), vec![MouseState::Move]);
+
+        // Thanks to the capture we see the cursor leave, the caller releases the capture on that
+        let events: Vec<Event> = translate(WM_MOUSEMOVE, WPARAM(0), points(-3, 20), &mut state);
+        assert_eq!(mouse_states(&events), vec![MouseState::Leave]);
+        assert!(!state.mouse.is_in_window);
+        assert_eq!(state.mouse.pos, ClientPoint { x: 11, y: 20 });
+    }
+
+    #[test]
+    fn a_drag_keeps_tracking_outside() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        translate(WM_MOUSEMOVE, WPARAM(0), points(10, 20), &mut state);
+        translate(
+            WM_LBUTTONDOWN,
+            WPARAM(MK_LBUTTON as usize),
+            points(10, 20),
+            &mut state,
+        );
+
+        let events: Vec<Event> = translate(
+            WM_MOUSEMOVE,
+            WPARAM(MK_LBUTTON as usize),
+            points(900, -40),
+            &mut state,
+        );
+        assert_eq!(mouse_states(&events), vec![MouseState::Move]);
+        assert_eq!(state.mouse.pos, ClientPoint { x: 900, y: -40 });
+        assert!(state.mouse.is_in_window);
+
+        let events: Vec<Event> = translate(WM_LBUTTONUP, WPARAM(0), points(900, -40), &mut state);
+        assert_eq!(mouse_states(&events), vec![MouseState::LRelease]);
+        let events: Vec<Event> = translate(WM_MOUSEMOVE, WPARAM(0), points(901, -40), &mut state);
+        assert_eq!(mouse_states(&events), vec![MouseState::Leave]);
+    }
+
+    #[test]
+    fn wheel_carry() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        translate(WM_MOVE, WPARAM(0), points(100, 50), &mut state);
+
+        // Half a notch at a time, like a precision touchpad
+        let events: Vec<Event> =
+            translate(WM_MOUSEWHEEL, wheel(60, 0), points(110, 70), &mut state);
+        let pos: ClientPoint = ClientPoint { x: 10, y: 20 };
+        assert_eq!(events, vec![Event::Wheel { pos, delta: 60 }]);
+        assert!(state.mouse.event_queue.is_empty());
+
+        translate(WM_MOUSEWHEEL, wheel(60, 0), points(110, 70), &mut state);
+        assert_eq!(state.mouse.wheel_delta_carry, 0);
+        assert_eq!(state.mouse.event_queue.len(), 1);
+        assert_eq!(state.mouse.event_queue[0].mouse_state, MouseState::WheelUp);
+        assert_eq!(state.mouse.event_queue[0].pos, pos);
+
+        // Two and a bit notches down, the rest is carried to the next message
+        translate(WM_MOUSEWHEEL, wheel(-250, 0), points(110, 70), &mut state);
+        assert_eq!(state.mouse.wheel_delta_carry, -10);
+        let states: Vec<MouseState> = state
+            .mouse
+            .event_queue
+            .iter()
+            .map(|event| event.mouse_state)
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                MouseState::WheelUp,
+                MouseState::WHeelDown,
+                MouseState::WHeelDown
+            ]
+        );
+        translate(WM_MOUSEWHEEL, wheel(-110, 0), points(110, 70), &mut state);
+        assert_eq!(state.mouse.wheel_delta_carry, 0);
+        assert_eq!(state.mouse.event_queue.len(), 4);
+    }
+
+    #[test]
+    fn ctrl_wheel_zooms() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        let events: Vec<Event> = translate(
+            WM_MOUSEWHEEL,
+            wheel(120, MK_CONTROL),
+            points(30, 40),
+            &mut state,
+        );
+
+        assert_eq!(events.len(), 1);
+        let Event::Gesture(gesture) = events[0] else {
+            panic!("expected a gesture, got {:?}", events[0]);
+        };
+        assert_eq!((gesture.x, gesture.y), (30.0, 40.0));
+        assert!((gesture.scale - 1.1).abs() < 1e-6);
+        assert_eq!(state.mouse.wheel_delta_carry, 0);
+    }
+
+    #[test]
+    fn auto_repeat() {
+        let mut state: WindowState = WindowState::new(800, 600);
+        let key: WPARAM = WPARAM(VK_A.0 as usize);
+
+        let first: Vec<Event> = translate(WM_KEYDOWN, key, LPARAM(1), &mut state);
+        assert!(!state.keyboard.auto_repeat_enabled);
+        // Bit 30 is the previous key state, set for the repeats while the key is held
+        let repeat: Vec<Event> = translate(WM_KEYDOWN, key, LPARAM(1 << 30 | 1), &mut state);
+        assert!(state.keyboard.auto_repeat_enabled);
+        assert_eq!(first, repeat);
+        assert_eq!(
+            first,
+            vec![Event::Key(KeyEvent {
+                key_state: KeyState::Press,
+                key_code: VK_A.0 as u32,
+            })]
+        );
+
+        let release: Vec<Event> = translate(WM_KEYUP, key, LPARAM(3 << 30 | 1), &mut state);
+        assert!(!state.keyboard.auto_repeat_enabled);
+        assert_eq!(
+            release,
+            vec![Event::Key(KeyEvent {
+                key_state: KeyState::Release,
+                key_code: VK_A.0 as u32,
+            })]
+        );
+    }
+
+    #[test]
+    fn key_codes_out_of_range_are_ignored() {
+        let mut state: WindowState = WindowState::new(800, 600);
+
+        for msg in [WM_KEYDOWN, WM_SYSKEYDOWN, WM_KEYUP, WM_SYSKEYUP] {
+            let events: Vec<Event> = translate(msg, WPARAM(KEY_CODE_COUNT), LPARAM(1), &mut state);
+            assert!(events.is_empty());
+            let events: Vec<Event> = translate(msg, WPARAM(300), LPARAM(1), &mut state);
+            assert!(events.is_empty());
+        }
+        assert!(state.keyboard.key_queue.is_empty());
+
+        // The last key code is still a key
+        let events: Vec<Event> = translate(
+            WM_KEYDOWN,
+            WPARAM(KEY_CODE_COUNT - 1),
+            LPARAM(1),
+            &mut state,
+        );
+        assert_eq!(events.len(), 1);
+        assert!(state.keyboard.key_is_pressed((KEY_CODE_COUNT - 1) as u16));
+    }
+
+    #[test]
+    fn child_windows_keep_their_client_origin() {
+        let mut state: WindowState = WindowState::new(200, 100);
+        state.style = WS_CHILD;
+        state.client_origin = ScreenPoint { x: 500, y: 400 };
+
+        // Relative to the parent, so it says nothing about the screen position
+        let events: Vec<Event> = translate(WM_MOVE, WPARAM(0), points(20, 10), &mut state);
+        assert_eq!(
+            events,
+            vec![Event::Window(WindowEvent::Moved { x: 20, y: 10 })]
+        );
+        assert_eq!(state.client_origin, ScreenPoint { x: 500, y: 400 });
+
+        let events: Vec<Event> =
+            translate(WM_MOUSEWHEEL, wheel(120, 0), points(510, 420), &mut state);
+        assert_eq!(
+            events,
+            vec![Event::Wheel {
+                pos: ClientPoint { x: 10, y: 20 },
+                delta: 120,
+            }]
+        );
+    }
+
+    #[test]
+    fn buttons_take_the_position_of_the_message() {
+        let mut state: WindowState = WindowState::new(800, 600);
+
+        let buttons: [(u32, MouseState, i16); 6] = [
+            (WM_LBUTTONDOWN, MouseState::LPress, 10),
+            (WM_LBUTTONUP, MouseState::LRelease, 20),
+            (WM_RBUTTONDOWN, MouseState::RPress, 30),
+            (WM_RBUTTONUP, MouseState::RRelease, 40),
+            (WM_MBUTTONDOWN, MouseState::WheelPress, 50),
+            (WM_MBUTTONUP, MouseState::WHeelRelease, 60),
+        ];
+        for (msg, mouse_state, x) in buttons {
+            // No WM_MOUSEMOVE in between, like a click on a window that was just shown
+            let events: Vec<Event> = translate(msg, WPARAM(0), points(x, 5), &mut state);
+            let pos: ClientPoint = ClientPoint { x: x as i32, y: 5 };
+            match events.as_slice() {
+                [Event::Mouse(event)] => {
+                    assert_eq!(event.mouse_state, mouse_state);
+                    assert_eq!(event.pos, pos);
+                }
+                _ => panic!("expected one mouse event, got {:?}", events),
+            }
+            assert_eq!(state.mouse.pos, pos);
+        }
+    }
+}
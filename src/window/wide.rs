@@ -0,0 +1,111 @@
+use windows::core::PCWSTR;
+
+/// A string which can't be passed to Win32 because it contains a NUL character.
+/// Win32 would silently cut the string at that position.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct NulError {
+    /// The position of the NUL character in UTF-16 code units.
+    pub position: usize,
+}
+
+impl std::fmt::Display for NulError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Found a NUL character at position {}", self.position)
+    }
+}
+
+impl std::error::Error for NulError {}
+
+/**
+    An owned, NUL-terminated UTF-16 string for the W functions of Win32.<br>
+    Rust strings are UTF-8 and not NUL-terminated, so `PCWSTR::from_raw(my_string.as_ptr())` points to the wrong
+    kind of data and will read past the end. This type owns the converted buffer, so the pointer from
+    [WideCString::as_pcwstr()] stays valid for as long as the WideCString lives. Keep it alive until the Win32
+    call that uses the pointer returns, or longer if Win32 keeps the pointer (like the class name of a window class).
+*/
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct WideCString {
+    /// The UTF-16 code units including the NUL terminator.
+    buffer: Vec<u16>,
+}
+
+impl WideCString {
+    /// Convert a UTF-8 string. Fails if the string contains a NUL character.
+    pub fn new(text: &str) -> Result<WideCString, NulError> {
+        let mut buffer: Vec<u16> = text.encode_utf16().collect();
+        if let Some(position) = buffer.iter().position(|unit| *unit == 0) {
+            return Err(NulError { position });
+        }
+        buffer.push(0);
+        return Ok(WideCString { buffer });
+    }
+
+    /// Convert a UTF-8 string and cut it off at the first NUL character, like Win32 would.
+    pub fn new_truncated(text: &str) -> WideCString {
+        let mut buffer: Vec<u16> = text.encode_utf16().take_while(|unit| *unit != 0).collect();
+        buffer.push(0);
+        return WideCString { buffer };
+    }
+
+    pub fn as_pcwstr(&self) -> PCWSTR {
+        return PCWSTR::from_raw(self.buffer.as_ptr());
+    }
+
+    /// The UTF-16 code units without the NUL terminator.
+    pub fn as_slice(&self) -> &[u16] {
+        return &self.buffer[..self.buffer.len() - 1];
+    }
+
+    /// The UTF-16 code units including the NUL terminator.
+    pub fn as_slice_with_nul(&self) -> &[u16] {
+        return &self.buffer;
+    }
+
+    /// Convert back to a Rust string. Unpaired surrogates are replaced with U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        return String::from_utf16_lossy(self.as_slice());
+    }
+}
+
+/// Convert a NUL-terminated (or full) UTF-16 buffer that Win32 filled in to a Rust string.
+pub fn from_wide_lossy(buffer: &[u16]) -> String {
+    let length: usize = buffer
+        .iter()
+        .position(|unit| *unit == 0)
+        .unwrap_or(buffer.len());
+    return String::from_utf16_lossy(&buffer[..length]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for text in ["", "Example App", "Überprüfung 日本", "🦀 crab"] {
+            let wide: WideCString = WideCString::new(text).unwrap();
+            assert_eq!(wide.to_string_lossy(), text);
+            assert_eq!(wide.as_slice_with_nul().last(), Some(&0));
+            assert_eq!(from_wide_lossy(wide.as_slice_with_nul()), text);
+        }
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        let wide: WideCString = WideCString::new("日🦀").unwrap();
+        // One unit for the kanji and a surrogate pair for the crab
+        assert_eq!(wide.as_slice(), &[0x65E5, 0xD83E, 0xDD80]);
+        assert_eq!(from_wide_lossy(&[0xD83E, 0x0041, 0]), "\u{FFFD}A");
+    }
+
+    #[test]
+    fn nul_characters() {
+        // The position is in UTF-16 code units, the crab takes two
+        assert_eq!(WideCString::new("🦀\0Ü"), Err(NulError { position: 2 }));
+        assert_eq!(
+            WideCString::new_truncated("日本\0rest").to_string_lossy(),
+            "日本"
+        );
+        assert_eq!(from_wide_lossy(&[0x41, 0, 0x42]), "A");
+    }
+}